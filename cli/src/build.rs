@@ -0,0 +1,283 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use reflexo_typst::config::{entry::EntryOpts, CompileOpts, EntryConfig, WorkspaceConfig};
+use reflexo_typst::path::PathClean;
+use reflexo_typst::task::Pages;
+use reflexo_typst::{DiagnosticsTask, EntryReader, TaskInputs, TypstSystemUniverse};
+use typst::foundations::{Dict, IntoValue};
+use typst::utils::LazyHash;
+
+use crate::export::ReflexoTaskBuilder;
+use crate::font::fonts;
+use crate::utils::{self, current_dir, UnwrapOrExit};
+use crate::{BuildArgs, CompileArgs, CompileOnceArgs, ExportArgs};
+
+/// Loads a project manifest. Manifests ending with `.json` are parsed as
+/// JSON, others as TOML.
+pub fn load_manifest(path: &Path) -> Result<WorkspaceConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read manifest {}: {err}", path.display()))?;
+
+    parse_manifest(path, &content)
+}
+
+/// Parses the content of a project manifest at the path.
+fn parse_manifest(path: &Path, content: &str) -> Result<WorkspaceConfig, String> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(content)
+            .map_err(|err| format!("failed to parse manifest {}: {err}", path.display())),
+        _ => toml::from_str(content)
+            .map_err(|err| format!("failed to parse manifest {}: {err}", path.display())),
+    }
+}
+
+/// Resolves a path in the manifest against the directory containing the
+/// manifest. Absolute paths are kept as is.
+fn resolve_path(base_dir: &Path, path: &str) -> PathBuf {
+    base_dir.join(path).clean()
+}
+
+/// Parses pages separated by commas, e.g. `1,3-5,8-`.
+fn parse_pages(pages: &str) -> Result<Vec<Pages>, String> {
+    pages
+        .split(',')
+        .map(|page| page.trim().parse::<Pages>().map_err(|err| err.to_string()))
+        .collect()
+}
+
+/// Builds all entries listed in a project manifest, exiting with failure if
+/// any of them fails to compile or export.
+pub fn build(args: BuildArgs) -> ! {
+    let manifest_path = if args.manifest.is_absolute() {
+        args.manifest.clean()
+    } else {
+        current_dir().join(&args.manifest).clean()
+    };
+
+    let config = load_manifest(&manifest_path).unwrap_or_else(|err| {
+        clap::Error::raw(clap::error::ErrorKind::InvalidValue, format!("{err}\n")).exit()
+    });
+
+    let base_dir = manifest_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(current_dir);
+    let resolve = |path: &str| resolve_path(&base_dir, path);
+
+    let workspace_dir = resolve(&config.workspace);
+
+    let mut font_paths = args.font.paths.clone();
+    font_paths.extend(config.font_paths.iter().map(|path| resolve(path)));
+
+    let mut verse = TypstSystemUniverse::new(CompileOpts {
+        entry: EntryOpts::new_workspace(workspace_dir.clone()),
+        font_paths,
+        no_system_fonts: args.font.ignore_system_fonts,
+        with_embedded_fonts: fonts().map(Cow::Borrowed).collect(),
+        ..CompileOpts::default()
+    })
+    .unwrap_or_exit();
+    if let Some(timestamp) = args.creation_timestamp {
+        verse.increment_revision(|this| this.set_creation_timestamp(Some(timestamp)));
+    }
+
+    let mut all_ok = true;
+    for entry in config.all_entries() {
+        if let Err(err) = build_entry(&verse, &args, &entry, &workspace_dir, &resolve) {
+            eprintln!("{}: {err}", entry.entry);
+            all_ok = false;
+        }
+    }
+
+    utils::logical_exit(all_ok)
+}
+
+fn build_entry(
+    verse: &TypstSystemUniverse,
+    args: &BuildArgs,
+    entry: &EntryConfig,
+    workspace_dir: &Path,
+    resolve: &impl Fn(&str) -> PathBuf,
+) -> Result<(), String> {
+    let entry_path = resolve(&entry.entry);
+    if !entry_path.starts_with(workspace_dir) {
+        return Err(format!(
+            "entry file path must be in workspace directory: {}",
+            workspace_dir.display()
+        ));
+    }
+
+    let pages = entry.pages.as_deref().map(parse_pages).transpose()?;
+
+    let compile_args = CompileArgs {
+        compile: CompileOnceArgs {
+            workspace: workspace_dir.to_string_lossy().to_string(),
+            entry: entry_path.to_string_lossy().to_string(),
+            output: entry
+                .output
+                .as_deref()
+                .map(|output| resolve(output).to_string_lossy().to_string())
+                .unwrap_or_default(),
+            ..Default::default()
+        },
+        export: ExportArgs {
+            creation_timestamp: args.creation_timestamp,
+            pages,
+            ppi: entry.ppi,
+            ..Default::default()
+        },
+        format: entry.formats.clone(),
+        diagnostic_format: args.diagnostic_format,
+        ..Default::default()
+    };
+
+    let mut tb = ReflexoTaskBuilder::new();
+    tb.args(&compile_args, Some(&entry_path));
+    tb.print_compile_status(true);
+    let exporter = tb.build();
+
+    let entry_state = match verse
+        .entry_state()
+        .try_select_path_in_workspace(&entry_path)
+    {
+        Ok(Some(entry_state)) => entry_state,
+        Ok(None) => return Err("failed to select entry file".to_owned()),
+        Err(err) => return Err(format!("failed to select entry file: {err}")),
+    };
+
+    let inputs = (!entry.inputs.is_empty()).then(|| {
+        let inputs: Dict = entry
+            .inputs
+            .iter()
+            .map(|(k, v)| (k.as_str().into(), v.as_str().into_value()))
+            .collect();
+        Arc::new(LazyHash::new(inputs))
+    });
+
+    let graph = verse.computation_with(TaskInputs {
+        entry: Some(entry_state),
+        inputs,
+    });
+
+    (exporter)(&graph).map_err(|err| format!("export failed: {err}"))?;
+
+    match graph.compute::<DiagnosticsTask>() {
+        Ok(diag) if diag.error_cnt() > 0 => Err(format!("{} compile error(s)", diag.error_cnt())),
+        Err(err) => Err(format!("diagnostics failed: {err}")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML_MANIFEST: &str = r#"
+workspace = "."
+files = ["main.typ"]
+formats = ["pdf"]
+
+[[entries]]
+entry = "docs/guide.typ"
+formats = ["svg", "png"]
+pages = "1,3-"
+ppi = 72.0
+inputs = { theme = "dark" }
+
+[[entries]]
+entry = "docs/slides.typ"
+output = "dist/slides"
+"#;
+
+    const JSON_MANIFEST: &str = r#"{
+  "workspace": "..",
+  "fontPaths": ["fonts"],
+  "entries": [{ "entry": "a.typ", "formats": ["html"] }]
+}"#;
+
+    #[test]
+    fn test_parse_toml_manifest() {
+        let config = parse_manifest(Path::new("typst-ts.toml"), TOML_MANIFEST).unwrap();
+        assert_eq!(config.workspace, ".");
+
+        let entries = config.all_entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].entry, "main.typ");
+        assert_eq!(entries[1].entry, "docs/guide.typ");
+        assert_eq!(entries[1].pages.as_deref(), Some("1,3-"));
+        assert_eq!(entries[1].ppi, Some(72.0));
+        assert_eq!(entries[1].inputs["theme"], "dark");
+        assert_eq!(entries[2].output.as_deref(), Some("dist/slides"));
+    }
+
+    #[test]
+    fn test_parse_json_manifest() {
+        let config = parse_manifest(Path::new("typst-ts.json"), JSON_MANIFEST).unwrap();
+        assert_eq!(config.workspace, "..");
+        assert_eq!(config.font_paths, ["fonts"]);
+
+        let entries = config.all_entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].entry, "a.typ");
+        assert_eq!(entries[0].formats, ["html"]);
+        assert!(entries[0].inputs.is_empty());
+
+        // a json manifest is not parsed as toml, and vice versa
+        assert!(parse_manifest(Path::new("typst-ts.toml"), JSON_MANIFEST).is_err());
+        assert!(parse_manifest(Path::new("typst-ts.json"), TOML_MANIFEST).is_err());
+    }
+
+    #[test]
+    fn test_default_formats() {
+        let config = parse_manifest(Path::new("typst-ts.toml"), TOML_MANIFEST).unwrap();
+        let formats = config
+            .all_entries()
+            .map(|entry| entry.formats)
+            .collect::<Vec<_>>();
+        assert_eq!(formats, [vec!["pdf"], vec!["svg", "png"], vec!["pdf"]]);
+    }
+
+    #[test]
+    fn test_load_manifest() {
+        let dir = std::env::temp_dir().join(format!("typst-ts-build-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("typst-ts.json");
+        std::fs::write(&path, JSON_MANIFEST).unwrap();
+
+        let config = load_manifest(&path);
+        let missing = load_manifest(&dir.join("missing.toml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(config.unwrap().entries.len(), 1);
+        assert!(missing.unwrap_err().starts_with("failed to read manifest"));
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let base = Path::new("/project/config");
+        assert_eq!(
+            resolve_path(base, "main.typ"),
+            Path::new("/project/config/main.typ")
+        );
+        assert_eq!(
+            resolve_path(base, "../fonts/./a"),
+            Path::new("/project/fonts/a")
+        );
+        assert_eq!(resolve_path(base, "."), Path::new("/project/config"));
+        assert_eq!(
+            resolve_path(base, "/abs/main.typ"),
+            Path::new("/abs/main.typ")
+        );
+    }
+
+    #[test]
+    fn test_parse_pages() {
+        let pages = parse_pages("1, 3-5,8-").unwrap();
+        assert_eq!(pages.len(), 3);
+        assert!(parse_pages("").is_err());
+        assert!(parse_pages("0").is_err());
+        assert!(parse_pages("a-b").is_err());
+    }
+}
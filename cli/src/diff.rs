@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use reflexo_typst::error::prelude::*;
+use reflexo_typst::path::unix_slash;
+use reflexo_typst::vector::ir::MultiVecDocument;
+use reflexo_typst::vector::stream::BytesModuleStream;
+use reflexo_vec2bbox::diff::{diff_documents, DocumentDiff, PageStatus};
+
+use crate::utils::{logical_exit, UnwrapOrExit};
+use crate::DiffArgs;
+
+/// Execute a diff command. The process exits with a failure status if the
+/// artifacts differ, like `diff(1)`.
+pub fn diff(args: DiffArgs) -> ! {
+    let old = load_artifact(&args.old).unwrap_or_exit();
+    let new = load_artifact(&args.new).unwrap_or_exit();
+    let diff = diff_documents(&old, &new);
+
+    match args.output_format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&diff).unwrap_or_exit()),
+        _ => print_summary(&diff),
+    }
+
+    if let Some(dir) = &args.overlay {
+        std::fs::create_dir_all(dir).unwrap_or_exit();
+        for page in diff.changed_pages() {
+            let path = dir.join(format!("page-{}.diff.svg", page.page));
+            std::fs::write(&path, page.overlay_svg()).unwrap_or_exit();
+            eprintln!("overlay is written to {}", unix_slash(&path));
+        }
+    }
+
+    logical_exit(diff.is_empty())
+}
+
+fn load_artifact(path: &Path) -> Result<MultiVecDocument> {
+    let data = std::fs::read(path).context_ut("failed to read vector artifact")?;
+    let module = BytesModuleStream::from_slice(&data)
+        .try_checkout_owned()
+        .map_err(
+            |err| error_once!("corrupted vector artifact", path: unix_slash(path), err: err),
+        )?;
+
+    let mut doc = MultiVecDocument::default();
+    doc.merge_delta(&module);
+    Ok(doc)
+}
+
+fn print_summary(diff: &DocumentDiff) {
+    if diff.is_empty() {
+        println!("no differences");
+        return;
+    }
+
+    for page in diff.changed_pages() {
+        let status = match page.status {
+            PageStatus::Unchanged => continue,
+            PageStatus::Changed => "changed",
+            PageStatus::Added => "added",
+            PageStatus::Removed => "removed",
+        };
+        println!(
+            "page {}: {status}, {} added, {} removed",
+            page.page,
+            page.added.len(),
+            page.removed.len()
+        );
+
+        let items = page.removed.iter().map(|item| ('-', item));
+        let items = items.chain(page.added.iter().map(|item| ('+', item)));
+        for (sign, item) in items {
+            let [x, y, w, h] = item.rect;
+            print!("  {sign} {:?} at ({x:.1}, {y:.1}) {w:.1}x{h:.1}", item.kind);
+            match &item.text {
+                Some(text) => println!(" {text:?}"),
+                None => println!(),
+            }
+        }
+    }
+}
@@ -3,15 +3,20 @@ use std::sync::Arc;
 
 use reflexo_typst::error::prelude::*;
 use reflexo_typst::program_meta::REPORT_BUG_MESSAGE;
-use reflexo_typst::svg::DefaultExportFeature;
-use reflexo_typst::task::{ExportHtmlTask, ExportPdfTask, ExportTextTask};
+use reflexo_typst::svg::{
+    DefaultExportFeature, SvgExportFeature, SvgExportOptions, SvgMinifyOptions,
+};
+use reflexo_typst::task::{
+    ExportHtmlTask, ExportPdfTask, ExportSvgTask, ExportTask, ExportTextTask, ExportTransform,
+};
 use reflexo_typst::{
     AstExport, BundleCompilationTask, Bytes, CompilationTask, CompileReport, ConfigTask,
     DiagnosticHandler, DiagnosticsTask, DynSvgModuleExport, DynSystemComputation, ExportAstTask,
-    ExportComputation, ExportDynSvgModuleTask, ExportWebSvgHtmlTask, ExportWebSvgModuleTask,
-    ExportWebSvgTask, FlagTask, HtmlCompilationTask, HtmlExport, OptionDocumentTask,
-    PagedCompilationTask, PdfExport, SystemCompilerFeat, TakeAs, TextExport, WebSvgExport,
-    WebSvgHtmlExport, WebSvgModuleExport, WorldComputable, WorldComputeGraph,
+    ExportComputation, ExportDynSvgModuleTask, ExportPngPagesTask, ExportWebSvgHtmlTask,
+    ExportWebSvgModuleTask, ExportWebSvgPagesTask, ExportWebSvgTask, FlagTask, HtmlCompilationTask,
+    HtmlExport, OptionDocumentTask, PagedCompilationTask, PagedTextExport, PdfExport,
+    PngPagesExport, SystemCompilerFeat, TakeAs, TypstPagedDocument, WebSvgExport, WebSvgHtmlExport,
+    WebSvgModuleExport, WebSvgPagesExport, WorldComputable, WorldComputeGraph,
 };
 use typst::{foundations::Output, model::Document, World};
 
@@ -22,9 +27,13 @@ use crate::{utils::current_dir, CompileArgs};
 pub static AVAILABLE_FORMATS: &[(/* format name */ &str, /* feature hint */ &str)] = &[
     ("ast", REPORT_BUG_MESSAGE),
     ("nothing", REPORT_BUG_MESSAGE),
+    ("html", "html"),
+    ("html_site", "html"),
     ("pdf", "pdf"),
+    ("png", "png"),
     ("svg", "svg"),
     ("svg_html", "svg"),
+    ("svg_pages", "svg"),
     ("sir", "svg"),
     ("vector", "svg"),
     ("text", "text"),
@@ -61,9 +70,13 @@ fn exit_by_unknown_format(f: &str) -> ! {
 pub enum ReflexoTask {
     Ast(ExportAstTask),
     Pdf(ExportPdfTask),
+    Png(ExportPngPagesTask),
     Html(ExportHtmlTask),
+    #[cfg(feature = "html")]
+    HtmlSite(reflexo_typst::ExportHtmlSiteTask),
     WebSvg(ExportWebSvgTask),
     WebSvgHtml(ExportWebSvgHtmlTask),
+    WebSvgPages(ExportWebSvgPagesTask),
     WebSvgModule(ExportWebSvgModuleTask),
     DynSvgModule(ExportDynSvgModuleTask),
     Text(ExportTextTask),
@@ -124,29 +137,84 @@ impl ReflexoTaskBuilder {
                 #[cfg(feature = "pdf")]
                 "pdf" => {
                     self.add_pdf(ExportPdfTask {
+                        export: export_task(args),
                         creation_timestamp: args.export.creation_timestamp,
                         ..ExportPdfTask::default()
                     });
                 }
+                #[cfg(feature = "png")]
+                "png" => {
+                    let mut config = ExportPngPagesTask {
+                        export: export_task(args),
+                        ..ExportPngPagesTask::default()
+                    };
+                    if let Some(ppi) = args.export.ppi {
+                        config.ppi = ppi;
+                    }
+                    self.add_png(config);
+                }
                 #[cfg(feature = "html")]
                 "html" => {
                     self.add_html(ExportHtmlTask::default());
                 }
+                #[cfg(feature = "html")]
+                "html_site" => {
+                    self.add_html_site(reflexo_typst::ExportHtmlSiteTask {
+                        split: html_site_split(args),
+                        ..Default::default()
+                    });
+                }
                 #[cfg(feature = "svg")]
                 "svg" => {
-                    self.add_web_svg(ExportWebSvgTask::default());
+                    self.add_web_svg(ExportWebSvgTask {
+                        base: ExportSvgTask {
+                            export: export_task(args),
+                        },
+                        options: svg_options(args, SvgExportOptions::of::<SvgExportFeature>()),
+                    });
                 }
                 #[cfg(feature = "svg")]
                 "svg_html" => {
-                    self.add_web_svg_html(ExportWebSvgHtmlTask::default());
+                    self.add_web_svg_html(ExportWebSvgHtmlTask {
+                        base: ExportSvgTask {
+                            export: export_task(args),
+                        },
+                        options: svg_options(args, SvgExportOptions::of::<DefaultExportFeature>()),
+                    });
+                }
+                #[cfg(feature = "svg")]
+                "svg_pages" => {
+                    let glyph_sprite = args.export.svg_glyph_sprite.then(|| {
+                        let sprite = self.output_path.with_extension("glyphs.svg");
+                        let Some(name) = sprite.file_name() else {
+                            clap::Error::raw(
+                                clap::error::ErrorKind::InvalidValue,
+                                format!(
+                                    "cannot name the glyph sprite of output: {output}\n",
+                                    output = self.output_path.display()
+                                ),
+                            )
+                            .exit()
+                        };
+                        name.to_string_lossy().into_owned()
+                    });
+                    self.add_web_svg_pages(ExportWebSvgPagesTask {
+                        export: export_task(args),
+                        options: svg_options(args, SvgExportOptions::of::<SvgExportFeature>()),
+                        glyph_sprite,
+                    });
                 }
                 #[cfg(feature = "svg")]
                 "sir" | "vector" => {
-                    self.add_web_svg_module(ExportWebSvgModuleTask::default());
+                    self.add_web_svg_module(ExportWebSvgModuleTask {
+                        export: export_task(args),
+                    });
                 }
                 #[cfg(feature = "svg")]
                 "text" => {
-                    self.add_text(ExportTextTask::default());
+                    self.add_text(ExportTextTask {
+                        export: export_task(args),
+                    });
                 }
                 format => exit_by_unknown_format(format),
             }
@@ -170,11 +238,22 @@ impl ReflexoTaskBuilder {
         self
     }
 
+    pub fn add_png(&mut self, config: ExportPngPagesTask) -> &mut Self {
+        self.tasks.push(ReflexoTask::Png(config));
+        self
+    }
+
     pub fn add_html(&mut self, config: ExportHtmlTask) -> &mut Self {
         self.tasks.push(ReflexoTask::Html(config));
         self
     }
 
+    #[cfg(feature = "html")]
+    pub fn add_html_site(&mut self, config: reflexo_typst::ExportHtmlSiteTask) -> &mut Self {
+        self.tasks.push(ReflexoTask::HtmlSite(config));
+        self
+    }
+
     pub fn add_web_svg(&mut self, config: ExportWebSvgTask) -> &mut Self {
         self.tasks.push(ReflexoTask::WebSvg(config));
         self
@@ -185,6 +264,11 @@ impl ReflexoTaskBuilder {
         self
     }
 
+    pub fn add_web_svg_pages(&mut self, config: ExportWebSvgPagesTask) -> &mut Self {
+        self.tasks.push(ReflexoTask::WebSvgPages(config));
+        self
+    }
+
     pub fn add_web_svg_module(&mut self, config: ExportWebSvgModuleTask) -> &mut Self {
         self.tasks.push(ReflexoTask::WebSvgModule(config));
         self
@@ -214,6 +298,69 @@ impl ReflexoTaskBuilder {
     }
 }
 
+/// Creates the shared export configuration, e.g. the selected pages, from the
+/// arguments.
+fn export_task(args: &CompileArgs) -> ExportTask {
+    let transform = match &args.export.pages {
+        Some(ranges) => vec![ExportTransform::Pages {
+            ranges: ranges.clone(),
+        }],
+        None => vec![],
+    };
+
+    ExportTask {
+        transform,
+        ..ExportTask::default()
+    }
+}
+
+/// Gets the output path of a page, e.g. `main.2.png` for the second page of
+/// `main.typ`.
+fn page_output_path(out: &Path, index: usize, ext: &str) -> PathBuf {
+    out.with_extension(format!("{}.{ext}", index + 1))
+}
+
+/// Gets where to split `html_site` outputs into pages from the arguments.
+#[cfg(feature = "html")]
+fn html_site_split(args: &CompileArgs) -> reflexo_typst::HtmlSplit {
+    use crate::HtmlSiteSplit;
+    use reflexo_typst::HtmlSplit;
+
+    match &args.export.html_site_split {
+        Some(HtmlSiteSplit::Heading(level)) => HtmlSplit::Heading(*level),
+        Some(HtmlSiteSplit::Label(label)) => HtmlSplit::Label(label.as_str().into()),
+        None => HtmlSplit::default(),
+    }
+}
+
+/// Overrides the svg options of a format by the arguments. Returns `None` if
+/// no svg option is given, so that the format uses its own options.
+fn svg_options(args: &CompileArgs, base: SvgExportOptions) -> Option<SvgExportOptions> {
+    let args = &args.export;
+    let given = args.svg_no_text
+        || args.svg_no_builtin_css
+        || args.svg_responsive_js
+        || args.svg_no_responsive_js
+        || args.svg_minify
+        || args.svg_precision.is_some();
+    if !given {
+        return None;
+    }
+
+    let mut options = base;
+    options.should_render_text_element &= !args.svg_no_text;
+    options.with_builtin_css &= !args.svg_no_builtin_css;
+    options.with_responsive_js =
+        (options.with_responsive_js || args.svg_responsive_js) && !args.svg_no_responsive_js;
+    if args.svg_minify {
+        options.minify = SvgMinifyOptions::full();
+    }
+    if let Some(precision) = args.svg_precision {
+        options.minify.precision = Some(precision);
+    }
+    Some(options)
+}
+
 /// With the given arguments, prepare exporters for the compilation.
 fn prepare_exporters_impl(
     diag_handler: DiagnosticHandler,
@@ -222,20 +369,28 @@ fn prepare_exporters_impl(
 ) -> DynSystemComputation {
     type EF = DefaultExportFeature;
 
-    fn export_to_path(result: Result<Option<Bytes>>, output_path: PathBuf) {
-        let result = match result {
-            Ok(Some(bytes)) => bytes,
-            Ok(None) => return,
-            Err(err) => {
-                eprintln!("export failed: {err}");
-                return;
-            }
+    fn export_to_path(result: Result<Option<Bytes>>, output_path: PathBuf) -> Result<()> {
+        let Some(bytes) = result? else {
+            return Ok(());
         };
 
-        let err = std::fs::write(output_path, result.as_slice());
-        if let Err(err) = err {
-            eprintln!("export failed: {err}");
+        std::fs::write(&output_path, bytes.as_slice()).map_err(
+            |err| error_once!("failed to write output", path: output_path.display(), err: err),
+        )
+    }
+
+    #[cfg(feature = "html")]
+    fn export_site(site: &reflexo_typst::HtmlSite, output_dir: PathBuf) -> Result<()> {
+        std::fs::create_dir_all(&output_dir).map_err(
+            |err| error_once!("failed to create output directory", path: output_dir.display(), err: err),
+        )?;
+
+        for page in &site.pages {
+            let data = Ok(Some(Bytes::from_string(page.html.clone())));
+            export_to_path(data, output_dir.join(page.path().as_str()))?;
         }
+        let manifest = site.manifest_json().map(|m| Some(Bytes::from_string(m)));
+        export_to_path(manifest, output_dir.join("manifest.json"))
     }
 
     fn compile_it<D: Document + Output + Send + Sync + 'static>(
@@ -278,6 +433,8 @@ fn prepare_exporters_impl(
 
         diag_handler.status(&CompileReport::Stage(main, "compiling", start));
 
+        // The exports go on after a failure, and the failures are reported together.
+        let mut failures = vec![];
         for task in tasks.iter() {
             use ReflexoTask::*;
             match task {
@@ -285,50 +442,110 @@ fn prepare_exporters_impl(
                 Ast(_config) => {
                     let output_path = out.with_extension("ast.ansi.text");
                     let result = AstExport::compute(graph);
-                    export_to_path(result, output_path);
+                    failures.extend(export_to_path(result, output_path).err());
                 }
                 #[cfg(feature = "pdf")]
                 Pdf(config) => {
                     let output_path = out.with_extension("pdf");
                     let result = export_bytes::<_, PdfExport>(graph, config);
-                    export_to_path(result, output_path);
+                    failures.extend(export_to_path(result, output_path).err());
+                }
+                #[cfg(feature = "png")]
+                Png(config) => {
+                    let doc = compile_it::<TypstPagedDocument>(graph);
+                    let result = doc.and_then(|doc| {
+                        let doc = doc.as_ref();
+                        doc.map(|doc| PngPagesExport::run(graph, doc, config))
+                            .transpose()
+                    });
+                    match result {
+                        Ok(Some(pages)) => {
+                            for page in pages {
+                                let path = page_output_path(&out, page.index, "png");
+                                failures.extend(export_to_path(Ok(Some(page.data)), path).err());
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(err) => failures.push(err),
+                    }
                 }
                 #[cfg(feature = "html")]
                 Html(config) => {
                     let output_path = out.with_extension("html");
                     let result = export_string::<_, HtmlExport>(graph, config);
-                    export_to_path(result, output_path);
+                    failures.extend(export_to_path(result, output_path).err());
+                }
+                #[cfg(feature = "html")]
+                HtmlSite(config) => {
+                    let doc = compile_it::<reflexo_typst::TypstHtmlDocument>(graph);
+                    let result = doc.and_then(|doc| {
+                        let doc = doc.as_ref();
+                        doc.map(|doc| reflexo_typst::HtmlSiteExport::run(graph, doc, config))
+                            .transpose()
+                    });
+                    match result {
+                        Ok(Some(site)) => {
+                            failures.extend(export_site(&site, out.with_extension("site")).err());
+                        }
+                        Ok(None) => {}
+                        Err(err) => failures.push(err),
+                    }
                 }
                 #[cfg(feature = "svg")]
                 WebSvg(config) => {
                     let output_path = out.with_extension("artifact.svg");
                     let result = export_string::<_, WebSvgExport<EF>>(graph, config);
-                    export_to_path(result, output_path);
+                    failures.extend(export_to_path(result, output_path).err());
                 }
                 #[cfg(feature = "svg")]
                 WebSvgHtml(config) => {
                     let output_path = out.with_extension("artifact.svg.html");
                     let result = export_string::<_, WebSvgHtmlExport<EF>>(graph, config);
-                    export_to_path(result, output_path);
+                    failures.extend(export_to_path(result, output_path).err());
+                }
+                #[cfg(feature = "svg")]
+                WebSvgPages(config) => {
+                    let doc = compile_it::<TypstPagedDocument>(graph);
+                    let result = doc.and_then(|doc| {
+                        let doc = doc.as_ref();
+                        doc.map(|doc| WebSvgPagesExport::run(graph, doc, config))
+                            .transpose()
+                    });
+                    match result {
+                        Ok(Some(output)) => {
+                            if let Some(glyphs) = output.glyphs {
+                                let glyphs = Ok(Some(Bytes::from_string(glyphs)));
+                                let path = out.with_extension("glyphs.svg");
+                                failures.extend(export_to_path(glyphs, path).err());
+                            }
+                            for page in output.pages {
+                                let path = page_output_path(&out, page.index, "svg");
+                                let data = Ok(Some(Bytes::from_string(page.data)));
+                                failures.extend(export_to_path(data, path).err());
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(err) => failures.push(err),
+                    }
                 }
                 #[cfg(feature = "svg")]
                 WebSvgModule(config) => {
                     let output_path = out.with_extension("artifact.sir.in");
                     let result = export_bytes::<_, WebSvgModuleExport<EF>>(graph, config);
-                    export_to_path(result, output_path);
+                    failures.extend(export_to_path(result, output_path).err());
                 }
                 #[cfg(feature = "svg")]
                 DynSvgModule(config) => {
                     let output_path = out.with_extension("multi.sir.in");
                     let result = DynSvgModuleExport::run(graph, config);
                     let result = result.map(|d| d.map(|d| Bytes::new(d.to_bytes())));
-                    export_to_path(result, output_path);
+                    failures.extend(export_to_path(result, output_path).err());
                 }
                 #[cfg(feature = "text")]
                 Text(config) => {
                     let output_path = out.with_extension("txt");
-                    let result = export_string::<_, TextExport>(graph, config);
-                    export_to_path(result, output_path);
+                    let result = export_string::<_, PagedTextExport>(graph, config);
+                    failures.extend(export_to_path(result, output_path).err());
                 }
             }
         }
@@ -354,7 +571,14 @@ fn prepare_exporters_impl(
         // todo: export diagnostics.
         diag_handler.report(&graph.snap.world, diag.diagnostics());
 
-        Ok(())
+        match failures.len() {
+            0 => Ok(()),
+            1 => Err(failures.remove(0)),
+            _ => {
+                let errors = failures.iter().map(ToString::to_string).collect::<Vec<_>>();
+                Err(error_once!("multiple exports failed", errors: errors.join("; ")))
+            }
+        }
     })
 }
 
@@ -364,3 +588,157 @@ pub fn prepare_exporters(args: &CompileArgs, entry_file: Option<&Path>) -> DynSy
     tb.args(args, entry_file);
     tb.build()
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use reflexo_typst::exported_page_indices;
+
+    use super::*;
+
+    fn builder(argv: &[&str]) -> ReflexoTaskBuilder {
+        let prefix = ["compile", "--entry", "main.typ"];
+        let args = CompileArgs::parse_from(prefix.iter().chain(argv));
+        let mut tb = ReflexoTaskBuilder::new();
+        tb.args(&args, Some(Path::new("dist/main.typ")));
+        tb
+    }
+
+    #[test]
+    fn test_page_output_path() {
+        let out = Path::new("dist/main.typ");
+        assert_eq!(
+            page_output_path(out, 0, "png"),
+            Path::new("dist/main.1.png")
+        );
+        assert_eq!(
+            page_output_path(out, 9, "png"),
+            Path::new("dist/main.10.png")
+        );
+        assert_eq!(
+            page_output_path(out, 1, "svg"),
+            Path::new("dist/main.2.svg")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "png")]
+    fn test_png_args() {
+        let tb = builder(&["--format", "png", "--ppi", "300", "--pages", "2,4-"]);
+        let [ReflexoTask::Png(config)] = tb.tasks.as_slice() else {
+            panic!("expected a png task");
+        };
+        assert_eq!(config.ppi, 300.);
+        assert_eq!(exported_page_indices(&config.export, 5), [1, 3, 4]);
+
+        let tb = builder(&["--format", "png"]);
+        let [ReflexoTask::Png(config)] = tb.tasks.as_slice() else {
+            panic!("expected a png task");
+        };
+        assert_eq!(config.ppi, ExportPngPagesTask::default().ppi);
+        assert_eq!(exported_page_indices(&config.export, 2), [0, 1]);
+    }
+
+    #[test]
+    #[cfg(feature = "svg")]
+    fn test_svg_options_args() {
+        let svg = |argv: &[&str]| {
+            let tb = builder(&[&["--format", "svg"][..], argv].concat());
+            let [ReflexoTask::WebSvg(config)] = tb.tasks.as_slice() else {
+                panic!("expected an svg task");
+            };
+            config.options
+        };
+        let svg_html = |argv: &[&str]| {
+            let tb = builder(&[&["--format", "svg_html"][..], argv].concat());
+            let [ReflexoTask::WebSvgHtml(config)] = tb.tasks.as_slice() else {
+                panic!("expected an svg html task");
+            };
+            config.options
+        };
+
+        // The formats keep their own options if no svg option is given.
+        assert_eq!(svg(&[]), None);
+        assert_eq!(svg_html(&[]), None);
+
+        let plain = SvgExportOptions::of::<SvgExportFeature>();
+        let options = svg(&["--svg-no-text", "--svg-no-builtin-css"]).unwrap();
+        assert_eq!(
+            options,
+            SvgExportOptions {
+                should_render_text_element: false,
+                with_builtin_css: false,
+                ..plain
+            }
+        );
+        let options = svg(&["--svg-responsive-js"]).unwrap();
+        assert_eq!(
+            options,
+            SvgExportOptions {
+                with_responsive_js: true,
+                ..plain
+            }
+        );
+
+        let full = SvgExportOptions::of::<DefaultExportFeature>();
+        assert!(full.with_responsive_js);
+        let options = svg_html(&["--svg-no-responsive-js"]).unwrap();
+        assert_eq!(
+            options,
+            SvgExportOptions {
+                with_responsive_js: false,
+                ..full
+            }
+        );
+        let options = svg_html(&["--svg-no-text"]).unwrap();
+        assert_eq!(
+            options,
+            SvgExportOptions {
+                should_render_text_element: false,
+                ..full
+            }
+        );
+
+        let args = [
+            "compile",
+            "--entry",
+            "main.typ",
+            "--svg-responsive-js",
+            "--svg-no-responsive-js",
+        ];
+        let err = CompileArgs::try_parse_from(args).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    #[cfg(feature = "html")]
+    fn test_html_site_split_args() {
+        use reflexo_typst::HtmlSplit;
+
+        let split = |argv: &[&str]| {
+            let tb = builder(&[&["--format", "html_site"][..], argv].concat());
+            let [ReflexoTask::HtmlSite(config)] = tb.tasks.as_slice() else {
+                panic!("expected an html site task");
+            };
+            config.split.clone()
+        };
+        assert_eq!(split(&[]), HtmlSplit::Heading(1));
+        assert_eq!(split(&["--html-site-split", "2"]), HtmlSplit::Heading(2));
+        assert_eq!(
+            split(&["--html-site-split", "<chapter>"]),
+            HtmlSplit::Label("chapter".into())
+        );
+
+        for invalid in ["0", "chapter", "<chapter"] {
+            let args = [
+                "compile",
+                "--entry",
+                "main.typ",
+                "--html-site-split",
+                invalid,
+            ];
+            let err = CompileArgs::try_parse_from(args).unwrap_err();
+            assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+        }
+    }
+}
@@ -1,9 +1,12 @@
+pub mod build;
 pub mod compile;
+pub mod diff;
 pub mod export;
 pub mod font;
 #[cfg(feature = "gen-manual")]
 pub mod manual;
 pub mod query;
+pub mod query_repl;
 pub mod utils;
 pub mod version;
 
@@ -15,6 +18,7 @@ use std::{
 };
 
 use clap::{builder::ValueParser, ArgAction, Args, Command, Parser, Subcommand, ValueEnum};
+use reflexo_typst::task::Pages;
 use reflexo_typst::typst_shim::syntax::VirtualPathExt;
 use reflexo_typst::{
     build_info::VERSION, vfs::WorkspaceResolver, DiagnosticHandler, ImmutPath, TypstFileId,
@@ -56,9 +60,19 @@ pub enum Subcommands {
     #[clap(visible_alias = "c")]
     Compile(CompileArgs),
 
+    /// Compiles all entries listed in a project manifest
+    #[clap(visible_alias = "b")]
+    Build(BuildArgs),
+
     /// Processes an input file to extract provided metadata
     Query(QueryArgs),
 
+    /// Compiles an input file once and queries it interactively
+    QueryRepl(QueryReplArgs),
+
+    /// Compares two vector artifacts (`.sir` files) page by page
+    Diff(DiffArgs),
+
     /// Generates a shell completion script for CLI.
     Completion(CompletionArgs),
 
@@ -87,6 +101,9 @@ pub enum Subcommands {
 pub enum FontSubCommands {
     /// List all discovered fonts in system and custom font paths
     List(ListFontsArgs),
+
+    /// Measure fonts and generate a profile file for compiler
+    Measure(MeasureFontsArgs),
 }
 
 #[derive(Debug, Subcommand)]
@@ -273,6 +290,91 @@ pub struct ExportArgs {
         value_name = "UNIX_TIMESTAMP"
     )]
     pub creation_timestamp: Option<i64>,
+
+    /// Which pages to export. When unspecified, all pages are exported.
+    ///
+    /// Pages to export are separated by commas, and can be either simple page
+    /// numbers (e.g. '2,5' to export only pages 2 and 5) or page ranges (e.g.
+    /// '2,3-6,8-' to export page 2, pages 3 to 6 (inclusive), page 8 and any
+    /// pages after it).
+    ///
+    /// It applies to the paged formats, i.e. `pdf`, `png`, `svg`,
+    /// `svg_pages`, `svg_html`, `sir` (`vector`) and `text`.
+    #[clap(long = "pages", value_delimiter = ',')]
+    pub pages: Option<Vec<Pages>>,
+
+    /// The resolution of `png` output, in pixels per inch. Defaults to 144.
+    #[clap(long = "ppi")]
+    pub ppi: Option<f32>,
+
+    /// Does not render selectable text elements in `svg`, `svg_pages` and
+    /// `svg_html` outputs.
+    #[clap(long = "svg-no-text")]
+    pub svg_no_text: bool,
+
+    /// Does not include the builtin css in `svg`, `svg_pages` and `svg_html`
+    /// outputs.
+    #[clap(long = "svg-no-builtin-css")]
+    pub svg_no_builtin_css: bool,
+
+    /// Includes the js for interactive and responsive actions in `svg`
+    /// outputs. It is always included in `svg_html` outputs unless
+    /// `--svg-no-responsive-js` is given.
+    #[clap(long = "svg-responsive-js", conflicts_with = "svg_no_responsive_js")]
+    pub svg_responsive_js: bool,
+
+    /// Does not include the js for interactive and responsive actions in
+    /// `svg_html` outputs.
+    #[clap(long = "svg-no-responsive-js")]
+    pub svg_no_responsive_js: bool,
+
+    /// Minifies `svg`, `svg_pages` and `svg_html` outputs, e.g. compacts path
+    /// data, collapses groups, deduplicates paths and shortens ids.
+    #[clap(long = "svg-minify")]
+    pub svg_minify: bool,
+
+    /// The number of fraction digits kept in the coordinates of `svg`,
+    /// `svg_pages` and `svg_html` outputs. Defaults to 2 if `--svg-minify` is
+    /// given, and otherwise the coordinates are kept as is.
+    #[clap(long = "svg-precision", value_name = "DIGITS")]
+    pub svg_precision: Option<u8>,
+
+    /// Moves the glyph definitions of `svg_pages` outputs into a shared
+    /// sprite file, `<output>.glyphs.svg`, which is referenced by each page.
+    #[clap(long = "svg-glyph-sprite")]
+    pub svg_glyph_sprite: bool,
+
+    /// Where to split `html_site` outputs into pages, either a heading level,
+    /// e.g. `2`, or a label, e.g. `<chapter>`. Defaults to `1`.
+    ///
+    /// The pages are written into `<output>.site/` along with a navigation
+    /// manifest, `manifest.json`.
+    #[clap(
+        long = "html-site-split",
+        value_name = "LEVEL_OR_LABEL",
+        value_parser = ValueParser::new(parse_html_site_split),
+    )]
+    pub html_site_split: Option<HtmlSiteSplit>,
+}
+
+/// Where to split `html_site` outputs into pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmlSiteSplit {
+    /// Splits at the headings of the level or a higher level.
+    Heading(usize),
+    /// Splits at the elements carrying the label.
+    Label(String),
+}
+
+/// Parses a heading level, e.g. `2`, or a label, e.g. `<chapter>`.
+fn parse_html_site_split(raw: &str) -> Result<HtmlSiteSplit, String> {
+    if let Some(label) = raw.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+        return Ok(HtmlSiteSplit::Label(label.to_owned()));
+    }
+    match raw.parse() {
+        Ok(level) if level > 0 => Ok(HtmlSiteSplit::Heading(level)),
+        _ => Err("expected a heading level, e.g. `2`, or a label, e.g. `<chapter>`".to_owned()),
+    }
 }
 
 #[derive(Default, Debug, Clone, Parser)]
@@ -295,8 +397,8 @@ pub struct CompileArgs {
     #[clap(long)]
     pub dynamic_layout: bool,
 
-    /// Outputs format(s), possible values: `ast`, `pdf`, `svg`, and,
-    /// `svg_html`.
+    /// Outputs format(s), possible values: `ast`, `pdf`, `png`, `svg`,
+    /// `svg_pages`, `svg_html`, `html`, and, `html_site`.
     #[clap(long)]
     pub format: Vec<String>,
 
@@ -318,6 +420,52 @@ impl CompileArgs {
     }
 }
 
+/// Compiles all entries listed in a project manifest, e.g. `typst-ts.toml`.
+///
+/// Example manifest:
+/// ```toml
+/// workspace = "."
+/// fontPaths = ["fonts"]
+/// formats = ["pdf"]
+/// files = ["main.typ"]
+///
+/// [[entries]]
+/// entry = "slides.typ"
+/// formats = ["svg", "png"]
+/// pages = "1,3-5"
+/// ppi = 300
+/// output = "dist/slides"
+/// inputs = { theme = "dark" }
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct BuildArgs {
+    /// Path to the project manifest, in TOML or JSON format.
+    #[clap(long, short, default_value = "typst-ts.toml")]
+    pub manifest: PathBuf,
+
+    /// Shared arguments for font related commands.
+    #[clap(flatten)]
+    pub font: FontArgs,
+
+    /// The document's creation date formatted as a UNIX timestamp.
+    ///
+    /// For more information, see <https://reproducible-builds.org/specs/source-date-epoch/>.
+    #[clap(
+        long = "creation-timestamp",
+        env = "SOURCE_DATE_EPOCH",
+        value_name = "UNIX_TIMESTAMP"
+    )]
+    pub creation_timestamp: Option<i64>,
+
+    /// The format to emit diagnostics in
+    #[clap(
+        long,
+        default_value_t = DiagnosticFormat::Human,
+        value_parser = clap::value_parser!(DiagnosticFormat)
+    )]
+    pub diagnostic_format: DiagnosticFormat,
+}
+
 /// Processes an input file to extract provided metadata
 ///
 /// Examples:
@@ -328,6 +476,8 @@ impl CompileArgs {
 /// query --selector "heading.where(level: 1)"
 /// # query first element with selector "heading" which is of level 1
 /// query --selector "heading.where(level: 1)" --one
+/// # query headings with their pages and source ranges in YAML
+/// query --selector "heading" --with-location --output-format yaml
 /// ```
 #[derive(Debug, Clone, Parser)]
 pub struct QueryArgs {
@@ -346,14 +496,64 @@ pub struct QueryArgs {
     /// Expect and retrieve exactly one element
     #[clap(long = "one", default_value = "false")]
     pub one: bool,
+
+    /// Attach the page number, the position on the page and the source range
+    /// to each retrieved element
+    #[clap(long = "with-location", default_value = "false")]
+    pub with_location: bool,
+
+    /// The format to serialize the query result in
+    #[clap(long = "output-format", default_value = "json", value_parser = ["json", "yaml"])]
+    pub output_format: String,
 }
 
-/// TODO: Repl Doc
+/// Compiles an input file and queries it interactively. The input file is
+/// recompiled when the files it depends on change, and each query is evaluated
+/// against the latest document.
+///
+/// Each line is a selector optionally followed by modifiers:
+/// ```text
+/// >> heading.where(level: 1)
+/// >> heading --field body
+/// >> <my-label> --one
+/// ```
 #[derive(Debug, Clone, Parser)]
 pub struct QueryReplArgs {
     /// compile arguments before query.
     #[clap(flatten)]
     pub compile: CompileOnceArgs,
+
+    /// The format to emit diagnostics in
+    #[clap(
+        long,
+        default_value_t = DiagnosticFormat::Human,
+        value_parser = clap::value_parser!(DiagnosticFormat)
+    )]
+    pub diagnostic_format: DiagnosticFormat,
+}
+
+/// Compares two vector artifacts page by page and reports the items added or
+/// removed on each page.
+///
+/// ```shell
+/// diff old.artifact.sir new.artifact.sir --overlay diff/
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct DiffArgs {
+    /// Path to the old vector artifact
+    pub old: PathBuf,
+
+    /// Path to the new vector artifact
+    pub new: PathBuf,
+
+    /// The format to print the diff in
+    #[clap(long = "output-format", default_value = "human", value_parser = ["human", "json"])]
+    pub output_format: String,
+
+    /// Writes an svg overlay highlighting the changes of each changed page
+    /// into the directory
+    #[clap(long)]
+    pub overlay: Option<PathBuf>,
 }
 
 /// List all discovered fonts in system and custom font paths
@@ -375,7 +575,7 @@ pub struct MeasureFontsArgs {
     #[clap(flatten)]
     pub font: FontArgs,
 
-    /// Path to output profile file
+    /// Path to output profile file, in JSON format
     #[arg(long, required = true)]
     pub output: PathBuf,
 
@@ -449,13 +649,16 @@ pub enum DiagnosticFormat {
     #[default]
     Human,
     Short,
+    /// One JSON object per line.
+    Json,
 }
 
-impl From<DiagnosticFormat> for reflexo_typst::DiagnosticFormat {
+impl From<DiagnosticFormat> for reflexo_typst::DiagnosticOutputFormat {
     fn from(fmt: DiagnosticFormat) -> Self {
         match fmt {
             DiagnosticFormat::Human => Self::Human,
             DiagnosticFormat::Short => Self::Short,
+            DiagnosticFormat::Json => Self::Json,
         }
     }
 }
@@ -18,7 +18,6 @@ use typst::{text::FontVariant, World};
 use typst_assets::fonts;
 use typst_ts_cli::compile::compile_export;
 use typst_ts_cli::manual::generate_manual;
-use typst_ts_cli::query::serialize;
 use typst_ts_cli::utils::*;
 use typst_ts_cli::version::*;
 use typst_ts_cli::*;
@@ -45,7 +44,10 @@ fn main() {
 
     match opts.sub {
         Some(Subcommands::Compile(args)) => compile(args),
+        Some(Subcommands::Build(args)) => typst_ts_cli::build::build(args),
         Some(Subcommands::Query(args)) => query(args),
+        Some(Subcommands::QueryRepl(args)) => typst_ts_cli::query_repl::query_repl(args),
+        Some(Subcommands::Diff(args)) => typst_ts_cli::diff::diff(args),
         Some(Subcommands::Completion(args)) => generate_completion(args),
         #[cfg(feature = "gen-manual")]
         Some(Subcommands::Manual(args)) => {
@@ -62,6 +64,7 @@ fn main() {
         },
         Some(Subcommands::Font(font_sub)) => match font_sub {
             FontSubCommands::List(args) => list_fonts(args),
+            FontSubCommands::Measure(args) => measure_fonts(args),
         },
         Some(Subcommands::Package(pkg_sub)) => match pkg_sub {
             PackageSubCommands::List(args) => list_packages(args),
@@ -106,8 +109,6 @@ fn compile(args: CompileArgs) -> ! {
 
 /// Execute a query command.
 pub fn query(args: QueryArgs) -> ! {
-    use reflexo_typst::query::retrieve;
-    use typst_ts_cli::query::format;
     let compile_args = args.compile.clone();
 
     let exporter = Arc::new(
@@ -120,21 +121,10 @@ pub fn query(args: QueryArgs) -> ! {
                 .context("no document found")?;
             let output = TypstDocument::Paged(output);
 
-            if args.selector == "document_title" {
-                let title = output
-                    .info()
-                    .title
-                    .as_ref()
-                    .map(|e| e.as_str())
-                    .unwrap_or("null");
-                let serialized = serialize(&title, "json").context("serialize query")?;
-                println!("{serialized}");
-                return Ok(());
-            }
-
             let world = &g.snap.world;
-            let data = retrieve(world, &args.selector, &output).context("query")?;
-            let serialized = format(data, &args).context("serialize query")?;
+            let serialized =
+                typst_ts_cli::query::query(world, &output, &args.selector, &args.query_format())
+                    .context("query")?;
             println!("{serialized}");
             Ok(())
         },
@@ -189,6 +179,19 @@ fn list_fonts(command: ListFontsArgs) -> ! {
     exit(0)
 }
 
+fn measure_fonts(args: MeasureFontsArgs) -> ! {
+    let include_system_fonts = !(args.no_system_fonts || args.font.ignore_system_fonts);
+    let profile =
+        reflexo_typst::font_profile::measure_fonts(&args.font.paths, include_system_fonts)
+            .unwrap_or_exit();
+
+    let profile = serde_json::to_vec(&profile).unwrap_or_exit();
+    std::fs::write(&args.output, profile).unwrap_or_exit();
+
+    eprintln!("measured fonts are written to {}", unix_slash(&args.output));
+    exit(0)
+}
+
 fn list_packages(args: ListPackagesArgs) -> ! {
     fn get_string(v: &toml::Value) -> &str {
         match v {
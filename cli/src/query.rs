@@ -1,29 +1,96 @@
+use reflexo_typst::query::{locate, retrieve, ElementLocation};
+use reflexo_typst::TypstDocument;
 use serde::Serialize;
 use typst::{
     diag::{bail, eco_format, StrResult},
-    foundations::{Content, IntoValue},
+    foundations::{IntoValue, Value},
+    World,
 };
 
 use crate::QueryArgs;
 
-/// Format the query result in the output format.
-pub fn format(elements: Vec<Content>, command: &QueryArgs) -> StrResult<String> {
-    if command.one && elements.len() != 1 {
+/// Options to format the query result.
+#[derive(Debug, Clone, Copy)]
+pub struct QueryFormat<'a> {
+    /// Extract just one field from all retrieved elements.
+    pub field: Option<&'a str>,
+    /// Expect and retrieve exactly one element.
+    pub one: bool,
+    /// Attach the location to each retrieved element.
+    pub with_location: bool,
+    /// The output format, `json` or `yaml`.
+    pub format: &'a str,
+}
+
+impl QueryArgs {
+    /// Gets the options to format the query result.
+    pub fn query_format(&self) -> QueryFormat<'_> {
+        QueryFormat {
+            field: self.field.as_deref(),
+            one: self.one,
+            with_location: self.with_location,
+            format: &self.output_format,
+        }
+    }
+}
+
+/// A retrieved element with its location.
+#[derive(Serialize)]
+struct LocatedValue {
+    value: Value,
+    location: ElementLocation,
+}
+
+/// Retrieve the matches for the selector and format them in the output
+/// format.
+pub fn query(
+    world: &dyn World,
+    document: &TypstDocument,
+    selector: &str,
+    opts: &QueryFormat,
+) -> StrResult<String> {
+    if selector == "document_title" {
+        let title = document
+            .info()
+            .title
+            .as_ref()
+            .map(|e| e.as_str())
+            .unwrap_or("null");
+        return serialize(&title, opts.format);
+    }
+
+    let elements = retrieve(world, selector, document)?;
+    if opts.one && elements.len() != 1 {
         bail!("expected exactly one element, found {}", elements.len())
     }
 
-    let mapped: Vec<_> = elements
-        .into_iter()
-        .filter_map(|c| match &command.field {
-            Some(field) => c.get_by_name(field).ok(),
-            _ => Some(c.into_value()),
-        })
-        .collect();
+    let mapped = elements.into_iter().filter_map(|elem| {
+        let value = match opts.field {
+            Some(field) => elem.get_by_name(field).ok()?,
+            _ => elem.clone().into_value(),
+        };
+        Some((elem, value))
+    });
 
-    if command.one {
-        serialize(&mapped[0], "json")
+    if opts.with_location {
+        let located = mapped
+            .map(|(elem, value)| LocatedValue {
+                value,
+                location: locate(world, document, &elem),
+            })
+            .collect();
+        format_items(located, opts)
     } else {
-        serialize(&mapped, "json")
+        format_items(mapped.map(|(_, value)| value).collect(), opts)
+    }
+}
+
+/// Format the query result in the output format.
+fn format_items<T: Serialize>(items: Vec<T>, opts: &QueryFormat) -> StrResult<String> {
+    if opts.one {
+        serialize(&items.first(), opts.format)
+    } else {
+        serialize(&items, opts.format)
     }
 }
 
@@ -31,6 +98,105 @@ pub fn format(elements: Vec<Content>, command: &QueryArgs) -> StrResult<String>
 pub fn serialize(data: &impl Serialize, format: &str) -> StrResult<String> {
     match format {
         "json" => serde_json::to_string_pretty(data).map_err(|e| eco_format!("{e}")),
+        "yaml" => serde_yaml::to_string(data).map_err(|e| eco_format!("{e}")),
         _ => bail!("unsupported serialization format: {}", format),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use reflexo_typst::config::{entry::EntryOpts, CompileOpts};
+    use reflexo_typst::{TypstPagedDocument, TypstSystemUniverse, TypstSystemWorld};
+    use typst::foundations::Bytes;
+
+    use super::*;
+
+    const SOURCE: &str = r#"#set page(width: 100pt, height: 100pt, margin: 10pt)
+#metadata("a") <item>
+#pagebreak()
+#metadata("b") <item>
+"#;
+
+    fn compile(source: &str) -> (TypstSystemWorld, TypstDocument) {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let verse = TypstSystemUniverse::new(CompileOpts {
+            entry: EntryOpts::new_workspace(root.into()),
+            no_system_fonts: true,
+            with_embedded_fonts: typst_assets::fonts().map(Cow::Borrowed).collect(),
+            ..CompileOpts::default()
+        })
+        .unwrap();
+        let world = verse
+            .with_entry_file(root.join("__test__.typ"))
+            .snapshot_with_entry_content(Bytes::from_string(source.to_owned()), None);
+
+        let doc = typst::compile::<TypstPagedDocument>(&world).output;
+        let doc = doc.unwrap_or_else(|err| panic!("failed to compile the source: {err:?}"));
+        (world, TypstDocument::Paged(Arc::new(doc)))
+    }
+
+    fn query_items(format: &str) -> String {
+        let (world, doc) = compile(SOURCE);
+        let opts = QueryFormat {
+            field: Some("value"),
+            one: false,
+            with_location: true,
+            format,
+        };
+        query(&world, &doc, "<item>", &opts).unwrap()
+    }
+
+    #[test]
+    fn test_query_with_location() {
+        let items: serde_json::Value = serde_json::from_str(&query_items("json")).unwrap();
+        let items = items.as_array().unwrap();
+        assert_eq!(items.len(), 2);
+
+        for (i, (item, value)) in items.iter().zip(["a", "b"]).enumerate() {
+            assert_eq!(item["value"], value);
+
+            let location = &item["location"];
+            assert_eq!(location["page"], i + 1);
+            assert!(location["position"]["x"].is_f64(), "{location}");
+            assert!(location["position"]["y"].is_f64(), "{location}");
+            assert!(location["package"].is_null(), "{location}");
+            let path = location["path"].as_str().unwrap();
+            assert!(path.ends_with("__test__.typ"), "{path}");
+            assert_eq!(location["range"]["start"]["line"], i * 2 + 1);
+            assert_eq!(location["range"]["start"]["column"], 1);
+        }
+    }
+
+    #[test]
+    fn test_query_yaml() {
+        let yaml = query_items("yaml");
+        assert!(yaml.starts_with("- value: a\n"), "{yaml}");
+        assert!(yaml.contains("  location:\n    page: 1\n"), "{yaml}");
+
+        // The YAML output carries the same data as the JSON output.
+        let yaml: serde_json::Value = serde_yaml::from_str(&yaml).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&query_items("json")).unwrap();
+        assert_eq!(yaml, json);
+    }
+
+    #[test]
+    fn test_query_one_without_location() {
+        let (world, doc) = compile(SOURCE);
+        let opts = QueryFormat {
+            field: None,
+            one: true,
+            with_location: false,
+            format: "yaml",
+        };
+        let err = query(&world, &doc, "<item>", &opts).unwrap_err();
+        assert_eq!(err, "expected exactly one element, found 2");
+
+        let opts = QueryFormat { one: false, ..opts };
+        let yaml = query(&world, &doc, "<item>", &opts).unwrap();
+        assert!(!yaml.contains("location"), "{yaml}");
+    }
+}
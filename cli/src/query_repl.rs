@@ -0,0 +1,308 @@
+use std::sync::Arc;
+
+use reflexo_typst::{
+    CompilationHandle, CompileActor, CompileReport, CompileServerOpts, DynSystemComputation,
+    OptionDocumentTask, SystemCompilerFeat, TypstDocument, TypstPagedDocument, WorldComputeGraph,
+};
+use rustyline::{error::ReadlineError, DefaultEditor};
+use tokio::sync::{mpsc, watch};
+
+use crate::compile::resolve_universe;
+use crate::export::ReflexoTaskBuilder;
+use crate::query::QueryFormat;
+use crate::utils::{self, UnwrapOrExit};
+use crate::{CompileArgs, QueryReplArgs};
+
+const REPL_HELP: &str = r#"Enter a selector to query the latest document, e.g.:
+  heading.where(level: 1)
+  heading --field body
+  <my-label> --one
+Modifiers:
+  --field <name>  Extract just one field from all retrieved elements
+  --one           Expect and retrieve exactly one element
+  --with-location Attach the page, position and source range to each element
+Commands:
+  :help           Print this message
+  :quit           Exit the REPL (or press Ctrl-D)"#;
+
+/// The latest compilation seen by the REPL.
+#[derive(Clone)]
+struct ReplState {
+    /// The latest compiled graph.
+    graph: Arc<WorldComputeGraph<SystemCompilerFeat>>,
+    /// The latest successfully compiled document.
+    doc: Option<TypstDocument>,
+}
+
+struct ReplHandler {
+    /// Reports diagnostics of each compilation.
+    reporter: DynSystemComputation,
+    state: watch::Sender<Option<ReplState>>,
+}
+
+impl CompilationHandle<SystemCompilerFeat> for ReplHandler {
+    fn status(&self, _revision: usize, _rep: CompileReport) {}
+
+    fn notify_compile(&self, g: &Arc<WorldComputeGraph<SystemCompilerFeat>>) {
+        let doc = g
+            .compute::<OptionDocumentTask<TypstPagedDocument>>()
+            .ok()
+            .and_then(|doc| doc.as_ref().clone())
+            .map(TypstDocument::Paged);
+
+        if let Err(err) = (self.reporter)(g) {
+            eprintln!("compile failed: {err}");
+        }
+
+        self.state.send_modify(|state| {
+            // Keeps the last good document if the compilation fails.
+            let doc = doc.or_else(|| state.as_ref().and_then(|s| s.doc.clone()));
+            *state = Some(ReplState {
+                graph: g.clone(),
+                doc,
+            });
+        });
+    }
+}
+
+/// A query parsed from a line of the REPL.
+struct ReplQuery<'a> {
+    selector: &'a str,
+    format: QueryFormat<'a>,
+}
+
+/// Parses a line in form of
+/// `<selector> [--field <name>] [--one] [--with-location]`.
+///
+/// The modifiers are parsed from the end of the line, so that a selector may
+/// contain ` --`, e.g. in a string.
+fn parse_query(line: &str) -> Result<ReplQuery<'_>, String> {
+    let mut format = QueryFormat {
+        field: None,
+        one: false,
+        with_location: false,
+        format: "json",
+    };
+
+    let mut rest = line.trim();
+    while let Some((head, word)) = split_last_word(rest) {
+        let Some(name) = modifier_name(word) else {
+            match split_last_word(head) {
+                Some((head, "--field")) if is_field_name(word) => {
+                    format.field.get_or_insert(word);
+                    rest = head;
+                    continue;
+                }
+                _ => break,
+            }
+        };
+
+        match name {
+            "one" => format.one = true,
+            "with-location" => format.with_location = true,
+            "field" => return Err("missing field name after --field".to_owned()),
+            _ => return Err(format!("unknown modifier: {word}")),
+        }
+        rest = head;
+    }
+
+    let selector = rest.trim();
+    if selector.is_empty() {
+        return Err("missing selector".to_owned());
+    }
+
+    Ok(ReplQuery { selector, format })
+}
+
+/// Splits the last whitespace-separated word from the text.
+fn split_last_word(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_end();
+    if text.is_empty() {
+        return None;
+    }
+
+    Some(match text.rsplit_once(char::is_whitespace) {
+        Some((head, word)) => (head.trim_end(), word),
+        None => ("", text),
+    })
+}
+
+/// Whether the word is a field name, e.g. `value`.
+fn is_field_name(word: &str) -> bool {
+    word.chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'))
+}
+
+/// Gets the name of a modifier, e.g. `one` for `--one`.
+fn modifier_name(word: &str) -> Option<&str> {
+    let name = word.strip_prefix("--")?;
+    let is_name = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c == '-');
+    is_name.then_some(name)
+}
+
+/// Evaluates a line of the REPL against the latest compilation.
+fn eval_line(state: &watch::Receiver<Option<ReplState>>, line: &str) -> Result<String, String> {
+    let query = parse_query(line)?;
+
+    let state = state.borrow().clone();
+    let Some(ReplState { graph, doc }) = state else {
+        return Err("document is not compiled yet".to_owned());
+    };
+    let doc = doc.ok_or("no document found, please fix compile errors first")?;
+
+    crate::query::query(&graph.snap.world, &doc, query.selector, &query.format)
+        .map_err(|err| err.to_string())
+}
+
+fn run_repl(state: watch::Receiver<Option<ReplState>>) -> rustyline::Result<()> {
+    let mut rl = DefaultEditor::new()?;
+    eprintln!("{REPL_HELP}");
+
+    loop {
+        let line = match rl.readline(">> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = rl.add_history_entry(line);
+
+        match line {
+            ":q" | ":quit" | ":exit" => return Ok(()),
+            ":h" | ":help" => eprintln!("{REPL_HELP}"),
+            _ => match eval_line(&state, line) {
+                Ok(serialized) => println!("{serialized}"),
+                Err(err) => eprintln!("error: {err}"),
+            },
+        }
+    }
+}
+
+/// Compiles the entry in watch mode and evaluates selectors read from the
+/// prompt against the latest document.
+pub fn query_repl(args: QueryReplArgs) -> ! {
+    let compile_args = CompileArgs {
+        compile: args.compile,
+        format: vec!["nothing".to_owned()],
+        diagnostic_format: args.diagnostic_format,
+        ..Default::default()
+    };
+
+    let mut tb = ReflexoTaskBuilder::new();
+    tb.args(&compile_args, None);
+    let reporter = tb.build();
+
+    let (state_tx, mut state_rx) = watch::channel(None);
+    let handle = Arc::new(ReplHandler {
+        reporter,
+        state: state_tx,
+    });
+
+    let (intr_tx, intr_rx) = mpsc::unbounded_channel();
+    let verse = resolve_universe(compile_args.compile);
+    let actor = CompileActor::new_with(
+        verse,
+        intr_tx,
+        intr_rx,
+        CompileServerOpts {
+            compile_handle: handle,
+            ..Default::default()
+        },
+    )
+    .with_watch(true);
+
+    utils::async_continue(async move {
+        let repl = async move {
+            // Waits for the first compilation before prompting.
+            let _ = state_rx.wait_for(Option::is_some).await;
+            tokio::task::spawn_blocking(move || run_repl(state_rx)).await
+        };
+
+        tokio::select! {
+            res = actor.run() => {
+                utils::logical_exit(res.unwrap_or_exit());
+            }
+            res = repl => {
+                res.unwrap_or_exit().unwrap_or_exit();
+                utils::logical_exit(true);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> (&str, Option<&str>, bool, bool) {
+        let query = parse_query(line).unwrap();
+        let format = query.format;
+        (
+            query.selector,
+            format.field,
+            format.one,
+            format.with_location,
+        )
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        assert_eq!(parse("heading"), ("heading", None, false, false));
+        assert_eq!(
+            parse("  heading.where(level: 1)  "),
+            ("heading.where(level: 1)", None, false, false)
+        );
+    }
+
+    #[test]
+    fn test_parse_modifiers() {
+        assert_eq!(
+            parse("<label> --field value --one --with-location"),
+            ("<label>", Some("value"), true, true)
+        );
+        assert_eq!(
+            parse("<label> --one --field value"),
+            ("<label>", Some("value"), true, false)
+        );
+    }
+
+    #[test]
+    fn test_parse_selector_containing_dashes() {
+        assert_eq!(
+            parse(r#"figure.where(caption: [a --one b]) --one"#),
+            ("figure.where(caption: [a --one b])", None, true, false)
+        );
+        assert_eq!(
+            parse(r#"metadata.where(value: "x --field y")"#),
+            (
+                r#"metadata.where(value: "x --field y")"#,
+                None,
+                false,
+                false
+            )
+        );
+        assert_eq!(
+            parse("heading --one]"),
+            ("heading --one]", None, false, false)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |line| parse_query(line).err().unwrap();
+        assert_eq!(err(""), "missing selector");
+        assert_eq!(err("--one"), "missing selector");
+        assert_eq!(err("heading --field"), "missing field name after --field");
+        assert_eq!(
+            err("heading --field --one"),
+            "missing field name after --field"
+        );
+        assert_eq!(err("heading --two"), "unknown modifier: --two");
+    }
+}
@@ -3,7 +3,7 @@
 use ecow::EcoString;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum HastElementContent {
     Root(HastRoot),
@@ -18,20 +18,22 @@ impl Default for HastElementContent {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Hash, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct HastRoot {
     pub children: Vec<HastElementContent>,
     // data
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct HastText {
     pub value: EcoString,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<HastPosition>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct HastElement {
     pub tag_name: EcoString,
@@ -41,12 +43,40 @@ pub struct HastElement {
     // todo: data
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<HastElementData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<HastPosition>,
 }
 
 pub type HastElementProperties = std::collections::BTreeMap<EcoString, EcoString>;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct HastElementData {
     pub hash: Option<EcoString>,
 }
+
+/// The location of a node in the source file.
+///
+/// See [unist.](https://github.com/syntax-tree/unist#position)
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HastPosition {
+    /// The place of the first character of the node.
+    pub start: HastPoint,
+    /// The place of the first character after the node.
+    pub end: HastPoint,
+}
+
+/// A place in the source file.
+///
+/// See [unist.](https://github.com/syntax-tree/unist#point)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HastPoint {
+    /// The 1-indexed line.
+    pub line: usize,
+    /// The 1-indexed column, in UTF-16 code units.
+    pub column: usize,
+    /// The 0-indexed offset in the file, in UTF-16 code units.
+    pub offset: usize,
+}
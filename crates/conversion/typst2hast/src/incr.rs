@@ -0,0 +1,647 @@
+//! Incremental encoding of Hast.
+//!
+//! The [`IncrHastServer`] keeps the Hast of the last revision, and packs the
+//! changes to the next revision as a list of [`HastPatch`]es, which are
+//! applied to the Hast kept by an [`IncrHastClient`].
+
+use std::sync::Arc;
+
+use ecow::{eco_format, EcoString};
+use reflexo::error::prelude::*;
+use reflexo::typst::TypstHtmlDocument;
+use serde::{Deserialize, Serialize};
+use typst::diag::SourceResult;
+use typst::World;
+
+use crate::hast::{HastElement, HastElementContent, HastElementData, HastPosition};
+
+/// The kind of a [`HastDelta`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HastDeltaKind {
+    /// The delta replaces the entire Hast, which can be merged regardless of
+    /// the client's state.
+    Full,
+    /// The delta contains the changes since the base revision.
+    Diff,
+}
+
+/// The changes of a Hast between two revisions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HastDelta {
+    /// The kind of the delta.
+    pub kind: HastDeltaKind,
+    /// The revision which the delta is based on.
+    pub base_revision: u64,
+    /// The revision of the Hast after merging the delta.
+    pub revision: u64,
+    /// The patches to apply in order.
+    pub patches: Vec<HastPatch>,
+}
+
+/// A change to a Hast.
+///
+/// A node is addressed by the indices of the children from the root to the
+/// node, hence the root is addressed by an empty path. The indices are
+/// resolved against the Hast after applying the previous patches.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "op")]
+pub enum HastPatch {
+    /// Inserts a node before the child at the path, or at the end of the
+    /// children if the index equals to their length.
+    Insert {
+        path: Vec<u32>,
+        node: HastElementContent,
+    },
+    /// Removes the node at the path.
+    Remove { path: Vec<u32> },
+    /// Replaces the subtree at the path.
+    Replace {
+        path: Vec<u32>,
+        node: HastElementContent,
+    },
+    /// Updates the value of the text or comment at the path.
+    UpdateText { path: Vec<u32>, value: EcoString },
+    /// Updates the fingerprint of the element at the path, whose descendants
+    /// are changed.
+    UpdateHash {
+        path: Vec<u32>,
+        hash: Option<EcoString>,
+    },
+    /// Updates the source position of the node at the path, e.g. when the
+    /// node is shifted by an edit before it.
+    UpdatePosition {
+        path: Vec<u32>,
+        position: Option<HastPosition>,
+    },
+}
+
+/// Packs the changes of the Hast of a document between compilations.
+///
+/// The elements are filled with content fingerprints in their
+/// [`HastElementData::hash`], which are compared to find the changed
+/// subtrees. The fingerprints don't cover the source positions, which are
+/// compared separately, so that the nodes shifted by an edit are patched by
+/// [`HastPatch::UpdatePosition`] instead of being replaced.
+#[derive(Default)]
+pub struct IncrHastServer {
+    /// The revision of the last packed delta.
+    revision: u64,
+    /// The Hast of the last packed delta.
+    prev: Option<HastElementContent>,
+}
+
+impl IncrHastServer {
+    /// Gets the revision of the last packed delta.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Packs the changes since the last packed delta. The first delta is a
+    /// full one.
+    pub fn pack_delta(
+        &mut self,
+        document: &Arc<TypstHtmlDocument>,
+        world: Option<&dyn World>,
+    ) -> SourceResult<HastDelta> {
+        let mut next = crate::hast(document, world)?;
+        fill_fingerprints(&mut next);
+
+        let base_revision = self.revision;
+        self.revision += 1;
+
+        let delta = match &self.prev {
+            Some(prev) => {
+                let mut patches = vec![];
+                diff_node(prev, &next, &mut vec![], &mut patches);
+                HastDelta {
+                    kind: HastDeltaKind::Diff,
+                    base_revision,
+                    revision: self.revision,
+                    patches,
+                }
+            }
+            None => full_delta(self.revision, next.clone()),
+        };
+
+        self.prev = Some(next);
+        Ok(delta)
+    }
+
+    /// Packs the current Hast as a full delta, e.g. for a newly connected
+    /// client.
+    pub fn pack_current(&self) -> Option<HastDelta> {
+        Some(full_delta(self.revision, self.prev.clone()?))
+    }
+}
+
+/// Keeps the Hast by merging deltas packed by an [`IncrHastServer`].
+#[derive(Default)]
+pub struct IncrHastClient {
+    /// The revision of the Hast.
+    revision: u64,
+    /// The Hast, or `None` if no full delta is merged yet.
+    root: Option<HastElementContent>,
+}
+
+impl IncrHastClient {
+    /// Gets the revision of the Hast.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Gets the Hast.
+    pub fn root(&self) -> Option<&HastElementContent> {
+        self.root.as_ref()
+    }
+
+    /// Merges a delta into the Hast.
+    ///
+    /// The Hast is left unchanged if the delta is not based on the current
+    /// revision or cannot be applied.
+    pub fn merge_delta(&mut self, delta: HastDelta) -> Result<()> {
+        let mut root = match delta.kind {
+            HastDeltaKind::Full => None,
+            HastDeltaKind::Diff if delta.base_revision != self.revision => {
+                return Err(error_once!("IncrHastClient.OutOfOrder",
+                    client_revision: self.revision,
+                    base_revision: delta.base_revision,
+                ));
+            }
+            HastDeltaKind::Diff => self.root.clone(),
+        };
+
+        for (idx, patch) in delta.patches.into_iter().enumerate() {
+            if !apply_patch(&mut root, patch) {
+                return Err(error_once!("IncrHastClient.InvalidPatch", idx: idx));
+            }
+        }
+
+        self.root = root;
+        self.revision = delta.revision;
+        Ok(())
+    }
+}
+
+fn full_delta(revision: u64, root: HastElementContent) -> HastDelta {
+    HastDelta {
+        kind: HastDeltaKind::Full,
+        base_revision: 0,
+        revision,
+        patches: vec![HastPatch::Replace {
+            path: vec![],
+            node: root,
+        }],
+    }
+}
+
+/// Fills the content fingerprints of the elements, bottom up.
+fn fill_fingerprints(node: &mut HastElementContent) {
+    match node {
+        HastElementContent::Root(root) => root.children.iter_mut().for_each(fill_fingerprints),
+        HastElementContent::Element(element) => {
+            element.children.iter_mut().for_each(fill_fingerprints);
+            // Frames are already hashed by their content.
+            if element
+                .data
+                .as_ref()
+                .is_some_and(|data| data.hash.is_some())
+            {
+                return;
+            }
+
+            let hash = content_hash(element);
+            element.data = Some(HastElementData {
+                hash: Some(eco_format!("siphash128_13:{hash:016x}")),
+            });
+        }
+        HastElementContent::Text(..) | HastElementContent::Comment(..) => {}
+    }
+}
+
+/// Computes the fingerprint of an element, whose child elements are already
+/// filled with fingerprints. The source positions are left out.
+fn content_hash(element: &HastElement) -> u128 {
+    #[derive(Hash)]
+    enum ChildKey<'a> {
+        Root,
+        Text(&'a str),
+        Comment(&'a str),
+        Element(Option<&'a str>),
+    }
+
+    let children = element.children.iter().map(|child| match child {
+        HastElementContent::Root(..) => ChildKey::Root,
+        HastElementContent::Text(text) => ChildKey::Text(&text.value),
+        HastElementContent::Comment(text) => ChildKey::Comment(&text.value),
+        HastElementContent::Element(element) => ChildKey::Element(hash_of(element)),
+    });
+
+    reflexo::hash::hash128(&(
+        &element.tag_name,
+        &element.properties,
+        children.collect::<Vec<_>>(),
+    ))
+}
+
+fn hash_of(element: &HastElement) -> Option<&str> {
+    element.data.as_ref()?.hash.as_deref()
+}
+
+fn position_of(node: &HastElementContent) -> Option<&HastPosition> {
+    match node {
+        HastElementContent::Root(..) => None,
+        HastElementContent::Text(text) | HastElementContent::Comment(text) => {
+            text.position.as_ref()
+        }
+        HastElementContent::Element(element) => element.position.as_ref(),
+    }
+}
+
+/// Whether two nodes have the same content, comparing the fingerprints of
+/// elements. The source positions are not compared.
+fn is_same(prev: &HastElementContent, next: &HastElementContent) -> bool {
+    use HastElementContent::*;
+    match (prev, next) {
+        (Element(prev), Element(next)) => match (hash_of(prev), hash_of(next)) {
+            (Some(h1), Some(h2)) => h1 == h2,
+            _ => prev == next,
+        },
+        (Text(prev), Text(next)) | (Comment(prev), Comment(next)) => prev.value == next.value,
+        _ => prev == next,
+    }
+}
+
+fn diff_node(
+    prev: &HastElementContent,
+    next: &HastElementContent,
+    path: &mut Vec<u32>,
+    patches: &mut Vec<HastPatch>,
+) {
+    use HastElementContent::*;
+
+    if is_same(prev, next) {
+        diff_positions(prev, next, path, patches);
+        return;
+    }
+
+    match (prev, next) {
+        (Text(prev_text), Text(next_text)) | (Comment(prev_text), Comment(next_text)) => {
+            patches.push(HastPatch::UpdateText {
+                path: path.clone(),
+                value: next_text.value.clone(),
+            });
+            diff_position(prev, next, path, patches);
+        }
+        (Element(prev_element), Element(next_element))
+            if prev_element.tag_name == next_element.tag_name
+                && prev_element.properties == next_element.properties =>
+        {
+            diff_children(
+                &prev_element.children,
+                &next_element.children,
+                path,
+                patches,
+            );
+            patches.push(HastPatch::UpdateHash {
+                path: path.clone(),
+                hash: hash_of(next_element).map(EcoString::from),
+            });
+            diff_position(prev, next, path, patches);
+        }
+        (Root(prev), Root(next)) => diff_children(&prev.children, &next.children, path, patches),
+        _ => patches.push(HastPatch::Replace {
+            path: path.clone(),
+            node: next.clone(),
+        }),
+    }
+}
+
+/// Patches the position of a node if it is changed.
+fn diff_position(
+    prev: &HastElementContent,
+    next: &HastElementContent,
+    path: &[u32],
+    patches: &mut Vec<HastPatch>,
+) {
+    if position_of(prev) != position_of(next) {
+        patches.push(HastPatch::UpdatePosition {
+            path: path.to_vec(),
+            position: position_of(next).cloned(),
+        });
+    }
+}
+
+/// Patches the positions of two nodes with the same content, recursively.
+fn diff_positions(
+    prev: &HastElementContent,
+    next: &HastElementContent,
+    path: &mut Vec<u32>,
+    patches: &mut Vec<HastPatch>,
+) {
+    use HastElementContent::*;
+
+    diff_position(prev, next, path, patches);
+    let (Element(prev), Element(next)) = (prev, next) else {
+        return;
+    };
+
+    for (idx, (prev, next)) in prev.children.iter().zip(next.children.iter()).enumerate() {
+        path.push(idx as u32);
+        diff_positions(prev, next, path, patches);
+        path.pop();
+    }
+}
+
+fn diff_children(
+    prev: &[HastElementContent],
+    next: &[HastElementContent],
+    path: &mut Vec<u32>,
+    patches: &mut Vec<HastPatch>,
+) {
+    let prefix = prev
+        .iter()
+        .zip(next.iter())
+        .take_while(|(prev, next)| is_same(prev, next))
+        .count();
+    let suffix = prev[prefix..]
+        .iter()
+        .rev()
+        .zip(next[prefix..].iter().rev())
+        .take_while(|(prev, next)| is_same(prev, next))
+        .count();
+
+    // The unchanged nodes may still be shifted in the source.
+    diff_pairs(0, &prev[..prefix], &next[..prefix], path, patches);
+
+    let prev_suffix = &prev[prev.len() - suffix..];
+    let next_suffix = &next[next.len() - suffix..];
+    let prev = &prev[prefix..prev.len() - suffix];
+    let next = &next[prefix..next.len() - suffix];
+    let common = prev.len().min(next.len());
+
+    diff_pairs(prefix, prev, next, path, patches);
+
+    let at = |idx: usize| {
+        let mut path = path.clone();
+        path.push((prefix + idx) as u32);
+        path
+    };
+    for _ in common..prev.len() {
+        patches.push(HastPatch::Remove { path: at(common) });
+    }
+    for (idx, node) in next.iter().enumerate().skip(common) {
+        patches.push(HastPatch::Insert {
+            path: at(idx),
+            node: node.clone(),
+        });
+    }
+
+    // The suffix is addressed after the removals and insertions.
+    let suffix_start = prefix + next.len();
+    diff_pairs(suffix_start, prev_suffix, next_suffix, path, patches);
+}
+
+/// Diffs the nodes paired by their indices, starting at the `offset`.
+fn diff_pairs(
+    offset: usize,
+    prev: &[HastElementContent],
+    next: &[HastElementContent],
+    path: &mut Vec<u32>,
+    patches: &mut Vec<HastPatch>,
+) {
+    for (idx, (prev, next)) in prev.iter().zip(next.iter()).enumerate() {
+        path.push((offset + idx) as u32);
+        diff_node(prev, next, path, patches);
+        path.pop();
+    }
+}
+
+/// Applies a patch to the Hast, returning `false` if the patch is invalid.
+fn apply_patch(root: &mut Option<HastElementContent>, patch: HastPatch) -> bool {
+    let path = match &patch {
+        HastPatch::Insert { path, .. }
+        | HastPatch::Remove { path }
+        | HastPatch::Replace { path, .. }
+        | HastPatch::UpdateText { path, .. }
+        | HastPatch::UpdateHash { path, .. }
+        | HastPatch::UpdatePosition { path, .. } => path,
+    };
+    let Some((&idx, parent_path)) = path.split_last() else {
+        // The root can only be replaced.
+        return match patch {
+            HastPatch::Replace { node, .. } => {
+                *root = Some(node);
+                true
+            }
+            _ => false,
+        };
+    };
+
+    let mut parent = root.as_mut();
+    for &i in parent_path {
+        parent = parent
+            .and_then(children_mut)
+            .and_then(|children| children.get_mut(i as usize));
+    }
+    let Some(children) = parent.and_then(children_mut) else {
+        return false;
+    };
+
+    let idx = idx as usize;
+    match patch {
+        HastPatch::Insert { node, .. } if idx <= children.len() => children.insert(idx, node),
+        HastPatch::Remove { .. } if idx < children.len() => {
+            children.remove(idx);
+        }
+        patch => match (patch, children.get_mut(idx)) {
+            (HastPatch::Replace { node, .. }, Some(child)) => *child = node,
+            (
+                HastPatch::UpdateText { value, .. },
+                Some(HastElementContent::Text(text) | HastElementContent::Comment(text)),
+            ) => text.value = value,
+            (HastPatch::UpdateHash { hash, .. }, Some(HastElementContent::Element(element))) => {
+                element.data = hash.map(|hash| HastElementData { hash: Some(hash) });
+            }
+            (HastPatch::UpdatePosition { position, .. }, Some(child)) => match child {
+                HastElementContent::Text(text) | HastElementContent::Comment(text) => {
+                    text.position = position;
+                }
+                HastElementContent::Element(element) => element.position = position,
+                HastElementContent::Root(..) => return false,
+            },
+            _ => return false,
+        },
+    }
+
+    true
+}
+
+fn children_mut(node: &mut HastElementContent) -> Option<&mut Vec<HastElementContent>> {
+    match node {
+        HastElementContent::Root(root) => Some(&mut root.children),
+        HastElementContent::Element(element) => Some(&mut element.children),
+        HastElementContent::Text(..) | HastElementContent::Comment(..) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hast::{HastPoint, HastText};
+
+    fn text(value: &str) -> HastElementContent {
+        HastElementContent::Text(HastText {
+            value: value.into(),
+            position: None,
+        })
+    }
+
+    fn element(tag_name: &str, children: Vec<HastElementContent>) -> HastElementContent {
+        HastElementContent::Element(Box::new(HastElement {
+            tag_name: tag_name.into(),
+            properties: Default::default(),
+            children,
+            data: None,
+            position: None,
+        }))
+    }
+
+    /// Places an element at the offset in the first line of the source.
+    fn at(mut node: HastElementContent, offset: usize) -> HastElementContent {
+        let point = |offset: usize| HastPoint {
+            line: 1,
+            column: offset + 1,
+            offset,
+        };
+        if let HastElementContent::Element(element) = &mut node {
+            element.position = Some(HastPosition {
+                start: point(offset),
+                end: point(offset + 1),
+            });
+        }
+        node
+    }
+
+    fn diff(mut prev: HastElementContent, mut next: HastElementContent) -> Vec<HastPatch> {
+        fill_fingerprints(&mut prev);
+        fill_fingerprints(&mut next);
+
+        let mut patches = vec![];
+        diff_node(&prev, &next, &mut vec![], &mut patches);
+        patches
+    }
+
+    fn check_roundtrip(mut prev: HastElementContent, mut next: HastElementContent) -> usize {
+        fill_fingerprints(&mut prev);
+        fill_fingerprints(&mut next);
+
+        let patches = diff(prev.clone(), next.clone());
+        let count = patches.len();
+
+        let mut client = IncrHastClient::default();
+        client.merge_delta(full_delta(1, prev)).unwrap();
+        let delta = HastDelta {
+            kind: HastDeltaKind::Diff,
+            base_revision: 1,
+            revision: 2,
+            patches,
+        };
+        client.merge_delta(delta).unwrap();
+        assert_eq!(client.root(), Some(&next));
+        count
+    }
+
+    #[test]
+    fn test_diff_roundtrip() {
+        let prev = element(
+            "body",
+            vec![
+                element("p", vec![text("a")]),
+                element("p", vec![text("b")]),
+                element("p", vec![text("c")]),
+            ],
+        );
+
+        // Updates a text.
+        let next = element(
+            "body",
+            vec![
+                element("p", vec![text("a")]),
+                element("p", vec![text("x")]),
+                element("p", vec![text("c")]),
+            ],
+        );
+        assert_eq!(check_roundtrip(prev.clone(), next), 3);
+
+        // Inserts and removes elements.
+        let next = element(
+            "body",
+            vec![
+                element("p", vec![text("a")]),
+                element("h1", vec![text("b")]),
+                element("p", vec![text("d")]),
+                element("p", vec![text("c")]),
+            ],
+        );
+        check_roundtrip(prev.clone(), next);
+        let next = element("body", vec![element("p", vec![text("c")])]);
+        check_roundtrip(prev.clone(), next);
+
+        // Nothing changes.
+        assert_eq!(check_roundtrip(prev.clone(), prev), 0);
+    }
+
+    #[test]
+    fn test_diff_shifted_positions() {
+        let prev = element(
+            "body",
+            vec![
+                at(element("p", vec![text("a")]), 0),
+                at(element("p", vec![text("b")]), 10),
+            ],
+        );
+        // The edit in the first paragraph shifts the second one.
+        let next = element(
+            "body",
+            vec![
+                at(element("p", vec![text("ab")]), 0),
+                at(element("p", vec![text("b")]), 11),
+            ],
+        );
+
+        let patches = diff(prev.clone(), next.clone());
+        assert!(
+            !patches
+                .iter()
+                .any(|patch| matches!(patch, HastPatch::Replace { .. })),
+            "{patches:?}"
+        );
+        let positions = patches
+            .iter()
+            .filter_map(|patch| match patch {
+                HastPatch::UpdatePosition { path, position } => Some((path, position)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(positions.len(), 1, "{patches:?}");
+        assert_eq!(positions[0].0, &[1]);
+        assert_eq!(positions[0].1.as_ref().unwrap().start.offset, 11);
+
+        check_roundtrip(prev, next);
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let mut client = IncrHastClient::default();
+        let delta = HastDelta {
+            kind: HastDeltaKind::Diff,
+            base_revision: 1,
+            revision: 2,
+            patches: vec![],
+        };
+        assert!(client.merge_delta(delta).is_err());
+        assert_eq!(client.revision(), 0);
+    }
+}
@@ -1,3 +1,4 @@
+use std::ops::Range;
 use std::sync::Arc;
 
 use base64::Engine;
@@ -6,62 +7,107 @@ use ecow::{eco_format, EcoString};
 use reflexo::typst::TypstHtmlDocument;
 use typst::diag::SourceResult;
 use typst::model::LateLinkResolver;
-use typst::syntax::Span;
+use typst::syntax::{Source, Span};
+use typst::{World, WorldExt};
 use typst_html::{HtmlElement, HtmlNode};
 
-use crate::hast::{HastElement, HastElementContent, HastText};
+use crate::hast::{HastElement, HastElementContent, HastPoint, HastPosition, HastText};
 
 pub mod hast;
+pub mod incr;
 
 /// Encodes an HTML document into a Hast.
-pub fn hast(document: &Arc<TypstHtmlDocument>) -> SourceResult<HastElementContent> {
+///
+/// If the `world` is given, the nodes are annotated with their positions in
+/// the source files.
+pub fn hast(
+    document: &Arc<TypstHtmlDocument>,
+    world: Option<&dyn World>,
+) -> SourceResult<HastElementContent> {
     let link_resolver = LateLinkResolver::new(None, document.introspector().as_ref());
-    let link_resolver = link_resolver.track();
-    write_element(document.root(), link_resolver)
+    let w = Writer {
+        link_resolver: link_resolver.track(),
+        world,
+    };
+    write_element(&w, document.root())
+}
+
+/// The context of encoding.
+struct Writer<'a> {
+    /// The resolver of links in frames.
+    link_resolver: comemo::Tracked<'a, LateLinkResolver<'a>>,
+    /// The world to resolve source positions.
+    world: Option<&'a dyn World>,
+}
+
+impl Writer<'_> {
+    /// Resolves the position of a span in its source file.
+    fn position(&self, span: Span) -> Option<HastPosition> {
+        let world = self.world?;
+        let source = world.source(span.id()?).ok()?;
+        let range = world.range(span)?;
+
+        source_position(&source, range)
+    }
+}
+
+/// Converts a byte range in a source file into a position, whose lines and
+/// columns are 1-based and columns and offsets are counted in UTF-16 code
+/// units, as JavaScript strings are indexed.
+fn source_position(source: &Source, range: Range<usize>) -> Option<HastPosition> {
+    let lines = source.lines();
+    let point = |byte: usize| {
+        let line = lines.byte_to_line(byte)?;
+        let offset = lines.byte_to_utf16(byte)?;
+        let line_offset = lines.byte_to_utf16(lines.line_to_byte(line)?)?;
+        Some(HastPoint {
+            line: line + 1,
+            column: offset - line_offset + 1,
+            offset,
+        })
+    };
+
+    Some(HastPosition {
+        start: point(range.start)?,
+        end: point(range.end)?,
+    })
 }
 
 /// Encode an HTML node into the writer.
-fn write_node(
-    node: &HtmlNode,
-    buf: &mut Vec<HastElementContent>,
-    link_resolver: comemo::Tracked<LateLinkResolver<'_>>,
-) -> SourceResult<()> {
+fn write_node(w: &Writer, node: &HtmlNode, buf: &mut Vec<HastElementContent>) -> SourceResult<()> {
     match node {
         HtmlNode::Tag(_) => {}
         HtmlNode::Text(text, span) => {
-            buf.push(write_text(text, *span)?);
+            buf.push(write_text(w, text, *span)?);
         }
         HtmlNode::Element(element) => {
-            buf.push(write_element(element, link_resolver)?);
+            buf.push(write_element(w, element)?);
         }
         HtmlNode::Frame(frame) => {
-            write_frame(frame, buf, link_resolver);
+            write_frame(w, frame, buf);
         }
     }
     Ok(())
 }
 
 /// Encode plain text into the writer.
-fn write_text(text: &EcoString, _span: Span) -> SourceResult<HastElementContent> {
+fn write_text(w: &Writer, text: &EcoString, span: Span) -> SourceResult<HastElementContent> {
     Ok(HastElementContent::Text(HastText {
         value: EcoString::from(text),
-        // todo: span mapping
+        position: w.position(span),
     }))
 }
 
 /// Encode one element into the write.
-fn write_element(
-    element: &HtmlElement,
-    link_resolver: comemo::Tracked<LateLinkResolver<'_>>,
-) -> SourceResult<HastElementContent> {
-    write_element_with_tag(element, &element.tag.resolve(), link_resolver)
+fn write_element(w: &Writer, element: &HtmlElement) -> SourceResult<HastElementContent> {
+    write_element_with_tag(w, element, &element.tag.resolve())
 }
 
 /// Encode one element into the write.
 fn write_element_with_tag(
+    w: &Writer,
     element: &HtmlElement,
     tag: &str,
-    link_resolver: comemo::Tracked<LateLinkResolver<'_>>,
 ) -> SourceResult<HastElementContent> {
     let properties = element
         .attrs
@@ -76,7 +122,7 @@ fn write_element_with_tag(
 
     if !element.children.is_empty() {
         for c in &element.children {
-            write_node(c, &mut buf, link_resolver)?;
+            write_node(w, c, &mut buf)?;
         }
     }
 
@@ -85,15 +131,12 @@ fn write_element_with_tag(
         properties,
         children: buf,
         data: None,
+        position: w.position(element.span),
     })))
 }
 
 /// Encode a laid out frame into the writer.
-fn write_frame(
-    frame: &typst_html::HtmlFrame,
-    buf: &mut Vec<HastElementContent>,
-    link_resolver: comemo::Tracked<LateLinkResolver<'_>>,
-) {
+fn write_frame(w: &Writer, frame: &typst_html::HtmlFrame, buf: &mut Vec<HastElementContent>) {
     // FIXME: This string replacement is obviously a hack.
     let svg = typst_svg::svg_in_html(
         &frame.inner,
@@ -102,7 +145,7 @@ fn write_frame(
         frame.id.as_deref(),
         &eco_format!("{}", frame.css.to_inline()),
         &frame.anchors,
-        link_resolver,
+        w.link_resolver,
     )
     .replace("<svg class", "<svg style=\"overflow: visible;\" class");
 
@@ -127,5 +170,45 @@ fn write_frame(
                 reflexo::hash::hash128(&frame)
             )),
         }),
+        position: w.position(frame.span),
     })));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(text: &str, range: Range<usize>) -> ((usize, usize, usize), (usize, usize, usize)) {
+        let source = Source::detached(text);
+        let position = source_position(&source, range).unwrap();
+        let point = |p: HastPoint| (p.line, p.column, p.offset);
+        (point(position.start), point(position.end))
+    }
+
+    #[test]
+    fn test_position_ascii() {
+        let text = "= Heading\nHello world";
+        let start = text.find("world").unwrap();
+        assert_eq!(position(text, start..start + 5), ((2, 7, 16), (2, 12, 21)));
+    }
+
+    #[test]
+    fn test_position_utf16() {
+        // `é` takes two bytes and one UTF-16 unit, and `😀` takes four bytes
+        // and two UTF-16 units.
+        let text = "é😀 a\n😀b";
+        let a = text.find('a').unwrap();
+        assert_eq!(a, 7);
+        assert_eq!(position(text, a..a + 1), ((1, 5, 4), (1, 6, 5)));
+
+        let b = text.find('b').unwrap();
+        assert_eq!(position(text, b..b + 1), ((2, 3, 8), (2, 4, 9)));
+    }
+
+    #[test]
+    fn test_position_out_of_source() {
+        let source = Source::detached("hello");
+        assert!(source_position(&source, 0..5).is_some());
+        assert!(source_position(&source, 0..6).is_none());
+    }
+}
@@ -1,14 +1,98 @@
 use reflexo::error::prelude::*;
+use reflexo::hash::Fingerprint;
 use reflexo::typst::TypstDocument;
-use reflexo::vector::ir::{ModuleMetadata, Page};
+use reflexo::vector::frame::DeltaFeatures;
+use reflexo::vector::ir::{FontRef, GlyphRef, ModuleMetadata, Page, VecItem};
+use reflexo::vector::stream::RkyvStreamData;
+use rkyv::{Archive, Deserialize as rDeser, Serialize as rSer};
 
 use super::ir::FlatModule;
 use super::pass::IncrTypst2VecPass;
 use crate::debug_loc::{ElementPoint, SourceSpanOffset};
 
+pub use super::pass::GcPolicy;
+pub use reflexo::vector::frame::{DeltaError, DeltaHeader, DeltaKind, DELTA_PROTOCOL_VERSION};
 /// Client side implementation is free from typst details.
 pub use reflexo::vector::incr::{IncrDocClient, IncrDocClientKern};
 
+/// Statistics of a delta packed by [`IncrDocServer::pack_delta_with_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrDeltaStats {
+    /// The number of items added by the delta.
+    pub new_items: usize,
+    /// The number of fonts added by the delta.
+    pub new_fonts: usize,
+    /// The number of glyphs added by the delta.
+    pub new_glyphs: usize,
+    /// The number of items collected by the delta.
+    pub collected_items: usize,
+    /// The number of glyphs collected by the delta.
+    pub collected_glyphs: usize,
+    /// The number of items kept at server side after the delta.
+    pub module_items: usize,
+    /// The estimated size of items kept at server side after the delta, in
+    /// bytes.
+    pub module_bytes: usize,
+    /// The size of the packed delta in bytes.
+    pub delta_bytes: usize,
+}
+
+/// The version of the [`IncrServerSnapshot`] format.
+///
+/// It must be increased whenever the layout of the snapshot changes.
+pub const INCR_SNAPSHOT_VERSION: u32 = 2;
+
+/// A snapshot of the state of [`IncrDocServer`] shared with its clients.
+///
+/// It keeps the fingerprints and lifetimes of items, and the references of
+/// fonts and glyphs, but not the items themselves, since they are already
+/// held by clients. Thus, a restored server continues to pack small deltas
+/// for clients that are still alive. Only the items kept by the server, e.g.
+/// the text items retained by [`GcPolicy::retain_text`], are stored.
+#[derive(Debug, Clone, Archive, rDeser, rSer)]
+#[archive(check_bytes)]
+pub struct IncrServerSnapshot {
+    /// The version of the snapshot format, see [`INCR_SNAPSHOT_VERSION`].
+    pub version: u32,
+    /// The revision of the last packed delta.
+    pub revision: u64,
+    /// The lifetime of the typst2vec pass.
+    pub lifetime: u64,
+    /// The references of pages of the document.
+    pub pages: Option<Vec<Page>>,
+    /// The items held by clients, with their lifetimes.
+    pub items: Vec<(Fingerprint, u64)>,
+    /// The items kept by the server.
+    pub kept_items: Vec<(Fingerprint, VecItem)>,
+    /// The fonts held by clients, keyed by the hash of the font.
+    pub fonts: Vec<(u32, FontRef)>,
+    /// The glyphs held by clients.
+    pub glyphs: Vec<GlyphRef>,
+}
+
+impl IncrServerSnapshot {
+    /// Serializes the snapshot into a binary blob.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        use rkyv::ser::{serializers::AllocSerializer, Serializer};
+
+        let mut serializer = AllocSerializer::<0>::default();
+        serializer.serialize_value(self).map_err(
+            |err| error_once!("IncrServerSnapshot.SerializeFailed", err: format!("{err:?}")),
+        )?;
+        Ok(serializer.into_serializer().into_inner().into_vec())
+    }
+
+    /// Deserializes a snapshot from a binary blob.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let data = RkyvStreamData::from(data);
+        let archived = rkyv::check_archived_root::<Self>(data.as_ref())
+            .map_err(|err| error_once!("IncrServerSnapshot.Corrupted", err: format!("{err:?}")))?;
+
+        let Ok(snapshot) = archived.deserialize(&mut rkyv::Infallible);
+        Ok(snapshot)
+    }
+}
+
 /// maintains the data of the incremental rendering at server side
 #[derive(Default)]
 pub struct IncrDocServer {
@@ -18,6 +102,12 @@ pub struct IncrDocServer {
 
     /// Maintaining typst -> vector status
     typst2vec: IncrTypst2VecPass,
+
+    /// The garbage collection policy applied on each delta.
+    gc_policy: GcPolicy,
+
+    /// The revision of the last packed delta.
+    revision: u64,
 }
 
 impl IncrDocServer {
@@ -28,25 +118,115 @@ impl IncrDocServer {
             .set_should_attach_debug_info(should_attach_debug_info);
     }
 
+    /// Gets the garbage collection policy.
+    pub fn gc_policy(&self) -> &GcPolicy {
+        &self.gc_policy
+    }
+
+    /// Sets the garbage collection policy, which takes effect from the next
+    /// delta.
+    ///
+    /// Note: [`GcPolicy::retain_text`] only retains text items lowered after
+    /// it is set.
+    pub fn set_gc_policy(&mut self, gc_policy: GcPolicy) {
+        self.typst2vec.retain_text = gc_policy.retain_text;
+        self.gc_policy = gc_policy;
+    }
+
+    /// Gets the revision of the last packed delta.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Takes a snapshot of the state shared with clients.
+    pub fn snapshot(&mut self) -> IncrServerSnapshot {
+        let (fonts, glyphs) = self.typst2vec.glyphs.sent_refs();
+        IncrServerSnapshot {
+            version: INCR_SNAPSHOT_VERSION,
+            revision: self.revision,
+            lifetime: self.typst2vec.lifetime,
+            pages: self.pages.clone(),
+            items: self.typst2vec.item_lifetimes(),
+            kept_items: self.typst2vec.kept_items(),
+            fonts,
+            glyphs,
+        }
+    }
+
+    /// Restores a server from a snapshot taken by [`Self::snapshot`].
+    ///
+    /// The next [`Self::pack_delta`] is based on the revision of the
+    /// snapshot, so clients that merged all deltas before the snapshot can
+    /// keep merging deltas. Since the items, fonts and glyphs held by clients
+    /// are not restored, other clients should connect to a fresh server
+    /// instead.
+    ///
+    /// Settings such as [`Self::set_gc_policy`] are not part of the snapshot
+    /// and should be set again.
+    pub fn restore(snapshot: IncrServerSnapshot) -> Result<Self> {
+        if snapshot.version != INCR_SNAPSHOT_VERSION {
+            return Err(error_once!("IncrServerSnapshot.VersionMismatch",
+                expected: INCR_SNAPSHOT_VERSION,
+                actual: snapshot.version,
+            ));
+        }
+
+        let mut server = Self {
+            pages: snapshot.pages,
+            revision: snapshot.revision,
+            ..Default::default()
+        };
+        server
+            .typst2vec
+            .restore_items(snapshot.lifetime, snapshot.items, snapshot.kept_items);
+        server
+            .typst2vec
+            .glyphs
+            .restore_sent_refs(snapshot.fonts, snapshot.glyphs);
+
+        Ok(server)
+    }
+
+    fn features(&self) -> DeltaFeatures {
+        let mut features = DeltaFeatures::empty();
+        features.set(
+            DeltaFeatures::SOURCE_MAPPING,
+            self.typst2vec.spans.should_attach_debug_info,
+        );
+        features
+    }
+
     /// Pack the delta into a binary blob.
     pub fn pack_delta(&mut self, output: &TypstDocument) -> Vec<u8> {
+        self.pack_delta_with_stats(output).0
+    }
+
+    /// Pack the delta into a binary blob, with the statistics of the delta.
+    pub fn pack_delta_with_stats(&mut self, output: &TypstDocument) -> (Vec<u8>, IncrDeltaStats) {
         self.typst2vec.spans.reset();
 
         // Increment the lifetime of all items to touch.
         self.typst2vec.increment_lifetime();
 
         // it is important to call gc before building pages
-        let gc_items = self.typst2vec.gc(5 * 2);
+        let gc_items = self.typst2vec.gc_by(&self.gc_policy);
+        let collected_glyphs = self.typst2vec.gc_glyphs_by(&self.gc_policy);
 
         // run typst2vec pass
         let pages = self.typst2vec.doc(output);
         self.pages = Some(pages.clone());
 
-        // let new_items = builder.new_items.get_mut().len();
-        // let new_fonts = builder.glyphs.new_fonts.get_mut().len();
-        // let new_glyphs = builder.glyphs.new_glyphs.get_mut().len();
-
         let delta = self.typst2vec.finalize_delta();
+        let mut stats = IncrDeltaStats {
+            new_items: delta.items.len(),
+            new_fonts: delta.fonts.len(),
+            new_glyphs: delta.glyphs.len(),
+            collected_items: gc_items.len(),
+            collected_glyphs,
+            module_items: self.typst2vec.item_count(),
+            module_bytes: self.typst2vec.item_bytes(),
+            delta_bytes: 0,
+        };
 
         // max, min lifetime current, gc_items
         #[cfg(feature = "debug-gc")]
@@ -85,8 +265,19 @@ impl IncrDocServer {
         m.add_single_layout(pages);
         let delta = m.to_bytes();
 
+        let base_revision = self.revision;
+        self.revision += 1;
+        let header = DeltaHeader::new(
+            DeltaKind::Diff,
+            self.features(),
+            base_revision,
+            self.revision,
+        );
+
         // log::info!("svg render time (incremental bin): {:?}", instant.elapsed());
-        [b"diff-v1,", delta.as_slice()].concat()
+        let delta = [b"diff-v1,", header.encode().as_slice(), delta.as_slice()].concat();
+        stats.delta_bytes = delta.len();
+        (delta, stats)
     }
 
     /// Pack the current entirely into a binary blob.
@@ -99,7 +290,8 @@ impl IncrDocServer {
         m.add_single_layout(pages);
         let full = m.to_bytes();
 
-        Some([b"new,", full.as_slice()].concat())
+        let header = DeltaHeader::new(DeltaKind::Full, self.features(), 0, self.revision);
+        Some([b"new,", header.encode().as_slice(), full.as_slice()].concat())
     }
 
     /// Gets element paths by the given span.
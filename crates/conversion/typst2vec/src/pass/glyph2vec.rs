@@ -1,6 +1,6 @@
 //! Lowering Typst Document into SvgItem.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ops::DerefMut;
 use std::sync::Arc;
 
@@ -48,12 +48,19 @@ pub struct TGlyph2VecPass<const ENABLE_REF_CNT: bool = false> {
     /// Lock to get a unique local index for each font.
     font_index: Mutex<usize>,
 
-    /// Intermediate representation of an incompleted glyph pack.
-    glyph_defs: reflexo::adt::CHashMap<GlyphItem, (GlyphRef, FontRef)>,
+    /// Intermediate representation of an incompleted glyph pack, with the
+    /// lifetime the glyphs are last used.
+    glyph_defs: reflexo::adt::CHashMap<GlyphItem, (GlyphRef, FontRef, u64)>,
 
     /// for interning
     pub used_fonts: HashSet<FontRef>,
     pub used_glyphs: HashSet<GlyphRef>,
+
+    /// Fonts already sent to clients before restoring, keyed by the hash of
+    /// the font. See [`IncrGlyph2VecPass::restore_sent_refs`].
+    restored_fonts: HashMap<u32, FontRef>,
+    /// Glyphs already sent to clients before restoring.
+    restored_glyphs: HashSet<GlyphRef>,
 }
 
 impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
@@ -70,6 +77,8 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
             new_glyphs: Default::default(),
             used_fonts: Default::default(),
             used_glyphs: Default::default(),
+            restored_fonts: Default::default(),
+            restored_glyphs: Default::default(),
         }
     }
 
@@ -107,15 +116,30 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
 
         let entry = self.font_mapping.entry(font.clone());
         let entry = entry.or_insert_with(|| {
+            let font_hash = reflexo::hash::hash32(font);
+
+            // Reuses the reference if the font is already sent to clients.
+            if let Some(font_ref) = self.restored_fonts.get(&font_hash) {
+                self.font_conflict_checker
+                    .insert(font_ref.hash, font.clone());
+                return *font_ref;
+            }
+
             let font_index = font_index_lock.deref_mut();
             let mut abs_ref = FontRef {
-                hash: reflexo::hash::hash32(font),
+                hash: font_hash,
                 idx: (*font_index) as u32,
             };
             *font_index += 1;
 
             // Detect font short hash conflict
             'conflict_detection: loop {
+                let mut restored = self.restored_fonts.values();
+                if restored.any(|font_ref| font_ref.hash == abs_ref.hash) {
+                    abs_ref.hash += 1;
+                    continue 'conflict_detection;
+                }
+
                 if let Some(conflict) = self.font_conflict_checker.get(&abs_ref.hash) {
                     if *conflict != *font {
                         log::error!(
@@ -159,13 +183,13 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
 
         if self
             .glyph_defs
-            .insert(glyph.clone(), (abs_ref, font_ref))
+            .insert(glyph.clone(), (abs_ref, font_ref, self.lifetime))
             .is_some()
         {
             return abs_ref;
         }
 
-        if ENABLE_REF_CNT {
+        if ENABLE_REF_CNT && !self.restored_glyphs.contains(&abs_ref) {
             self.new_glyphs.lock().push((abs_ref, glyph));
         }
 
@@ -183,6 +207,74 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
 }
 
 impl IncrGlyph2VecPass {
+    /// Gets the references of fonts and glyphs sent to clients. The fonts
+    /// are keyed by the hash of the font and sorted by their indices.
+    pub fn sent_refs(&mut self) -> (Vec<(u32, FontRef)>, Vec<GlyphRef>) {
+        let mut fonts = self.restored_fonts.clone();
+        for shard in self.font_mapping.as_mut_slice() {
+            for (font, font_ref) in shard.get_mut().iter() {
+                fonts.insert(reflexo::hash::hash32(font), *font_ref);
+            }
+        }
+        let mut fonts = fonts.into_iter().collect::<Vec<_>>();
+        fonts.sort_by_key(|(_, font_ref)| font_ref.idx);
+
+        let mut glyphs = self.restored_glyphs.clone();
+        for shard in self.glyph_defs.as_mut_slice() {
+            glyphs.extend(shard.get_mut().values().map(|(id, ..)| *id));
+        }
+
+        (fonts, glyphs.into_iter().collect())
+    }
+
+    /// Restores the references of fonts and glyphs sent to clients, so that
+    /// they are not sent again when they are used.
+    pub fn restore_sent_refs(&mut self, fonts: Vec<(u32, FontRef)>, glyphs: Vec<GlyphRef>) {
+        let next_index = fonts.iter().map(|(_, f)| f.idx as usize + 1).max();
+        *self.font_index.get_mut() = next_index.unwrap_or_default();
+        self.restored_fonts = fonts.into_iter().collect();
+        self.restored_glyphs = glyphs.into_iter().collect();
+    }
+
+    /// Collects glyphs last used before the `threshold` lifetime, and fonts
+    /// without any glyph left, returning the number of collected glyphs.
+    ///
+    /// The collected glyphs and fonts are sent again when they are used later,
+    /// while clients keep the stale ones.
+    pub fn gc(&mut self, threshold: u64) -> usize {
+        let mut collected = 0;
+        let mut alive_fonts = HashSet::new();
+        for shard in self.glyph_defs.as_mut_slice() {
+            shard.get_mut().retain(|_, (_, font_ref, lifetime)| {
+                let alive = *lifetime >= threshold;
+                if alive {
+                    alive_fonts.insert(*font_ref);
+                } else {
+                    collected += 1;
+                }
+                alive
+            });
+        }
+
+        let mut collected_hashes = HashSet::new();
+        for shard in self.font_mapping.as_mut_slice() {
+            shard.get_mut().retain(|_, font_ref| {
+                let alive = alive_fonts.contains(font_ref);
+                if !alive {
+                    collected_hashes.insert(font_ref.hash);
+                }
+                alive
+            });
+        }
+        for shard in self.font_conflict_checker.as_mut_slice() {
+            shard
+                .get_mut()
+                .retain(|hash, _| !collected_hashes.contains(hash));
+        }
+
+        collected
+    }
+
     pub fn finalize_delta(&self) -> (FontPack, Vec<(GlyphRef, FlatGlyphItem)>) {
         let fonts = std::mem::take(self.new_fonts.lock().deref_mut());
         let glyphs = std::mem::take(self.new_glyphs.lock().deref_mut());
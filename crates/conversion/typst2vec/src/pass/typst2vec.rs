@@ -120,6 +120,9 @@ pub struct Typst2VecPassImpl<const ENABLE_REF_CNT: bool = false> {
     fingerprint_builder: FingerprintBuilder,
 
     pub lifetime: u64,
+    /// Whether to keep text items after they are sent, see
+    /// [`GcPolicy::retain_text`].
+    pub retain_text: bool,
 }
 
 pub type Typst2VecPass = Typst2VecPassImpl</* ENABLE_REF_CNT */ false>;
@@ -139,6 +142,7 @@ impl<const ENABLE_REF_CNT: bool> Default for Typst2VecPassImpl<ENABLE_REF_CNT> {
             new_items: Default::default(),
             fingerprint_builder: Default::default(),
             command_executor: Arc::new(()),
+            retain_text: false,
         }
     }
 }
@@ -274,13 +278,23 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
     }
 
     pub fn paged(&self, doc: &TypstPagedDocument) -> Vec<Page> {
+        let indices = (0..doc.pages().len()).collect::<Vec<_>>();
+        self.paged_by_indices(doc, &indices)
+    }
+
+    /// Lowers the pages at the given (zero-based) indices of the document.
+    ///
+    /// Only the items reachable from the selected pages are lowered, and the
+    /// `idx`-th lowered page is the `indices[idx]`-th page of the document.
+    pub fn paged_by_indices(&self, doc: &TypstPagedDocument, indices: &[usize]) -> Vec<Page> {
         let doc_reg = self.spans.start();
+        let doc_pages = doc.pages();
 
-        let pages = doc
-            .pages()
+        let pages = indices
             .par_iter()
             .enumerate()
-            .map(|(idx, p)| {
+            .map(|(idx, page_idx)| {
+                let p = &doc_pages[*page_idx];
                 let page_reg = self.spans.start();
 
                 let state = State::new(doc.introspector().as_ref(), p.frame.size().into_typst());
@@ -603,8 +617,10 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
         }
 
         let item_resolution = if ENABLE_REF_CNT {
-            self.new_items.lock().push((fg, item.into_owned()));
-            (AtomicU64::new(self.lifetime), VecItem::None)
+            let item = item.into_owned();
+            let kept = self.kept_item(&item);
+            self.new_items.lock().push((fg, item));
+            (AtomicU64::new(self.lifetime), kept)
         } else {
             (AtomicU64::new(0), item.into_owned())
         };
@@ -652,8 +668,10 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
             }
             Vacant(pos) => {
                 let item_resolution = if ENABLE_REF_CNT {
-                    self.new_items.lock().push((fg, item.into_owned()));
-                    (AtomicU64::new(self.lifetime), VecItem::None)
+                    let item = item.into_owned();
+                    let kept = self.kept_item(&item);
+                    self.new_items.lock().push((fg, item));
+                    (AtomicU64::new(self.lifetime), kept)
                 } else {
                     (AtomicU64::new(0), item.into_owned())
                 };
@@ -664,6 +682,15 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
         }
     }
 
+    /// Gets the item kept by an incremental pass after it is sent. Text items
+    /// are kept only if they are retained by [`GcPolicy::retain_text`].
+    fn kept_item(&self, item: &VecItem) -> VecItem {
+        match item {
+            VecItem::Text(text) if self.retain_text => VecItem::Text(text.clone()),
+            _ => VecItem::None,
+        }
+    }
+
     /// Convert a text into vector item.
     fn text(&self, state: State, text: &TypstTextItem) -> Fingerprint {
         let stateful_fill = match text.fill {
@@ -703,13 +730,27 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
             })
         };
 
+        let font = self.glyphs.build_font(&text.font);
+        let build_glyphs = || {
+            for glyph in &text.glyphs {
+                self.glyphs
+                    .build_glyph(font, GlyphItem::Raw(text.font.clone(), GlyphId(glyph.id)));
+            }
+        };
+
+        // The glyphs are also built if the text item is cached, which renews
+        // their lifetimes, or sends them again if they are collected.
+        if ENABLE_REF_CNT {
+            build_glyphs();
+        }
+
         self.store_cached(&cond, || {
-            let font = self.glyphs.build_font(&text.font);
+            if !ENABLE_REF_CNT {
+                build_glyphs();
+            }
 
             let mut glyphs = Vec::with_capacity(text.glyphs.len());
             for glyph in &text.glyphs {
-                self.glyphs
-                    .build_glyph(font, GlyphItem::Raw(text.font.clone(), GlyphId(glyph.id)));
                 glyphs.push((
                     Axes::<Abs> {
                         x: glyph.x_offset.at(text.size).into_typst(),
@@ -726,8 +767,6 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
             let glyph_chars: String = text.text.to_string();
             // let mut extras = ExtraSvgItems::default();
 
-            let font = self.glyphs.build_font(&text.font);
-
             let mut styles = vec![PathStyle::Fill(stateful_fill())];
             if let Some(stroke) = text.stroke.as_ref() {
                 self.stroke(stateful_stroke, stroke, &mut styles);
@@ -1199,6 +1238,39 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
     }
 }
 
+/// The garbage collection policy of [`IncrTypst2VecPass`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcPolicy {
+    /// Items unused in the last `max_age` revisions are collected.
+    pub max_age: u64,
+    /// The maximum number of items to keep. The least recently used items are
+    /// collected first if the budget is exceeded.
+    pub max_items: Option<usize>,
+    /// The maximum estimated size of items to keep, in bytes, including the
+    /// cached items lowered to them. The least recently used items are
+    /// collected first if the budget is exceeded.
+    pub max_bytes: Option<usize>,
+    /// Never collects text items, so that text is not re-sent when it is used
+    /// again after a while.
+    pub retain_text: bool,
+    /// Never collects glyphs and fonts. Otherwise, glyphs unused in the last
+    /// `max_age` revisions and fonts without any glyph left are collected, and
+    /// they are sent again when they are used later.
+    pub retain_glyphs: bool,
+}
+
+impl Default for GcPolicy {
+    fn default() -> Self {
+        Self {
+            max_age: 5,
+            max_items: None,
+            max_bytes: None,
+            retain_text: false,
+            retain_glyphs: true,
+        }
+    }
+}
+
 impl IncrTypst2VecPass {
     /// Increment the lifetime of the module.
     /// It increments by 2 which is used to distinguish between the
@@ -1215,7 +1287,31 @@ impl IncrTypst2VecPass {
 
     /// Perform garbage collection with given threshold.
     pub fn gc(&mut self, threshold: u64) -> Vec<Fingerprint> {
+        self.gc_impl(threshold, &GcPolicy::default())
+    }
+
+    /// Perform garbage collection by the given policy.
+    pub fn gc_by(&mut self, policy: &GcPolicy) -> Vec<Fingerprint> {
+        self.gc_impl(gc_threshold(policy), policy)
+    }
+
+    /// Collects glyphs and fonts by the given policy, returning the number of
+    /// collected glyphs.
+    pub fn gc_glyphs_by(&mut self, policy: &GcPolicy) -> usize {
+        if policy.retain_glyphs {
+            return 0;
+        }
+
+        let threshold = self.lifetime.saturating_sub(gc_threshold(policy));
+        self.glyphs.gc(threshold)
+    }
+
+    fn gc_impl(&mut self, threshold: u64, policy: &GcPolicy) -> Vec<Fingerprint> {
+        use std::sync::atomic::Ordering;
+
         let gc_items = Arc::new(Mutex::new(vec![]));
+        let retain_text = policy.retain_text;
+        let is_retained = |item: &VecItem| retain_text && matches!(item, VecItem::Text(..));
 
         // a threshold is set by current lifetime subtracted by the given threshold.
         // It uses saturating_sub to prevent underflow (u64).
@@ -1223,7 +1319,7 @@ impl IncrTypst2VecPass {
 
         self.items.as_mut_slice().par_iter_mut().for_each(|e| {
             e.get_mut().retain(|k, v| {
-                if v.0.load(std::sync::atomic::Ordering::Relaxed) < gc_threshold {
+                if v.0.load(Ordering::Relaxed) < gc_threshold && !is_retained(&v.1) {
                     gc_items.lock().push(*k);
                     false
                 } else {
@@ -1232,18 +1328,146 @@ impl IncrTypst2VecPass {
             });
         });
 
-        // Same as above
-        let cache_threshold = self.lifetime.saturating_sub(threshold);
+        let mut gc_items = Arc::try_unwrap(gc_items).unwrap().into_inner();
+
+        // Collects the least recently used items if the budget is exceeded.
+        if policy.max_items.is_some() || policy.max_bytes.is_some() {
+            let cached_sizes = self.cached_sizes();
+
+            let mut candidates = vec![];
+            let (mut item_count, mut item_bytes) = (0, 0);
+            for e in self.items.as_mut_slice() {
+                for (k, v) in e.get_mut().iter() {
+                    let size = estimated_size(&v.1) + cached_sizes.get(k).copied().unwrap_or(0);
+                    item_count += 1;
+                    item_bytes += size;
+                    if !is_retained(&v.1) {
+                        candidates.push((v.0.load(Ordering::Relaxed), *k, size));
+                    }
+                }
+            }
+            candidates.sort_unstable();
+
+            let mut evicted = std::collections::HashSet::new();
+            for (_, k, size) in candidates {
+                let over_items = policy.max_items.is_some_and(|max| item_count > max);
+                let over_bytes = policy.max_bytes.is_some_and(|max| item_bytes > max);
+                if !over_items && !over_bytes {
+                    break;
+                }
+                evicted.insert(k);
+                item_count -= 1;
+                item_bytes -= size;
+            }
+
+            if !evicted.is_empty() {
+                self.items.as_mut_slice().par_iter_mut().for_each(|e| {
+                    e.get_mut().retain(|k, _| !evicted.contains(k));
+                });
+                gc_items.extend(evicted);
+            }
+        }
+
+        // The cached items are collected with the same threshold, and together
+        // with the items they are lowered to. Otherwise, the cache would keep
+        // the memory of collected items.
+        let collected = gc_items
+            .iter()
+            .copied()
+            .collect::<std::collections::HashSet<_>>();
         self.cache_items
             .as_mut_slice()
             .par_iter_mut()
             .for_each(|e| {
                 e.get_mut().retain(|_, v| {
-                    v.0.load(std::sync::atomic::Ordering::Relaxed) >= cache_threshold
+                    v.0.load(Ordering::Relaxed) >= gc_threshold && !collected.contains(&v.1)
                 });
             });
 
-        Arc::try_unwrap(gc_items).unwrap().into_inner()
+        gc_items
+    }
+
+    /// Estimates the size of the cached items lowered to each item, in bytes.
+    fn cached_sizes(&mut self) -> std::collections::HashMap<Fingerprint, usize> {
+        let mut sizes = std::collections::HashMap::new();
+        for e in self.cache_items.as_mut_slice() {
+            for (_, v) in e.get_mut().iter() {
+                *sizes.entry(v.1).or_insert(0) += estimated_size(&v.2);
+            }
+        }
+        sizes
+    }
+
+    /// Estimates the size of items kept in the pass, including the cached
+    /// items lowered to them, in bytes.
+    pub fn item_bytes(&mut self) -> usize {
+        let cached = self.cached_sizes().into_values().sum::<usize>();
+        let kept = self
+            .items
+            .as_mut_slice()
+            .iter_mut()
+            .flat_map(|e| e.get_mut().values().map(|v| estimated_size(&v.1)))
+            .sum::<usize>();
+        cached + kept
+    }
+
+    /// Gets the number of items kept in the pass.
+    pub fn item_count(&mut self) -> usize {
+        self.items
+            .as_mut_slice()
+            .iter_mut()
+            .map(|e| e.get_mut().len())
+            .sum()
+    }
+
+    /// Gets the lifetimes of items kept in the pass.
+    pub fn item_lifetimes(&mut self) -> Vec<(Fingerprint, u64)> {
+        use std::sync::atomic::Ordering;
+
+        let mut items = Vec::with_capacity(self.item_count());
+        for e in self.items.as_mut_slice() {
+            let e = e.get_mut();
+            items.extend(e.iter().map(|(k, v)| (*k, v.0.load(Ordering::Relaxed))));
+        }
+        items
+    }
+
+    /// Gets the items whose data is kept in the pass, see
+    /// [`GcPolicy::retain_text`].
+    pub fn kept_items(&mut self) -> Vec<(Fingerprint, VecItem)> {
+        let mut items = vec![];
+        for e in self.items.as_mut_slice() {
+            let e = e.get_mut();
+            items.extend(
+                e.iter()
+                    .filter(|(_, v)| !matches!(v.1, VecItem::None))
+                    .map(|(k, v)| (*k, v.1.clone())),
+            );
+        }
+        items
+    }
+
+    /// Restores the items kept in the pass by their lifetimes, and the data of
+    /// the `kept` items. The items are assumed to be held by clients, so they
+    /// will not be sent again.
+    pub fn restore_items(
+        &mut self,
+        lifetime: u64,
+        items: Vec<(Fingerprint, u64)>,
+        kept: Vec<(Fingerprint, VecItem)>,
+    ) {
+        self.lifetime = lifetime;
+        self.glyphs.lifetime = lifetime;
+
+        let mut kept = kept
+            .into_iter()
+            .collect::<std::collections::HashMap<_, _>>();
+        for (fg, item_lifetime) in items {
+            let item = kept.remove(&fg).unwrap_or(VecItem::None);
+            let shard = self.items.shard(fg);
+            let mut shard = shard.write();
+            shard.insert(fg, (AtomicU64::new(item_lifetime), item));
+        }
     }
 
     /// Finalize modules containing new vector items.
@@ -1262,6 +1486,47 @@ impl IncrTypst2VecPass {
     }
 }
 
+/// Gets the threshold of lifetime by the policy. The lifetime is incremented
+/// by 2 for each revision.
+fn gc_threshold(policy: &GcPolicy) -> u64 {
+    policy.max_age.saturating_mul(2)
+}
+
+/// Estimates the memory held by an item, in bytes. The data shared between
+/// items, e.g. images, is counted for each item.
+fn estimated_size(item: &VecItem) -> usize {
+    use std::mem::{size_of, size_of_val};
+
+    let heap = match item {
+        VecItem::None
+        | VecItem::Item(..)
+        | VecItem::Labelled(..)
+        | VecItem::Color32(..)
+        | VecItem::ContentHint(..)
+        | VecItem::ColorTransform(..)
+        | VecItem::Pattern(..) => 0,
+        VecItem::Image(image) => image.image.data.len(),
+        VecItem::Link(link) => link.href.len(),
+        VecItem::Path(path) => path.d.len() + size_of_val(path.styles.as_slice()),
+        VecItem::Text(text) => {
+            text.content.content.len()
+                + size_of_val(text.content.glyphs.as_ref())
+                + size_of_val(text.shape.styles.as_slice())
+        }
+        VecItem::Group(group) => size_of_val(group.0.as_ref()),
+        VecItem::Gradient(gradient) => {
+            size_of_val(gradient.stops.as_slice()) + size_of_val(gradient.styles.as_slice())
+        }
+        VecItem::SizedRawHtml(html) => html.html.len(),
+        VecItem::Html(html) => {
+            let attrs = html.attrs.iter().map(|(k, v)| k.len() + v.len());
+            attrs.sum::<usize>() + size_of_val(html.children.as_slice())
+        }
+    };
+
+    size_of::<VecItem>() + heap
+}
+
 // impl<'m, const ENABLE_REF_CNT: bool> ItemIndice<'m> for
 // ConvertImpl<ENABLE_REF_CNT> {     fn get_item(&self, value: &Fingerprint) ->
 // Option<&'m VecItem> {         self.items.get(value).map(|item| &item.1)
@@ -1274,3 +1539,149 @@ static LINE_HINT_ELEMENTS: std::sync::LazyLock<std::collections::HashSet<&'stati
         set.insert("heading");
         set
     });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(href: &str) -> VecItem {
+        VecItem::Link(LinkItem {
+            href: href.into(),
+            size: Axes::new(Scalar(1.), Scalar(1.)),
+        })
+    }
+
+    fn text(content: &str) -> VecItem {
+        VecItem::Text(TextItem {
+            shape: Arc::new(TextShape {
+                font: FontRef { hash: 0, idx: 0 },
+                dir: "ltr".into(),
+                size: Scalar(10.),
+                styles: vec![],
+            }),
+            content: Arc::new(TextItemContent {
+                content: content.into(),
+                glyphs: Vec::new().into(),
+            }),
+        })
+    }
+
+    /// Runs a revision lowering the items, returning the collected items and
+    /// the fingerprints of the lowered items.
+    fn revision(
+        pass: &mut IncrTypst2VecPass,
+        policy: &GcPolicy,
+        items: &[VecItem],
+    ) -> (Vec<Fingerprint>, Vec<Fingerprint>) {
+        pass.increment_lifetime();
+        let collected = pass.gc_by(policy);
+        let lowered = items
+            .iter()
+            .map(|item| pass.store_cached(item, || item.clone()))
+            .collect();
+        (collected, lowered)
+    }
+
+    #[test]
+    fn test_gc_by_age() {
+        let policy = GcPolicy {
+            max_age: 2,
+            ..GcPolicy::default()
+        };
+        let mut pass = IncrTypst2VecPass::default();
+        let (a, b) = (link("a"), link("b"));
+
+        let (_, lowered) = revision(&mut pass, &policy, &[a.clone(), b.clone()]);
+        let b_fg = lowered[1];
+        assert!(revision(&mut pass, &policy, &[a.clone()]).0.is_empty());
+        assert!(revision(&mut pass, &policy, &[a.clone()]).0.is_empty());
+
+        let (collected, _) = revision(&mut pass, &policy, &[a.clone()]);
+        assert_eq!(collected, [b_fg]);
+        assert_eq!(pass.item_count(), 1);
+        assert!(!pass.cached_sizes().contains_key(&b_fg));
+
+        // The collected item is sent again when it is used later.
+        revision(&mut pass, &policy, &[b.clone()]);
+        assert_eq!(pass.new_items.get_mut().len(), 1);
+    }
+
+    #[test]
+    fn test_gc_by_count() {
+        let policy = GcPolicy {
+            max_age: 100,
+            max_items: Some(2),
+            ..GcPolicy::default()
+        };
+        let mut pass = IncrTypst2VecPass::default();
+
+        let (_, lowered) = revision(&mut pass, &policy, &[link("a")]);
+        let a_fg = lowered[0];
+        assert!(revision(&mut pass, &policy, &[link("b")]).0.is_empty());
+        assert!(revision(&mut pass, &policy, &[link("c")]).0.is_empty());
+
+        // The least recently used item is collected with its cached item.
+        let (collected, _) = revision(&mut pass, &policy, &[]);
+        assert_eq!(collected, [a_fg]);
+        assert_eq!(pass.item_count(), 2);
+        assert!(!pass.cached_sizes().contains_key(&a_fg));
+    }
+
+    #[test]
+    fn test_gc_by_bytes() {
+        let item_size = estimated_size(&VecItem::None) + estimated_size(&link("a"));
+        let policy = GcPolicy {
+            max_age: 100,
+            max_bytes: Some(item_size * 2),
+            ..GcPolicy::default()
+        };
+        let mut pass = IncrTypst2VecPass::default();
+
+        let (_, lowered) = revision(&mut pass, &policy, &[link("a")]);
+        let a_fg = lowered[0];
+        revision(&mut pass, &policy, &[link("b")]);
+        assert_eq!(pass.item_bytes(), item_size * 2);
+        revision(&mut pass, &policy, &[link("c")]);
+        assert_eq!(pass.item_bytes(), item_size * 3);
+
+        let (collected, _) = revision(&mut pass, &policy, &[]);
+        assert_eq!(collected, [a_fg]);
+        assert_eq!(pass.item_bytes(), item_size * 2);
+    }
+
+    #[test]
+    fn test_gc_retain_text() {
+        let policy = GcPolicy {
+            max_age: 1,
+            max_items: Some(0),
+            retain_text: true,
+            ..GcPolicy::default()
+        };
+        let mut pass = IncrTypst2VecPass {
+            retain_text: true,
+            ..Default::default()
+        };
+
+        let (_, lowered) = revision(&mut pass, &policy, &[text("a"), link("b")]);
+        let (collected, _) = revision(&mut pass, &policy, &[]);
+        assert_eq!(collected, [lowered[1]]);
+        assert_eq!(pass.item_count(), 1);
+
+        // The text item is kept even if it exceeds the age and the budget.
+        for _ in 0..3 {
+            assert!(revision(&mut pass, &policy, &[]).0.is_empty());
+        }
+        assert_eq!(pass.item_count(), 1);
+    }
+
+    #[test]
+    fn test_kept_item() {
+        let mut pass = IncrTypst2VecPass::default();
+        assert!(matches!(pass.kept_item(&text("a")), VecItem::None));
+        assert!(matches!(pass.kept_item(&link("a")), VecItem::None));
+
+        pass.retain_text = true;
+        assert!(matches!(pass.kept_item(&text("a")), VecItem::Text(..)));
+        assert!(matches!(pass.kept_item(&link("a")), VecItem::None));
+    }
+}
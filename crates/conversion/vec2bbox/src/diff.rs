@@ -0,0 +1,469 @@
+//! Page-level diff between two vector documents.
+//!
+//! Vector items are content-addressed by [`Fingerprint`], so identical
+//! subtrees are skipped without visiting them. Only the groups whose
+//! fingerprints differ are walked, and their children are matched by the
+//! fingerprint and the position.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tiny_skia as sk;
+
+use reflexo::{hash::Fingerprint, vector::ir::*};
+
+use crate::Vec2BBoxPass;
+
+/// The status of a page in the diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PageStatus {
+    /// The page is identical in both documents.
+    Unchanged,
+    /// The page exists in both documents but differs.
+    Changed,
+    /// The page only exists in the new document.
+    Added,
+    /// The page only exists in the old document.
+    Removed,
+}
+
+/// The kind of an added or removed item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffItemKind {
+    Group,
+    Text,
+    Path,
+    Image,
+    Link,
+    Html,
+}
+
+/// An item added to or removed from a page.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffItem {
+    pub kind: DiffItemKind,
+    /// The fingerprint of the item, encoded as an svg id.
+    pub fingerprint: String,
+    /// The content of a text item.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The bounding box in the page, in `[x, y, width, height]`.
+    pub rect: [f32; 4],
+}
+
+/// The diff of a page.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageDiff {
+    /// The index of the page.
+    pub page: usize,
+    pub status: PageStatus,
+    /// The size of the page, taken from the new document if it exists there.
+    pub size: [f32; 2],
+    /// The items only in the new document.
+    pub added: Vec<DiffItem>,
+    /// The items only in the old document.
+    pub removed: Vec<DiffItem>,
+    /// The changed regions, which are the merged bounding boxes of the added
+    /// and removed items, in `[x, y, width, height]`.
+    pub regions: Vec<[f32; 4]>,
+}
+
+/// The diff between two vector documents.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiff {
+    pub pages: Vec<PageDiff>,
+}
+
+impl DocumentDiff {
+    /// Whether the documents are identical.
+    pub fn is_empty(&self) -> bool {
+        self.changed_pages().next().is_none()
+    }
+
+    /// Iterates the pages that are changed, added or removed.
+    pub fn changed_pages(&self) -> impl Iterator<Item = &PageDiff> {
+        self.pages
+            .iter()
+            .filter(|page| page.status != PageStatus::Unchanged)
+    }
+}
+
+/// Diffs the pages in the first layouts of two documents.
+pub fn diff_documents(old: &MultiVecDocument, new: &MultiVecDocument) -> DocumentDiff {
+    fn pages_of(doc: &MultiVecDocument) -> &[Page] {
+        let layout = doc
+            .layouts
+            .first()
+            .and_then(|layout| layout.by_selector(&LayoutSelectorExpr::Any).ok());
+        layout
+            .and_then(|layout| layout.pages_meta())
+            .unwrap_or_default()
+    }
+
+    diff_pages(&old.module, pages_of(old), &new.module, pages_of(new))
+}
+
+/// Diffs two lists of pages, which are matched by their indices.
+pub fn diff_pages(
+    old_module: &Module,
+    old_pages: &[Page],
+    new_module: &Module,
+    new_pages: &[Page],
+) -> DocumentDiff {
+    let mut differ = Differ {
+        old: old_module,
+        new: new_module,
+        bbox: Vec2BBoxPass::default(),
+        added: vec![],
+        removed: vec![],
+    };
+
+    let len = old_pages.len().max(new_pages.len());
+    let pages = (0..len)
+        .map(|idx| {
+            let ts = sk::Transform::identity();
+            let (status, size) = match (old_pages.get(idx), new_pages.get(idx)) {
+                (Some(old), Some(new)) if old.content == new.content && old.size == new.size => {
+                    (PageStatus::Unchanged, new.size)
+                }
+                (Some(old), Some(new)) => {
+                    differ.diff(old.content, new.content, ts);
+                    (PageStatus::Changed, new.size)
+                }
+                (None, Some(new)) => {
+                    differ.record(Side::New, new.content, ts);
+                    (PageStatus::Added, new.size)
+                }
+                (Some(old), None) => {
+                    differ.record(Side::Old, old.content, ts);
+                    (PageStatus::Removed, old.size)
+                }
+                (None, None) => unreachable!(),
+            };
+
+            let added = std::mem::take(&mut differ.added);
+            let removed = std::mem::take(&mut differ.removed);
+            let regions = merge_regions(added.iter().chain(removed.iter()).map(|e| e.rect));
+            PageDiff {
+                page: idx,
+                status,
+                size: [size.x.0, size.y.0],
+                added,
+                removed,
+                regions,
+            }
+        })
+        .collect();
+
+    DocumentDiff { pages }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Old,
+    New,
+}
+
+struct Differ<'m> {
+    old: &'m Module,
+    new: &'m Module,
+    bbox: Vec2BBoxPass,
+    added: Vec<DiffItem>,
+    removed: Vec<DiffItem>,
+}
+
+impl Differ<'_> {
+    fn diff(&mut self, old: Fingerprint, new: Fingerprint, ts: sk::Transform) {
+        if old == new {
+            return;
+        }
+
+        let (old_module, new_module) = (self.old, self.new);
+        let (Some(old_item), Some(new_item)) =
+            (old_module.get_item(&old), new_module.get_item(&new))
+        else {
+            self.record(Side::Old, old, ts);
+            self.record(Side::New, new, ts);
+            return;
+        };
+
+        match (old_item, new_item) {
+            (VecItem::Group(old_group), VecItem::Group(new_group)) => {
+                self.diff_group(&old_group.0, &new_group.0, ts)
+            }
+            (VecItem::Item(old_item), VecItem::Item(new_item)) if old_item.0 == new_item.0 => {
+                let trans: Transform = old_item.0.clone().into();
+                self.diff(old_item.1, new_item.1, ts.pre_concat(trans.into()))
+            }
+            (VecItem::Labelled(old_item), VecItem::Labelled(new_item))
+                if old_item.0 == new_item.0 =>
+            {
+                self.diff(old_item.1, new_item.1, ts)
+            }
+            _ => {
+                self.record(Side::Old, old, ts);
+                self.record(Side::New, new, ts);
+            }
+        }
+    }
+
+    /// Matches the children of two groups. The children with the same
+    /// fingerprint and position are unchanged, and the rest of children at the
+    /// same position are diffed recursively.
+    fn diff_group(
+        &mut self,
+        old: &[(Point, Fingerprint)],
+        new: &[(Point, Fingerprint)],
+        ts: sk::Transform,
+    ) {
+        let mut new_by_fg = HashMap::<Fingerprint, Vec<usize>>::new();
+        for (idx, (_, fg)) in new.iter().enumerate() {
+            new_by_fg.entry(*fg).or_default().push(idx);
+        }
+
+        let mut new_matched = vec![false; new.len()];
+        let mut old_rest = vec![];
+        for (pos, fg) in old.iter() {
+            let matched = new_by_fg.get(fg).and_then(|indices| {
+                indices
+                    .iter()
+                    .copied()
+                    .find(|idx| !new_matched[*idx] && new[*idx].0 == *pos)
+            });
+            match matched {
+                Some(idx) => new_matched[idx] = true,
+                None => old_rest.push((*pos, *fg)),
+            }
+        }
+
+        for (pos, fg) in old_rest {
+            let ts = ts.pre_translate(pos.x.0, pos.y.0);
+            let paired = new
+                .iter()
+                .enumerate()
+                .position(|(idx, (new_pos, _))| !new_matched[idx] && *new_pos == pos);
+            match paired {
+                Some(idx) => {
+                    new_matched[idx] = true;
+                    self.diff(fg, new[idx].1, ts);
+                }
+                None => self.record(Side::Old, fg, ts),
+            }
+        }
+
+        for ((pos, fg), matched) in new.iter().zip(new_matched) {
+            if !matched {
+                self.record(Side::New, *fg, ts.pre_translate(pos.x.0, pos.y.0));
+            }
+        }
+    }
+
+    /// Records an item only in one side of the diff.
+    fn record(&mut self, side: Side, fg: Fingerprint, ts: sk::Transform) {
+        let module = match side {
+            Side::Old => self.old,
+            Side::New => self.new,
+        };
+        let Some(item) = module.get_item(&fg) else {
+            return;
+        };
+
+        let (kind, text) = match item {
+            VecItem::Item(t) => {
+                let trans: Transform = t.0.clone().into();
+                return self.record(side, t.1, ts.pre_concat(trans.into()));
+            }
+            VecItem::Labelled(t) => return self.record(side, t.1, ts),
+            VecItem::Group(..) => (DiffItemKind::Group, None),
+            VecItem::Text(t) => (DiffItemKind::Text, Some(t.content.content.to_string())),
+            VecItem::Path(..) => (DiffItemKind::Path, None),
+            VecItem::Image(..) => (DiffItemKind::Image, None),
+            VecItem::Link(..) => (DiffItemKind::Link, None),
+            VecItem::SizedRawHtml(..) | VecItem::Html(..) => (DiffItemKind::Html, None),
+            VecItem::ContentHint(..)
+            | VecItem::ColorTransform(..)
+            | VecItem::Pattern(..)
+            | VecItem::Gradient(..)
+            | VecItem::Color32(..)
+            | VecItem::None => return,
+        };
+
+        let Some(rect) = self.bbox_of(module, fg, ts) else {
+            return;
+        };
+
+        let item = DiffItem {
+            kind,
+            fingerprint: fg.as_svg_id(""),
+            text,
+            rect: [rect.left().0, rect.top().0, rect.width().0, rect.height().0],
+        };
+        match side {
+            Side::Old => self.removed.push(item),
+            Side::New => self.added.push(item),
+        }
+    }
+
+    /// Calculates the bounding box of an item in the page.
+    ///
+    /// The transforms are accumulated down to the leaf items, so that text
+    /// and paths under scaled or rotated groups are measured correctly.
+    fn bbox_of(&mut self, module: &Module, fg: Fingerprint, ts: sk::Transform) -> Option<Rect> {
+        let item = module.get_item(&fg)?;
+        let rect = match item {
+            VecItem::Group(g) => {
+                let mut r: Option<Rect> = None;
+                for (pos, fg) in g.0.iter() {
+                    let ts = ts.pre_translate(pos.x.0, pos.y.0);
+                    if let Some(sub) = self.bbox_of(module, *fg, ts) {
+                        r = Some(r.map_or(sub, |r| r.union(&sub)));
+                    }
+                }
+                return r;
+            }
+            VecItem::Item(t) => {
+                let trans: Transform = t.0.clone().into();
+                return self.bbox_of(module, t.1, ts.pre_concat(trans.into()));
+            }
+            VecItem::Labelled(t) => return self.bbox_of(module, t.1, ts),
+            VecItem::Path(p) => return Vec2BBoxPass::path_bbox(p, ts),
+            VecItem::Text(t) => {
+                let font = module.get_font(&t.shape.font)?;
+                let size = t.shape.size;
+                let top = -(font.ascender * size).0;
+                let bottom = (font.descender * size).0.abs();
+                Rect {
+                    lo: Point::new(Scalar(0.), Scalar(top)),
+                    hi: Point::new(t.width(), Scalar(bottom)),
+                }
+            }
+            _ => self.bbox.bbox_of(module, fg, Transform::identity())?,
+        };
+
+        let rect: sk::Rect = rect.try_into().ok()?;
+        rect.transform(ts).map(From::from)
+    }
+}
+
+/// Merges the overlapping rectangles.
+fn merge_regions(rects: impl Iterator<Item = [f32; 4]>) -> Vec<[f32; 4]> {
+    let overlaps = |a: &[f32; 4], b: &[f32; 4]| {
+        a[0] <= b[0] + b[2] && b[0] <= a[0] + a[2] && a[1] <= b[1] + b[3] && b[1] <= a[1] + a[3]
+    };
+
+    let mut regions: Vec<[f32; 4]> = vec![];
+    for mut rect in rects {
+        // Merging a rectangle may make it overlap with the previous regions,
+        // so the merged region is reinserted until it overlaps nothing.
+        while let Some(idx) = regions.iter().position(|r| overlaps(r, &rect)) {
+            let r = regions.swap_remove(idx);
+            let x0 = r[0].min(rect[0]);
+            let y0 = r[1].min(rect[1]);
+            let x1 = (r[0] + r[2]).max(rect[0] + rect[2]);
+            let y1 = (r[1] + r[3]).max(rect[1] + rect[3]);
+            rect = [x0, y0, x1 - x0, y1 - y0];
+        }
+        regions.push(rect);
+    }
+
+    regions
+}
+
+impl PageDiff {
+    /// Renders an svg overlay of the page that highlights the removed items
+    /// in red and the added items in green. The overlay has the same size as
+    /// the page and is meant to be stacked over a rendering of the page.
+    pub fn overlay_svg(&self) -> String {
+        use std::fmt::Write;
+
+        let [w, h] = self.size;
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            r#"<svg class="typst-diff" xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#
+        );
+
+        let groups = [
+            ("removed", "#d73a49", &self.removed),
+            ("added", "#28a745", &self.added),
+        ];
+        for (class, color, items) in groups {
+            let _ = write!(
+                svg,
+                r#"<g class="typst-diff-{class}" fill="{color}" fill-opacity="0.15" stroke="{color}" stroke-width="0.5">"#
+            );
+            for item in items.iter() {
+                let [x, y, w, h] = item.rect;
+                let _ = write!(svg, r#"<rect x="{x}" y="{y}" width="{w}" height="{h}"/>"#);
+            }
+            svg.push_str("</g>");
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst_ts_test_common::compile::lower_paged;
+
+    use super::*;
+
+    fn document(source: &str) -> MultiVecDocument {
+        let (module, pages) = lower_paged(source);
+        VecDocument { module, pages }.to_multi()
+    }
+
+    #[test]
+    fn test_diff_documents() {
+        let old = document("Same page\n#pagebreak()\nOld text");
+        let new = document("Same page\n#pagebreak()\nNew text");
+
+        let diff = diff_documents(&old, &new);
+        let status = diff.pages.iter().map(|p| p.status).collect::<Vec<_>>();
+        assert_eq!(status, [PageStatus::Unchanged, PageStatus::Changed]);
+        assert!(diff.pages[0].added.is_empty() && diff.pages[0].removed.is_empty());
+
+        let changed = diff.changed_pages().collect::<Vec<_>>();
+        assert_eq!(changed.len(), 1);
+        let page = changed[0];
+        assert_eq!(page.page, 1);
+
+        let texts = |items: &[DiffItem]| {
+            let texts = items.iter().filter(|item| item.kind == DiffItemKind::Text);
+            texts
+                .filter_map(|item| item.text.clone())
+                .collect::<String>()
+        };
+        assert!(texts(&page.added).contains("New"), "{page:?}");
+        assert!(texts(&page.removed).contains("Old"), "{page:?}");
+        assert!(!page.regions.is_empty());
+        for [x, y, w, h] in page.regions.iter().copied() {
+            assert!(w > 0. && h > 0., "{page:?}");
+            assert!(0. <= x && x + w <= page.size[0], "{page:?}");
+            assert!(0. <= y && y + h <= page.size[1], "{page:?}");
+        }
+
+        assert!(diff_documents(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_merge_regions() {
+        let regions = merge_regions(
+            [
+                [0., 0., 10., 10.],
+                [20., 0., 10., 10.],
+                [5., 5., 20., 2.],
+                [100., 100., 1., 1.],
+            ]
+            .into_iter(),
+        );
+        assert_eq!(regions, [[0., 0., 30., 10.], [100., 100., 1., 1.]]);
+    }
+}
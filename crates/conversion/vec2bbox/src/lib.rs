@@ -1,3 +1,5 @@
+pub mod diff;
+
 use std::collections::HashMap;
 
 use tiny_skia as sk;
@@ -19,7 +21,6 @@ impl Vec2BBoxPass {
         }
 
         let bbox = self.bbox_of_(module, v, ts);
-        eprintln!("bbox_of({v:?}, {ts:?}) = {bbox:?}");
         self.bbox_caches.insert((v, ts), bbox);
         bbox
     }
@@ -9,6 +9,7 @@ mod ops;
 mod paint;
 #[cfg(feature = "rasterize_glyph")]
 mod pixglyph_canvas;
+mod resolve;
 mod utils;
 
 pub use bounds::{hit_canvas_bound_at, BBoxAt, CanvasBound};
@@ -18,6 +19,7 @@ pub use incr::*;
 use js_sys::Promise;
 pub use ops::*;
 pub use paint::*;
+pub use resolve::*;
 use web_sys::{Blob, HtmlImageElement, OffscreenCanvas, OffscreenCanvasRenderingContext2d};
 
 use std::{
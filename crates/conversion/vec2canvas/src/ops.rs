@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use reflexo_vec2bbox::Vec2BBoxPass;
 
-use crate::{utils::EmptyFuture, CanvasDevice, CanvasPaint};
+use crate::{image_draw_size, utils::EmptyFuture, CanvasDevice, CanvasPaint, FillRule, PathStyles};
 use ecow::EcoVec;
 
 use std::{
@@ -25,9 +25,7 @@ use web_sys::{
     CanvasWindingRule, ImageBitmap, OffscreenCanvas, OffscreenCanvasRenderingContext2d, Path2d,
 };
 
-use reflexo::vector::ir::{
-    self, FlatGlyphItem, Image, ImageItem, ImmutStr, PathStyle, Rect, Scalar,
-};
+use reflexo::vector::ir::{self, FlatGlyphItem, Image, ImageItem, ImmutStr, Rect, Scalar};
 
 use super::{rasterize_image, set_transform, BBoxAt, CanvasBBox, CanvasStateGuard};
 
@@ -861,43 +859,31 @@ impl CanvasOp for CanvasPathElem {
         }
         // map_err(map_err("CanvasRenderTask.BuildPath2d")
 
-        let mut fill_rule = None;
-        let mut stroke_width = 0.;
-
-        for style in &self.path_data.styles {
-            match style {
-                PathStyle::Fill(_) | PathStyle::Stroke(_) => {}
-                PathStyle::StrokeWidth(width) => {
-                    canvas.set_line_width(width.0 as f64);
-                    stroke_width = width.0;
-                }
-                PathStyle::StrokeLineCap(cap) => {
-                    canvas.set_line_cap(cap);
-                }
-                PathStyle::StrokeLineJoin(join) => {
-                    canvas.set_line_join(join);
-                }
-                PathStyle::StrokeMitterLimit(limit) => {
-                    canvas.set_miter_limit(limit.0 as f64);
-                }
-                PathStyle::StrokeDashArray(array) => {
-                    let dash_array = js_sys::Array::from_iter(
-                        array.iter().map(|d| JsValue::from_f64(d.0 as f64)),
-                    );
-                    canvas.set_line_dash(&dash_array);
-                }
-                PathStyle::StrokeDashOffset(offset) => {
-                    canvas.set_line_dash_offset(offset.0 as f64);
-                }
-                PathStyle::FillRule(rule) => {
-                    fill_rule = match rule.as_ref() {
-                        "nonzero" => Some(CanvasWindingRule::Nonzero),
-                        "evenodd" => Some(CanvasWindingRule::Evenodd),
-                        _ => None,
-                    };
-                }
-            }
+        let styles = PathStyles::resolve(&self.path_data.styles);
+        if let Some(width) = styles.stroke_width {
+            canvas.set_line_width(width as f64);
         }
+        if let Some(cap) = &styles.line_cap {
+            canvas.set_line_cap(cap);
+        }
+        if let Some(join) = &styles.line_join {
+            canvas.set_line_join(join);
+        }
+        if let Some(limit) = styles.miter_limit {
+            canvas.set_miter_limit(limit as f64);
+        }
+        if let Some(array) = &styles.dash_array {
+            let dash_array =
+                js_sys::Array::from_iter(array.iter().map(|d| JsValue::from_f64(*d as f64)));
+            canvas.set_line_dash(&dash_array);
+        }
+        if let Some(offset) = styles.dash_offset {
+            canvas.set_line_dash_offset(offset as f64);
+        }
+        let fill_rule = styles.fill_rule.map(|rule| match rule {
+            FillRule::NonZero => CanvasWindingRule::Nonzero,
+            FillRule::EvenOdd => CanvasWindingRule::Evenodd,
+        });
 
         let path = self.path.get_or_init(&self.path_data.d);
 
@@ -918,7 +904,7 @@ impl CanvasOp for CanvasPathElem {
             }
         }
 
-        if stroke_width.abs() > 1e-5 {
+        if styles.has_stroke() {
             if let Some(stroke) = &self.stroke {
                 stroke.set_stroke_style(canvas, ts);
                 canvas.stroke_with_path(path);
@@ -975,17 +961,8 @@ impl CanvasImageElem {
             .unwrap();
 
         // resize image to fit the view
-        let (w, h) = {
-            let size = image_data.size;
-            let view_width = size.x.0;
-            let view_height = size.y.0;
-
-            let aspect = (image.width() as f32) / (image.height() as f32);
-
-            let w: f32 = view_width.max(aspect * view_height);
-            let h: f32 = w / aspect;
-            (w as f64, h as f64)
-        };
+        let (w, h) = image_draw_size(image_data);
+        let (w, h) = (w as f64, h as f64);
 
         let state = CanvasStateGuard::new(canvas);
         if !set_transform(canvas, ts) {
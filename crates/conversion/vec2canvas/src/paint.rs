@@ -142,14 +142,53 @@ impl CanvasPaint {
 }
 
 impl CanvasGradientPaint {
+    /// Gets the start and end points of a linear gradient, in the coordinate
+    /// space of the painted element.
+    pub fn linear_points(&self) -> Option<(f64, f64, f64, f64)> {
+        let GradientKind::Linear(angle) = self.gradient.kind else {
+            return None;
+        };
+
+        let transform = self.transform.unwrap_or_else(Transform::identity);
+        Some(linear_points(
+            angle.0,
+            transform,
+            self.resolved_aspect_ratio(),
+        ))
+    }
+
+    /// Gets the focal circle and the end circle of a radial gradient, as
+    /// `(x, y, radius)` in the unit space of the gradient, which is mapped to
+    /// the painted element by `transform`.
+    pub fn radial_circles(&self) -> Option<((f32, f32, f32), (f32, f32, f32))> {
+        let GradientKind::Radial(radius) = self.gradient.kind else {
+            return None;
+        };
+
+        let (center, focal_center, focal_radius) = radial_styles(&self.gradient);
+        Some((
+            (focal_center.x.0, focal_center.y.0, focal_radius.0),
+            (center.x.0, center.y.0, radius.0),
+        ))
+    }
+
+    /// Gets the color stops of the gradient, with the intermediate stops
+    /// sampled in the mixing color space.
+    pub fn color_stops(&self) -> Vec<(f32, Rgba8Item)> {
+        gradient_color_stops(&self.gradient)
+    }
+
+    fn resolved_aspect_ratio(&self) -> Option<f32> {
+        self.aspect_ratio
+            .or_else(|| self.transform.map(transform_aspect_ratio))
+    }
+
     fn to_canvas_gradient(
         &self,
         canvas: &dyn CanvasDevice,
         _ts: sk::Transform,
     ) -> Option<CanvasGradient> {
-        let aspect_ratio = self
-            .aspect_ratio
-            .or_else(|| self.transform.map(transform_aspect_ratio));
+        let aspect_ratio = self.resolved_aspect_ratio();
         create_canvas_gradient(canvas, &self.gradient, self.transform, aspect_ratio)
     }
 
@@ -257,10 +296,7 @@ impl CanvasGradientPaint {
             }
         }
 
-        let aspect_ratio = gradient_aspect_ratio(
-            self.aspect_ratio
-                .or_else(|| self.transform.map(transform_aspect_ratio)),
-        );
+        let aspect_ratio = gradient_aspect_ratio(self.resolved_aspect_ratio());
         let inverse_ratio = 1.0 / aspect_ratio;
 
         let rx = 2.0 * transform.sx.0.hypot(transform.ky.0);
@@ -318,17 +354,7 @@ impl CanvasGradientPaint {
         };
 
         let transform = self.transform.unwrap_or_else(Transform::identity);
-        let mut center = ir::Point::new(Scalar(0.5), Scalar(0.5));
-        let mut focal_center = ir::Point::new(Scalar(0.5), Scalar(0.5));
-        let mut focal_radius = Scalar(0.0);
-
-        for style in &self.gradient.styles {
-            match style {
-                GradientStyle::Center(c) => center = *c,
-                GradientStyle::FocalCenter(c) => focal_center = *c,
-                GradientStyle::FocalRadius(r) => focal_radius = *r,
-            }
-        }
+        let (center, focal_center, focal_radius) = radial_styles(&self.gradient);
 
         let Some(gradient) = canvas.create_radial_gradient(
             focal_center.x.0 as f64,
@@ -389,24 +415,11 @@ fn create_canvas_gradient(
     let transform = transform.unwrap_or_else(Transform::identity);
     let canvas_gradient = match gradient.kind {
         GradientKind::Linear(angle) => {
-            let (x1, y1, x2, y2) =
-                linear_gradient_points(angle.0, gradient_aspect_ratio(aspect_ratio));
-            let (x1, y1, x2, y2) = transform_linear_gradient(transform, x1, y1, x2, y2);
+            let (x1, y1, x2, y2) = linear_points(angle.0, transform, aspect_ratio);
             canvas.create_linear_gradient(x1, y1, x2, y2)
         }
         GradientKind::Radial(radius) => {
-            let mut center = ir::Point::new(Scalar(0.5), Scalar(0.5));
-            let mut focal_center = ir::Point::new(Scalar(0.5), Scalar(0.5));
-            let mut focal_radius = Scalar(0.0);
-
-            for style in &gradient.styles {
-                match style {
-                    GradientStyle::Center(c) => center = *c,
-                    GradientStyle::FocalCenter(c) => focal_center = *c,
-                    GradientStyle::FocalRadius(r) => focal_radius = *r,
-                }
-            }
-
+            let (center, focal_center, focal_radius) = radial_styles(gradient);
             let (fx, fy) =
                 transform_point(transform, focal_center.x.0 as f64, focal_center.y.0 as f64);
             let (cx, cy) = transform_point(transform, center.x.0 as f64, center.y.0 as f64);
@@ -437,12 +450,44 @@ fn create_canvas_gradient(
     Some(canvas_gradient)
 }
 
+fn linear_points(
+    angle: f32,
+    transform: Transform,
+    aspect_ratio: Option<f32>,
+) -> (f64, f64, f64, f64) {
+    let (x1, y1, x2, y2) = linear_gradient_points(angle, gradient_aspect_ratio(aspect_ratio));
+    transform_linear_gradient(transform, x1, y1, x2, y2)
+}
+
+fn radial_styles(gradient: &GradientItem) -> (ir::Point, ir::Point, Scalar) {
+    let mut center = ir::Point::new(Scalar(0.5), Scalar(0.5));
+    let mut focal_center = ir::Point::new(Scalar(0.5), Scalar(0.5));
+    let mut focal_radius = Scalar(0.0);
+
+    for style in &gradient.styles {
+        match style {
+            GradientStyle::Center(c) => center = *c,
+            GradientStyle::FocalCenter(c) => focal_center = *c,
+            GradientStyle::FocalRadius(r) => focal_radius = *r,
+        }
+    }
+
+    (center, focal_center, focal_radius)
+}
+
 fn append_color_stops(canvas_gradient: &CanvasGradient, gradient: &GradientItem) {
+    for (offset, color) in gradient_color_stops(gradient) {
+        add_color_stop(canvas_gradient, offset, rgba_to_css(color));
+    }
+}
+
+fn gradient_color_stops(gradient: &GradientItem) -> Vec<(f32, Rgba8Item)> {
+    let mut stops = vec![];
     for window in gradient.stops.windows(2) {
         let (start_c, start_t) = window[0];
         let (end_c, end_t) = window[1];
 
-        add_color_stop(canvas_gradient, start_t.0, rgba_to_css(start_c));
+        stops.push((start_t.0, start_c));
 
         let len = if gradient.anti_alias {
             (256 / gradient.stops.len() as u32).max(2)
@@ -453,15 +498,12 @@ fn append_color_stops(canvas_gradient: &CanvasGradient, gradient: &GradientItem)
         for i in 1..(len - 1) {
             let t0 = i as f32 / (len - 1) as f32;
             let t = start_t.0 + (end_t.0 - start_t.0) * t0;
-            add_color_stop(
-                canvas_gradient,
-                t,
-                typst_color_to_css(sample_color_stops(gradient, t)),
-            );
+            stops.push((t, typst_color_to_rgba(sample_color_stops(gradient, t))));
         }
 
-        add_color_stop(canvas_gradient, end_t.0, rgba_to_css(end_c));
+        stops.push((end_t.0, end_c));
     }
+    stops
 }
 
 fn add_color_stop(canvas_gradient: &CanvasGradient, offset: f32, color: String) {
@@ -525,8 +567,12 @@ fn color_space_to_typst(space: ColorSpace) -> Option<TypstColorSpace> {
 }
 
 fn typst_color_to_css(color: TypstColor) -> String {
+    rgba_to_css(typst_color_to_rgba(color))
+}
+
+fn typst_color_to_rgba(color: TypstColor) -> Rgba8Item {
     let (r, g, b, a) = color.to_rgb().into_format::<u8, u8>().into_components();
-    rgba_to_css(Rgba8Item { r, g, b, a })
+    Rgba8Item { r, g, b, a }
 }
 
 fn rgba_to_css(color: Rgba8Item) -> String {
@@ -0,0 +1,70 @@
+//! Resolves the paths and images of canvas elements into plain values.
+//!
+//! The resolution doesn't touch `web_sys`, so that it is shared by the
+//! browser canvas and the native backends, e.g. `reflexo-vec2raster`.
+
+use reflexo::vector::ir::{ImageItem, ImmutStr, PathStyle};
+
+/// The fill rule of a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+/// The styles of a path, in which the unset styles are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathStyles {
+    pub fill_rule: Option<FillRule>,
+    pub stroke_width: Option<f32>,
+    pub line_cap: Option<ImmutStr>,
+    pub line_join: Option<ImmutStr>,
+    pub miter_limit: Option<f32>,
+    pub dash_array: Option<Vec<f32>>,
+    pub dash_offset: Option<f32>,
+}
+
+impl PathStyles {
+    /// Resolves the styles of a path item.
+    pub fn resolve(styles: &[PathStyle]) -> Self {
+        let mut resolved = Self::default();
+        for style in styles {
+            match style {
+                PathStyle::Fill(_) | PathStyle::Stroke(_) => {}
+                PathStyle::StrokeWidth(width) => resolved.stroke_width = Some(width.0),
+                PathStyle::StrokeLineCap(cap) => resolved.line_cap = Some(cap.clone()),
+                PathStyle::StrokeLineJoin(join) => resolved.line_join = Some(join.clone()),
+                PathStyle::StrokeMitterLimit(limit) => resolved.miter_limit = Some(limit.0),
+                PathStyle::StrokeDashArray(array) => {
+                    resolved.dash_array = Some(array.iter().map(|d| d.0).collect());
+                }
+                PathStyle::StrokeDashOffset(offset) => resolved.dash_offset = Some(offset.0),
+                PathStyle::FillRule(rule) => {
+                    resolved.fill_rule = match rule.as_ref() {
+                        "nonzero" => Some(FillRule::NonZero),
+                        "evenodd" => Some(FillRule::EvenOdd),
+                        _ => None,
+                    };
+                }
+            }
+        }
+        resolved
+    }
+
+    /// Whether the path is stroked, i.e. the stroke width is not zero.
+    pub fn has_stroke(&self) -> bool {
+        self.stroke_width.is_some_and(|width| width.abs() > 1e-5)
+    }
+}
+
+/// Gets the size to draw an image in, which covers the view of the image item
+/// and keeps the aspect ratio of the image.
+pub fn image_draw_size(image_data: &ImageItem) -> (f32, f32) {
+    let image = &image_data.image;
+    let size = image_data.size;
+
+    let aspect = (image.width() as f32) / (image.height() as f32);
+    let w = size.x.0.max(aspect * size.y.0);
+    let h = w / aspect;
+    (w, h)
+}
@@ -0,0 +1,1238 @@
+//! Render vector items into PDF.
+//!
+//! The glyphs stored in the [`Module`] are embedded as Type3 fonts, so the
+//! text in the PDF stays selectable and searchable without the original font
+//! files. Linear and radial gradients are painted with shading patterns,
+//! whose colors are interpolated in sRGB without alpha. Conic gradients,
+//! tiling patterns and svg images are not supported yet, which are reported
+//! as warnings, and the paints fall back to solid colors.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use pdf_writer::types::{
+    ActionType, AnnotationType, ColorSpaceOperand, FunctionShadingType, LineCapStyle,
+    LineJoinStyle, SystemInfo, UnicodeCmap,
+};
+use pdf_writer::writers::Resources;
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use reflexo::error::prelude::*;
+use reflexo::hash::Fingerprint;
+use reflexo::vector::ir::*;
+use tiny_skia_path as sk;
+
+/// The system info of the ToUnicode CMaps.
+const SYSTEM_INFO: SystemInfo = SystemInfo {
+    registry: Str(b"Adobe"),
+    ordering: Str(b"Identity"),
+    supplement: 0,
+};
+
+/// The prefix of the links to a location in the document.
+const LOCATION_PREFIX: &str = "@typst:handleTypstLocation(this, ";
+
+/// A color in RGBA.
+type Rgba = [f32; 4];
+
+const BLACK: Rgba = [0.0, 0.0, 0.0, 1.0];
+
+/// Renders the pages of a module into a PDF document.
+pub fn render_pdf(module: &Module, pages: &[Page]) -> Result<Vec<u8>> {
+    Vec2PdfPass::new(module).render(pages)
+}
+
+/// A paint resolved from a paint reference of the module.
+#[derive(Clone, Copy)]
+enum Paint<'m> {
+    Solid(Rgba),
+    /// A gradient and the transform from the unit space of the gradient into
+    /// the space of the painted item.
+    Gradient(&'m GradientItem, sk::Transform),
+}
+
+/// A Type3 font holding at most 256 glyphs of a [`FontItem`], since glyphs
+/// are addressed by single-byte codes in a Type3 font.
+struct PdfFont {
+    id: Ref,
+    name: String,
+    font_idx: usize,
+    /// The glyph ids indexed by codes.
+    glyphs: Vec<u32>,
+    /// The advances indexed by codes, in font units.
+    widths: Vec<f32>,
+    /// The texts indexed by codes, if known.
+    texts: Vec<Option<String>>,
+}
+
+/// A link with its bbox on the page.
+struct PageLink {
+    rect: sk::Rect,
+    href: ImmutStr,
+}
+
+/// Lower vector items into a PDF document.
+pub struct Vec2PdfPass<'m> {
+    module: &'m Module,
+    pdf: Pdf,
+    alloc: Ref,
+
+    page_tree: Ref,
+    /// The pages, which are allocated ahead to resolve links to locations.
+    pages: Vec<(Ref, Size)>,
+    /// The height of the page being rendered.
+    page_height: f32,
+    /// The links on the page being rendered.
+    links: Vec<PageLink>,
+
+    /// The XObjects of images, or `None` if the image cannot be embedded.
+    images: HashMap<Fingerprint, Option<(String, Ref)>>,
+    /// The graphics states setting alpha, by `(is_stroke, alpha)`.
+    alphas: HashMap<(bool, u8), (String, Ref)>,
+    /// The shading patterns painting gradients.
+    shadings: Vec<(String, Ref)>,
+    /// The Type3 fonts.
+    fonts: Vec<PdfFont>,
+    /// The Type3 font to add new glyphs of a [`FontItem`] into.
+    open_fonts: HashMap<usize, usize>,
+    /// The Type3 font and the code of glyphs, by `(font_idx, glyph_id)`.
+    glyph_codes: HashMap<(usize, u32), (usize, u8)>,
+}
+
+impl<'m> Vec2PdfPass<'m> {
+    pub fn new(module: &'m Module) -> Self {
+        let mut alloc = Ref::new(1);
+        let page_tree = alloc.bump();
+
+        Self {
+            module,
+            pdf: Pdf::new(),
+            alloc,
+            page_tree,
+            pages: vec![],
+            page_height: 0.0,
+            links: vec![],
+            images: HashMap::new(),
+            alphas: HashMap::new(),
+            shadings: vec![],
+            fonts: vec![],
+            open_fonts: HashMap::new(),
+            glyph_codes: HashMap::new(),
+        }
+    }
+
+    /// Renders the pages into a PDF document.
+    pub fn render(mut self, pages: &[Page]) -> Result<Vec<u8>> {
+        let catalog = self.alloc.bump();
+        let resources = self.alloc.bump();
+        self.pages = pages.iter().map(|p| (self.alloc.bump(), p.size)).collect();
+
+        for (idx, page) in pages.iter().enumerate() {
+            self.render_page(idx, page, resources)?;
+        }
+
+        self.write_fonts()?;
+        self.write_resources(resources);
+
+        self.pdf.catalog(catalog).pages(self.page_tree);
+        self.pdf
+            .pages(self.page_tree)
+            .kids(self.pages.iter().map(|(id, _)| *id))
+            .count(self.pages.len() as i32);
+
+        Ok(self.pdf.finish())
+    }
+
+    fn render_page(&mut self, idx: usize, page: &Page, resources: Ref) -> Result<()> {
+        let (id, size) = self.pages[idx];
+        let (width, height) = (size.x.0, size.y.0);
+        self.page_height = height;
+
+        let mut content = Content::new();
+        // Vector items are placed from the top-left corner of the page.
+        content.transform([1.0, 0.0, 0.0, -1.0, 0.0, height]);
+        self.render_item(&mut content, sk::Transform::identity(), &page.content)?;
+        let content = deflate(&content.finish())?;
+
+        let content_id = self.alloc.bump();
+        self.pdf
+            .stream(content_id, &content)
+            .filter(Filter::FlateDecode);
+
+        let annotations = std::mem::take(&mut self.links)
+            .into_iter()
+            .filter_map(|link| self.write_link(height, link))
+            .collect::<Vec<_>>();
+
+        let mut pdf_page = self.pdf.page(id);
+        pdf_page
+            .parent(self.page_tree)
+            .media_box(Rect::new(0.0, 0.0, width, height))
+            .contents(content_id)
+            .pair(Name(b"Resources"), resources);
+        if !annotations.is_empty() {
+            pdf_page.insert(Name(b"Annots")).array().items(annotations);
+        }
+        Ok(())
+    }
+
+    /// Renders an item, where `ts` transforms the item into the page.
+    fn render_item(
+        &mut self,
+        content: &mut Content,
+        ts: sk::Transform,
+        abs_ref: &Fingerprint,
+    ) -> Result<()> {
+        let module = self.module;
+        let Some(item) = module.get_item(abs_ref) else {
+            log::warn!("vec2pdf: item not found: {abs_ref:?}");
+            return Ok(());
+        };
+
+        match item {
+            VecItem::Group(group) => {
+                for (pos, item) in group.0.iter() {
+                    let (x, y) = (pos.x.0, pos.y.0);
+                    content.save_state();
+                    content.transform([1.0, 0.0, 0.0, 1.0, x, y]);
+                    self.render_item(content, ts.pre_translate(x, y), item)?;
+                    content.restore_state();
+                }
+            }
+            VecItem::Item(transformed) => {
+                content.save_state();
+                let ts = match &transformed.0 {
+                    TransformItem::Clip(path) => {
+                        if let Some(sk_path) = convert_path(&path.d) {
+                            emit_path(content, &sk_path);
+                            if is_even_odd(&path.styles) {
+                                content.clip_even_odd();
+                            } else {
+                                content.clip_nonzero();
+                            }
+                            content.end_path();
+                        }
+                        ts
+                    }
+                    transform => {
+                        let transform = convert_transform(transform);
+                        content.transform(to_matrix(transform));
+                        ts.pre_concat(transform)
+                    }
+                };
+                self.render_item(content, ts, &transformed.1)?;
+                content.restore_state();
+            }
+            VecItem::Labelled(labelled) => self.render_item(content, ts, &labelled.1)?,
+            VecItem::Text(text) => self.render_text(content, ts, text)?,
+            VecItem::Path(path) => self.render_path(content, ts, path),
+            VecItem::Image(image) => self.render_image(content, image)?,
+            VecItem::Link(link) => {
+                let rect = sk::Rect::from_xywh(0.0, 0.0, link.size.x.0, link.size.y.0);
+                if let Some(rect) = rect.and_then(|rect| rect.transform(ts)) {
+                    self.links.push(PageLink {
+                        rect,
+                        href: link.href.clone(),
+                    });
+                }
+            }
+            VecItem::None
+            | VecItem::ContentHint(..)
+            | VecItem::ColorTransform(..)
+            | VecItem::Pattern(..)
+            | VecItem::Gradient(..)
+            | VecItem::Color32(..)
+            | VecItem::SizedRawHtml(..)
+            | VecItem::Html(..) => {}
+        }
+        Ok(())
+    }
+
+    fn render_path(&mut self, content: &mut Content, ts: sk::Transform, path: &PathItem) {
+        let Some(sk_path) = convert_path(&path.d) else {
+            return;
+        };
+
+        let mut fill = None;
+        let mut stroke = None;
+        let mut dash_array = None;
+        let mut dash_offset = 0.0;
+
+        content.save_state();
+        for style in path.styles.iter() {
+            match style {
+                PathStyle::Fill(color) => fill = self.resolve_paint(color),
+                PathStyle::Stroke(color) => stroke = self.resolve_paint(color),
+                PathStyle::StrokeWidth(width) => {
+                    content.set_line_width(width.0);
+                }
+                PathStyle::StrokeLineCap(cap) => {
+                    content.set_line_cap(match cap.as_ref() {
+                        "round" => LineCapStyle::RoundCap,
+                        "square" => LineCapStyle::ProjectingSquareCap,
+                        _ => LineCapStyle::ButtCap,
+                    });
+                }
+                PathStyle::StrokeLineJoin(join) => {
+                    content.set_line_join(match join.as_ref() {
+                        "round" => LineJoinStyle::RoundJoin,
+                        "bevel" => LineJoinStyle::BevelJoin,
+                        _ => LineJoinStyle::MiterJoin,
+                    });
+                }
+                PathStyle::StrokeMitterLimit(limit) => {
+                    content.set_miter_limit(limit.0);
+                }
+                PathStyle::StrokeDashArray(array) => dash_array = Some(array),
+                PathStyle::StrokeDashOffset(offset) => dash_offset = offset.0,
+                PathStyle::FillRule(..) => {}
+            }
+        }
+        if let Some(array) = dash_array {
+            content.set_dash_pattern(array.iter().map(|v| v.0), dash_offset);
+        }
+        if let Some(fill) = fill {
+            self.set_paint(content, fill, ts, false);
+        }
+        if let Some(stroke) = stroke {
+            self.set_paint(content, stroke, ts, true);
+        }
+
+        emit_path(content, &sk_path);
+        match (fill.is_some(), stroke.is_some(), is_even_odd(&path.styles)) {
+            (true, true, false) => content.fill_nonzero_and_stroke(),
+            (true, true, true) => content.fill_even_odd_and_stroke(),
+            (true, false, false) => content.fill_nonzero(),
+            (true, false, true) => content.fill_even_odd(),
+            (false, true, _) => content.stroke(),
+            (false, false, _) => content.end_path(),
+        };
+        content.restore_state();
+    }
+
+    fn render_text(
+        &mut self,
+        content: &mut Content,
+        ts: sk::Transform,
+        text: &TextItem,
+    ) -> Result<()> {
+        let module = self.module;
+        let font_idx = text.shape.font.idx as usize;
+        let Some(font) = module.fonts.get(font_idx) else {
+            log::warn!("vec2pdf: font not found: {:?}", text.shape.font);
+            return Ok(());
+        };
+        let size = text.shape.size.0;
+        let ppem = text.shape.ppem(font.units_per_em.0).0;
+
+        let fill = text.shape.styles.iter().find_map(|style| match style {
+            PathStyle::Fill(color) => Some(self.resolve_paint(color)),
+            _ => None,
+        });
+        let Some(fill) = fill.unwrap_or(Some(Paint::Solid(BLACK))) else {
+            return Ok(());
+        };
+
+        // The text of a glyph is only known if the glyphs correspond to the
+        // characters one by one, or the whole text is shaped into one glyph.
+        // Otherwise, e.g. for ligatures, the text is attached to the whole
+        // run as its actual text.
+        let glyphs = &text.content.glyphs;
+        let chars = text.content.content.chars().collect::<Vec<_>>();
+        let mapped = glyphs.len() == 1 || glyphs.len() == chars.len();
+        let glyph_text = |idx: usize| match glyphs.len() {
+            1 => Some(text.content.content.to_string()),
+            len if len == chars.len() => Some(chars[idx].to_string()),
+            _ => None,
+        };
+
+        content.save_state();
+        self.set_paint(content, fill, ts, false);
+
+        let actual_text = !mapped && !chars.is_empty();
+        if actual_text {
+            content
+                .begin_marked_content_with_properties(Name(b"Span"))
+                .properties()
+                .actual_text(TextStr(&text.content.content));
+        }
+
+        let mut image_glyphs = vec![];
+        let mut current_font = None;
+        let (mut adv_x, mut adv_y) = (0f32, 0f32);
+        content.begin_text();
+        for (idx, (offset, advance, glyph)) in glyphs.iter().enumerate() {
+            let x = adv_x + offset.x.0;
+            let y = adv_y + offset.y.0;
+            adv_x += advance.x.0;
+            adv_y += advance.y.0;
+
+            match font.get_glyph(*glyph).map(AsRef::as_ref) {
+                Some(FlatGlyphItem::Outline(..)) => {}
+                Some(FlatGlyphItem::Image(image)) => {
+                    image_glyphs.push((x, y, image));
+                    continue;
+                }
+                Some(FlatGlyphItem::None) | None => continue,
+            }
+
+            let width = advance.x.0 / ppem;
+            let (pdf_font, code) = self.glyph_code(font_idx, *glyph, width, glyph_text(idx));
+            if current_font != Some(pdf_font) {
+                content.set_font(Name(self.fonts[pdf_font].name.as_bytes()), size);
+                current_font = Some(pdf_font);
+            }
+
+            // Glyphs are flipped back, since the y-axis of the page is flipped.
+            content.set_text_matrix([1.0, 0.0, 0.0, -1.0, x, -y]);
+            content.show(Str(&[code]));
+        }
+        content.end_text();
+
+        for (x, y, glyph) in image_glyphs {
+            content.save_state();
+            content.transform([ppem, 0.0, 0.0, -ppem, x, -y]);
+            content.transform(to_matrix(glyph.ts.into()));
+            self.render_image(content, &glyph.image)?;
+            content.restore_state();
+        }
+
+        if actual_text {
+            content.end_marked_content();
+        }
+        content.restore_state();
+        Ok(())
+    }
+
+    fn render_image(&mut self, content: &mut Content, image: &ImageItem) -> Result<()> {
+        let Some(name) = self.image_name(&image.image)? else {
+            return Ok(());
+        };
+
+        let (w, h) = (image.size.x.0, image.size.y.0);
+        content.save_state();
+        // Images are painted into the unit square, whose y-axis points up.
+        content.transform([w, 0.0, 0.0, -h, 0.0, h]);
+        content.x_object(Name(name.as_bytes()));
+        content.restore_state();
+        Ok(())
+    }
+
+    /// Gets the Type3 font and the code of a glyph, adding the glyph into a
+    /// Type3 font if it is not added yet.
+    fn glyph_code(
+        &mut self,
+        font_idx: usize,
+        glyph: u32,
+        width: f32,
+        text: Option<String>,
+    ) -> (usize, u8) {
+        if let Some(&(pdf_font, code)) = self.glyph_codes.get(&(font_idx, glyph)) {
+            let slot = &mut self.fonts[pdf_font].texts[code as usize];
+            if slot.is_none() {
+                *slot = text;
+            }
+            return (pdf_font, code);
+        }
+
+        let pdf_font = match self.open_fonts.get(&font_idx) {
+            Some(&pdf_font) if self.fonts[pdf_font].glyphs.len() < 256 => pdf_font,
+            _ => {
+                let pdf_font = self.fonts.len();
+                self.fonts.push(PdfFont {
+                    id: self.alloc.bump(),
+                    name: format!("F{pdf_font}"),
+                    font_idx,
+                    glyphs: vec![],
+                    widths: vec![],
+                    texts: vec![],
+                });
+                self.open_fonts.insert(font_idx, pdf_font);
+                pdf_font
+            }
+        };
+
+        let f = &mut self.fonts[pdf_font];
+        let code = f.glyphs.len() as u8;
+        f.glyphs.push(glyph);
+        f.widths.push(width);
+        f.texts.push(text);
+        self.glyph_codes.insert((font_idx, glyph), (pdf_font, code));
+        (pdf_font, code)
+    }
+
+    /// Resolves a paint, or `None` if nothing is painted. The unsupported
+    /// paints are reported and fall back to solid colors.
+    fn resolve_paint(&self, paint: &str) -> Option<Paint<'m>> {
+        if paint.starts_with("@g") {
+            let Some((gradient, transform)) = self.resolve_gradient(paint) else {
+                log::warn!("vec2pdf: unsupported paint: {paint}");
+                return Some(Paint::Solid(BLACK));
+            };
+            let Some((color, _)) = gradient.stops.first() else {
+                log::warn!("vec2pdf: gradient without stops: {paint}");
+                return Some(Paint::Solid(BLACK));
+            };
+            if matches!(gradient.kind, GradientKind::Conic(..)) {
+                log::warn!("vec2pdf: conic gradients are not supported yet: {paint}");
+                return Some(Paint::Solid(rgba8(color)));
+            }
+            return Some(Paint::Gradient(gradient, transform));
+        }
+
+        if paint.starts_with('@') {
+            log::warn!("vec2pdf: unsupported paint: {paint}");
+            return Some(Paint::Solid(BLACK));
+        }
+
+        match paint {
+            "none" | "transparent" => None,
+            _ => Some(Paint::Solid(parse_hex_color(paint).unwrap_or(BLACK))),
+        }
+    }
+
+    /// Resolves a gradient and its transform, or `None` if the paint is not a
+    /// gradient, e.g. a tiling pattern.
+    fn resolve_gradient(&self, paint: &str) -> Option<(&'m GradientItem, sk::Transform)> {
+        let module = self.module;
+        let id = paint.strip_prefix("@g")?;
+        let mut id = Fingerprint::try_from_str(id).ok()?;
+
+        let mut transform = sk::Transform::identity();
+        if let VecItem::ColorTransform(color_transform) = module.get_item(&id)? {
+            id = color_transform.item;
+            transform = color_transform.transform.into();
+        }
+
+        match module.get_item(&id)? {
+            VecItem::Gradient(gradient) => Some((gradient, transform)),
+            _ => None,
+        }
+    }
+
+    /// Sets the paint, where `ts` transforms the painted item into the page.
+    fn set_paint(
+        &mut self,
+        content: &mut Content,
+        paint: Paint,
+        ts: sk::Transform,
+        is_stroke: bool,
+    ) {
+        let (gradient, transform) = match paint {
+            Paint::Solid(color) => return self.set_color(content, color, is_stroke),
+            Paint::Gradient(gradient, transform) => (gradient, transform),
+        };
+
+        // The space of patterns is the default space of the page, whose y-axis
+        // points up.
+        let page_ts = sk::Transform::from_row(1.0, 0.0, 0.0, -1.0, 0.0, self.page_height);
+        let matrix = page_ts.pre_concat(ts).pre_concat(transform);
+        let name = self.write_shading(gradient, transform, matrix);
+        if is_stroke {
+            content.set_stroke_color_space(ColorSpaceOperand::Pattern);
+            content.set_stroke_pattern(None, Name(name.as_bytes()));
+        } else {
+            content.set_fill_color_space(ColorSpaceOperand::Pattern);
+            content.set_fill_pattern(None, Name(name.as_bytes()));
+        }
+    }
+
+    /// Writes a shading pattern painting the gradient in its unit space, which
+    /// is mapped into the page by `matrix`.
+    fn write_shading(
+        &mut self,
+        gradient: &GradientItem,
+        transform: sk::Transform,
+        matrix: sk::Transform,
+    ) -> String {
+        let function = self.write_gradient_function(gradient);
+
+        let id = self.alloc.bump();
+        let name = format!("Sh{}", self.shadings.len());
+        let mut pattern = self.pdf.shading_pattern(id);
+        let mut shading = pattern.function_shading();
+        match gradient.kind {
+            GradientKind::Linear(angle) => {
+                let (x1, y1, x2, y2) = linear_coords(angle.0, aspect_ratio(transform));
+                shading
+                    .shading_type(FunctionShadingType::Axial)
+                    .coords([x1, y1, x2, y2]);
+            }
+            GradientKind::Radial(radius) => {
+                let (center, focal_center, focal_radius) = radial_styles(gradient);
+                shading.shading_type(FunctionShadingType::Radial).coords([
+                    focal_center.x.0,
+                    focal_center.y.0,
+                    focal_radius.0,
+                    center.x.0,
+                    center.y.0,
+                    radius.0,
+                ]);
+            }
+            GradientKind::Conic(..) => unreachable!("conic gradients are painted as solid colors"),
+        }
+        shading.color_space().device_rgb();
+        shading
+            .function(function)
+            .extend([true, true])
+            .anti_alias(gradient.anti_alias);
+        shading.finish();
+        pattern.matrix(to_matrix(matrix));
+        pattern.finish();
+
+        self.shadings.push((name.clone(), id));
+        name
+    }
+
+    /// Writes the function mapping the position in a gradient to its color,
+    /// which interpolates the stops linearly.
+    fn write_gradient_function(&mut self, gradient: &GradientItem) -> Ref {
+        let stops = &gradient.stops;
+        let color = |(color, _): &(Rgba8Item, Scalar)| {
+            let [r, g, b, _] = rgba8(color);
+            [r, g, b]
+        };
+
+        if stops.len() <= 2 {
+            let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+                unreachable!("gradients are resolved with stops");
+            };
+            let id = self.alloc.bump();
+            self.write_exponential(id, color(first), color(last));
+            return id;
+        }
+
+        let functions = stops
+            .windows(2)
+            .map(|window| {
+                let id = self.alloc.bump();
+                self.write_exponential(id, color(&window[0]), color(&window[1]));
+                id
+            })
+            .collect::<Vec<_>>();
+
+        let id = self.alloc.bump();
+        let mut function = self.pdf.stitching_function(id);
+        function
+            .domain([0.0, 1.0])
+            .range([0.0, 1.0, 0.0, 1.0, 0.0, 1.0])
+            .functions(functions.iter().copied())
+            .bounds(stops[1..stops.len() - 1].iter().map(|(_, t)| t.0))
+            .encode(functions.iter().flat_map(|_| [0.0, 1.0]));
+        id
+    }
+
+    fn write_exponential(&mut self, id: Ref, c0: [f32; 3], c1: [f32; 3]) {
+        self.pdf
+            .exponential_function(id)
+            .domain([0.0, 1.0])
+            .range([0.0, 1.0, 0.0, 1.0, 0.0, 1.0])
+            .c0(c0)
+            .c1(c1)
+            .n(1.0);
+    }
+
+    fn set_color(&mut self, content: &mut Content, color: Rgba, is_stroke: bool) {
+        let [r, g, b, a] = color;
+        if is_stroke {
+            content.set_stroke_rgb(r, g, b);
+        } else {
+            content.set_fill_rgb(r, g, b);
+        }
+
+        let alpha = (a * 255.0).round().clamp(0.0, 255.0) as u8;
+        if alpha == 255 {
+            return;
+        }
+
+        let state_count = self.alphas.len();
+        let (name, _) = self.alphas.entry((is_stroke, alpha)).or_insert_with(|| {
+            let id = self.alloc.bump();
+            let mut state = self.pdf.ext_graphics(id);
+            if is_stroke {
+                state.stroking_alpha(alpha as f32 / 255.0);
+            } else {
+                state.non_stroking_alpha(alpha as f32 / 255.0);
+            }
+            (format!("Gs{state_count}"), id)
+        });
+        content.set_parameters(Name(name.as_bytes()));
+    }
+
+    /// Gets the name of the XObject of an image, writing the XObject if it is
+    /// not written yet.
+    fn image_name(&mut self, image: &Arc<Image>) -> Result<Option<String>> {
+        if let Some(entry) = self.images.get(&image.hash) {
+            return Ok(entry.as_ref().map(|(name, _)| name.clone()));
+        }
+
+        let name = format!("Im{}", self.images.len());
+        let entry = self.write_image(image)?.map(|id| (name, id));
+        let name = entry.as_ref().map(|(name, _)| name.clone());
+        self.images.insert(image.hash, entry);
+        Ok(name)
+    }
+
+    fn write_image(&mut self, image: &Image) -> Result<Option<Ref>> {
+        if image.format.as_ref() == "svg+xml" {
+            log::warn!("vec2pdf: svg images are not supported yet");
+            return Ok(None);
+        }
+
+        let decoded = match image::load_from_memory(&image.data) {
+            Ok(decoded) => decoded,
+            Err(err) => {
+                log::warn!("vec2pdf: failed to decode {} image: {err}", image.format);
+                return Ok(None);
+            }
+        };
+        let (width, height) = (decoded.width() as i32, decoded.height() as i32);
+        let color = decoded.color();
+
+        // Embeds jpeg images as is, if PDF readers are able to decode them.
+        if image.format.as_ref() == "jpeg"
+            && matches!(color, image::ColorType::L8 | image::ColorType::Rgb8)
+        {
+            let id = self.alloc.bump();
+            let mut x_object = self.pdf.image_xobject(id, &image.data);
+            x_object.width(width).height(height).bits_per_component(8);
+            x_object.filter(Filter::DctDecode);
+            if color == image::ColorType::L8 {
+                x_object.color_space().device_gray();
+            } else {
+                x_object.color_space().device_rgb();
+            }
+            return Ok(Some(id));
+        }
+
+        let mask = if color.has_alpha() {
+            let alpha = decoded.to_luma_alpha8();
+            let alpha = alpha.pixels().map(|p| p.0[1]).collect::<Vec<_>>();
+
+            let id = self.alloc.bump();
+            let mut mask = self.pdf.image_xobject(id, &deflate(&alpha)?);
+            mask.width(width).height(height).bits_per_component(8);
+            mask.filter(Filter::FlateDecode);
+            mask.color_space().device_gray();
+            Some(id)
+        } else {
+            None
+        };
+
+        let id = self.alloc.bump();
+        let rgb = deflate(decoded.to_rgb8().as_raw())?;
+        let mut x_object = self.pdf.image_xobject(id, &rgb);
+        x_object.width(width).height(height).bits_per_component(8);
+        x_object.filter(Filter::FlateDecode);
+        x_object.color_space().device_rgb();
+        if let Some(mask) = mask {
+            x_object.s_mask(mask);
+        }
+        Ok(Some(id))
+    }
+
+    fn write_fonts(&mut self) -> Result<()> {
+        let module = self.module;
+        let fonts = std::mem::take(&mut self.fonts);
+        for f in fonts.iter() {
+            let font = &module.fonts[f.font_idx];
+            let upem = font.units_per_em.0;
+
+            let procs = (f.glyphs.iter().zip(f.widths.iter()))
+                .map(|(&glyph, &width)| {
+                    let id = self.alloc.bump();
+                    let data = deflate(&glyph_proc(font, glyph, width))?;
+                    self.pdf.stream(id, &data).filter(Filter::FlateDecode);
+                    Ok((format!("g{glyph}"), id))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let cmap_id = self.alloc.bump();
+            let mut cmap = UnicodeCmap::<u8>::new(Name(b"Custom"), SYSTEM_INFO);
+            for (code, text) in f.texts.iter().enumerate() {
+                if let Some(text) = text {
+                    cmap.pair_with_multiple(code as u8, text.chars());
+                }
+            }
+            self.pdf.cmap(cmap_id, &cmap.finish());
+
+            let mut pdf_font = self.pdf.type3_font(f.id);
+            pdf_font
+                .name(Name(f.name.as_bytes()))
+                .bbox(Rect::new(0.0, 0.0, 0.0, 0.0))
+                .matrix([1.0 / upem, 0.0, 0.0, 1.0 / upem, 0.0, 0.0])
+                .first_char(0)
+                .last_char((f.glyphs.len() - 1) as u8)
+                .widths(f.widths.iter().copied())
+                .to_unicode(cmap_id);
+            pdf_font
+                .encoding_custom()
+                .differences()
+                .consecutive(0, procs.iter().map(|(name, _)| Name(name.as_bytes())));
+            pdf_font
+                .char_procs()
+                .pairs(procs.iter().map(|(name, id)| (Name(name.as_bytes()), *id)));
+        }
+        self.fonts = fonts;
+        Ok(())
+    }
+
+    fn write_resources(&mut self, id: Ref) {
+        let mut resources = self.pdf.indirect(id).start::<Resources>();
+
+        let mut fonts = resources.fonts();
+        for f in self.fonts.iter() {
+            fonts.pair(Name(f.name.as_bytes()), f.id);
+        }
+        fonts.finish();
+
+        let mut x_objects = resources.x_objects();
+        for (name, id) in self.images.values().flatten() {
+            x_objects.pair(Name(name.as_bytes()), *id);
+        }
+        x_objects.finish();
+
+        let mut states = resources.ext_g_states();
+        for (name, id) in self.alphas.values() {
+            states.pair(Name(name.as_bytes()), *id);
+        }
+        states.finish();
+
+        let mut patterns = resources.patterns();
+        for (name, id) in self.shadings.iter() {
+            patterns.pair(Name(name.as_bytes()), *id);
+        }
+        patterns.finish();
+    }
+
+    fn write_link(&mut self, page_height: f32, link: PageLink) -> Option<Ref> {
+        let destination = match link.href.strip_prefix(LOCATION_PREFIX) {
+            Some(location) => {
+                let mut args = location.trim_end_matches(')').split(", ");
+                let page = args.next()?.parse::<usize>().ok()?.checked_sub(1)?;
+                let x = args.next()?.parse::<f32>().ok()?;
+                let y = args.next()?.parse::<f32>().ok()?;
+                let (page_id, size) = *self.pages.get(page)?;
+                Some((page_id, x, size.y.0 - y))
+            }
+            // Other actions of the renderer cannot be represented in PDF.
+            None if link.href.starts_with('@') => return None,
+            None => None,
+        };
+
+        let id = self.alloc.bump();
+        let rect = link.rect;
+        let mut annotation = self.pdf.annotation(id);
+        annotation
+            .subtype(AnnotationType::Link)
+            .rect(Rect::new(
+                rect.left(),
+                page_height - rect.bottom(),
+                rect.right(),
+                page_height - rect.top(),
+            ))
+            .border(0.0, 0.0, 0.0, None);
+
+        let mut action = annotation.action();
+        match destination {
+            Some((page_id, x, y)) => {
+                action
+                    .action_type(ActionType::GoTo)
+                    .destination()
+                    .page(page_id)
+                    .xyz(x, y, None);
+            }
+            None => {
+                action
+                    .action_type(ActionType::Uri)
+                    .uri(Str(link.href.as_bytes()));
+            }
+        }
+
+        Some(id)
+    }
+}
+
+/// Creates the procedure of a glyph in a Type3 font, in font units.
+fn glyph_proc(font: &FontItem, glyph: u32, width: f32) -> Vec<u8> {
+    let path = match font.get_glyph(glyph).map(AsRef::as_ref) {
+        Some(FlatGlyphItem::Outline(outline)) => convert_path(&outline.d).and_then(|path| {
+            let ts = outline.ts.as_deref().copied();
+            path.transform(ts.map_or_else(sk::Transform::identity, Into::into))
+        }),
+        _ => None,
+    };
+
+    let mut content = Content::new();
+    match path {
+        Some(path) => {
+            let bbox = path.bounds();
+            content.start_shape_glyph(
+                width,
+                0.0,
+                bbox.left(),
+                bbox.top(),
+                bbox.right(),
+                bbox.bottom(),
+            );
+            emit_path(&mut content, &path);
+            content.fill_nonzero();
+        }
+        None => {
+            content.start_shape_glyph(width, 0.0, 0.0, 0.0, 0.0, 0.0);
+        }
+    }
+
+    content.finish().to_vec()
+}
+
+fn emit_path(content: &mut Content, path: &sk::Path) {
+    let mut last = sk::Point::zero();
+    for segment in path.segments() {
+        match segment {
+            sk::PathSegment::MoveTo(p) => {
+                content.move_to(p.x, p.y);
+                last = p;
+            }
+            sk::PathSegment::LineTo(p) => {
+                content.line_to(p.x, p.y);
+                last = p;
+            }
+            sk::PathSegment::QuadTo(p1, p) => {
+                // Elevates the quadratic curve into a cubic one.
+                let c1 = (
+                    last.x + (p1.x - last.x) * 2.0 / 3.0,
+                    last.y + (p1.y - last.y) * 2.0 / 3.0,
+                );
+                let c2 = (
+                    p.x + (p1.x - p.x) * 2.0 / 3.0,
+                    p.y + (p1.y - p.y) * 2.0 / 3.0,
+                );
+                content.cubic_to(c1.0, c1.1, c2.0, c2.1, p.x, p.y);
+                last = p;
+            }
+            sk::PathSegment::CubicTo(p1, p2, p) => {
+                content.cubic_to(p1.x, p1.y, p2.x, p2.y, p.x, p.y);
+                last = p;
+            }
+            sk::PathSegment::Close => {
+                content.close_path();
+            }
+        }
+    }
+}
+
+fn convert_path(path_data: &str) -> Option<sk::Path> {
+    let mut builder = sk::PathBuilder::new();
+    for segment in svgtypes::SimplifyingPathParser::from(path_data) {
+        let segment = match segment {
+            Ok(v) => v,
+            Err(_) => break,
+        };
+
+        match segment {
+            svgtypes::SimplePathSegment::MoveTo { x, y } => {
+                builder.move_to(x as f32, y as f32);
+            }
+            svgtypes::SimplePathSegment::LineTo { x, y } => {
+                builder.line_to(x as f32, y as f32);
+            }
+            svgtypes::SimplePathSegment::Quadratic { x1, y1, x, y } => {
+                builder.quad_to(x1 as f32, y1 as f32, x as f32, y as f32);
+            }
+            svgtypes::SimplePathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                builder.cubic_to(
+                    x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32,
+                );
+            }
+            svgtypes::SimplePathSegment::ClosePath => {
+                builder.close();
+            }
+        }
+    }
+
+    builder.finish()
+}
+
+fn convert_transform(transform: &TransformItem) -> sk::Transform {
+    match transform {
+        TransformItem::Matrix(m) => (**m).into(),
+        TransformItem::Translate(t) => sk::Transform::from_translate(t.x.0, t.y.0),
+        TransformItem::Scale(s) => sk::Transform::from_scale(s.0 .0, s.1 .0),
+        TransformItem::Skew(s) => sk::Transform::from_skew(s.0 .0, s.1 .0),
+        TransformItem::Rotate(degree) => sk::Transform::from_rotate(degree.0),
+        TransformItem::Clip(..) => sk::Transform::identity(),
+    }
+}
+
+fn to_matrix(ts: sk::Transform) -> [f32; 6] {
+    [ts.sx, ts.ky, ts.kx, ts.sy, ts.tx, ts.ty]
+}
+
+/// Gets the aspect ratio of the space that a gradient is mapped into.
+fn aspect_ratio(ts: sk::Transform) -> f32 {
+    let width = ts.sx.hypot(ts.ky);
+    let height = ts.kx.hypot(ts.sy);
+    let ratio = width / height;
+    if ratio.is_finite() && ratio != 0.0 {
+        ratio
+    } else {
+        1.0
+    }
+}
+
+/// Gets the start and end points of a linear gradient in its unit space,
+/// where the angle is corrected so that it is kept after the unit space is
+/// stretched by the aspect ratio.
+fn linear_coords(angle: f32, aspect_ratio: f32) -> (f32, f32, f32, f32) {
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    let angle = (angle.sin() / aspect_ratio)
+        .atan2(angle.cos())
+        .rem_euclid(TAU);
+    let (sin, cos) = angle.sin_cos();
+    let length = sin.abs() + cos.abs();
+    let (x1, y1) = match angle {
+        angle if angle < FRAC_PI_2 => (0.0, 0.0),
+        angle if angle < PI => (1.0, 0.0),
+        angle if angle < PI * 1.5 => (1.0, 1.0),
+        _ => (0.0, 1.0),
+    };
+    (x1, y1, x1 + cos * length, y1 + sin * length)
+}
+
+/// Gets the center, the focal center and the focal radius of a radial
+/// gradient.
+fn radial_styles(gradient: &GradientItem) -> (Point, Point, Scalar) {
+    let mut center = Point::new(Scalar(0.5), Scalar(0.5));
+    let mut focal_center = None;
+    let mut focal_radius = Scalar(0.0);
+    for style in gradient.styles.iter() {
+        match style {
+            GradientStyle::Center(c) => center = *c,
+            GradientStyle::FocalCenter(c) => focal_center = Some(*c),
+            GradientStyle::FocalRadius(r) => focal_radius = *r,
+        }
+    }
+    (center, focal_center.unwrap_or(center), focal_radius)
+}
+
+fn rgba8(color: &Rgba8Item) -> Rgba {
+    [color.r, color.g, color.b, color.a].map(|c| c as f32 / 255.0)
+}
+
+fn is_even_odd(styles: &[PathStyle]) -> bool {
+    styles
+        .iter()
+        .any(|s| matches!(s, PathStyle::FillRule(rule) if rule.as_ref() == "evenodd"))
+}
+
+/// Parses a color in the form of `#rgb`, `#rrggbb`, or `#rrggbbaa`.
+fn parse_hex_color(color: &str) -> Option<Rgba> {
+    let hex = color.strip_prefix('#')?;
+    let digit = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 1)?, 16).ok();
+    let byte = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+
+    let rgba = match hex.len() {
+        3 => [digit(0)? * 17, digit(1)? * 17, digit(2)? * 17, 255],
+        6 => [byte(0)?, byte(2)?, byte(4)?, 255],
+        8 => [byte(0)?, byte(2)?, byte(4)?, byte(6)?],
+        _ => return None,
+    };
+    Some(rgba.map(|c| c as f32 / 255.0))
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .map_err(|err| error_once!("vec2pdf: failed to compress a stream", err: err))
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::content::{Content as PdfContent, Operation};
+    use lopdf::{Dictionary, Document};
+    use typst_ts_test_common::compile::lower_paged;
+
+    use super::*;
+
+    /// A red png image of 2x2 pixels.
+    const RED_PNG: &[u8] = &[
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 2, 0, 0, 0, 2, 8, 2,
+        0, 0, 0, 253, 212, 154, 115, 0, 0, 0, 16, 73, 68, 65, 84, 120, 156, 99, 248, 207, 192, 0,
+        68, 12, 16, 10, 0, 31, 238, 3, 253, 139, 95, 20, 212, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66,
+        96, 130,
+    ];
+
+    fn render(source: &str) -> Document {
+        let (module, pages) = lower_paged(source);
+        let pdf = render_pdf(&module, &pages).unwrap();
+        Document::load_mem(&pdf).unwrap()
+    }
+
+    fn page_id(doc: &Document, page: u32) -> lopdf::ObjectId {
+        doc.get_pages()[&page]
+    }
+
+    fn operations(doc: &Document, page: u32) -> Vec<Operation> {
+        let content = doc.get_page_content(page_id(doc, page)).unwrap();
+        PdfContent::decode(&content).unwrap().operations
+    }
+
+    fn resource<'a>(doc: &'a Document, page: u32, kind: &[u8]) -> &'a Dictionary {
+        let page = doc.get_dictionary(page_id(doc, page)).unwrap();
+        let resources = page
+            .get_deref(b"Resources", doc)
+            .unwrap()
+            .as_dict()
+            .unwrap();
+        resources.get_deref(kind, doc).unwrap().as_dict().unwrap()
+    }
+
+    fn has_operator(operations: &[Operation], operator: &str) -> bool {
+        operations.iter().any(|op| op.operator == operator)
+    }
+
+    #[test]
+    fn test_pages_and_fonts() {
+        let doc = render("#set page(width: 120pt, height: 60pt)\nHello\n#pagebreak()\nWorld");
+
+        assert_eq!(doc.get_pages().len(), 2);
+        let page = doc.get_dictionary(page_id(&doc, 1)).unwrap();
+        let media_box = page.get(b"MediaBox").unwrap().as_array().unwrap();
+        let media_box = media_box.iter().map(|v| v.as_float().unwrap());
+        assert_eq!(media_box.collect::<Vec<_>>(), vec![0.0, 0.0, 120.0, 60.0]);
+
+        let fonts = doc.get_page_fonts(page_id(&doc, 1)).unwrap();
+        assert!(!fonts.is_empty());
+        for font in fonts.values() {
+            let subtype = font.get(b"Subtype").unwrap().as_name().unwrap();
+            assert_eq!(subtype, b"Type3");
+            assert!(font.has(b"ToUnicode"));
+            assert!(font.has(b"CharProcs"));
+        }
+
+        for page in [1, 2] {
+            assert!(has_operator(&operations(&doc, page), "Tj"));
+        }
+    }
+
+    #[test]
+    fn test_actual_text_of_ligatures() {
+        let doc = render("#set page(width: 120pt, height: 60pt)\noffice");
+
+        let operations = operations(&doc, 1);
+        let span = operations.iter().find(|op| op.operator == "BDC").unwrap();
+        let properties = span.operands[1].as_dict().unwrap();
+        let text = properties.get(b"ActualText").unwrap().as_str().unwrap();
+        assert_eq!(text, b"office");
+        assert!(has_operator(&operations, "EMC"));
+    }
+
+    #[test]
+    fn test_paths_and_images() {
+        let png = RED_PNG.iter().map(u8::to_string).collect::<Vec<_>>();
+        let doc = render(&format!(
+            "#set page(width: 120pt, height: 60pt)\n\
+             #rect(width: 20pt, height: 10pt, fill: blue, stroke: 1pt + red)\n\
+             #image(bytes(({})), width: 10pt)",
+            png.join(", ")
+        ));
+
+        let operations = operations(&doc, 1);
+        assert!(has_operator(&operations, "rg"));
+        assert!(has_operator(&operations, "RG"));
+        assert!(has_operator(&operations, "Do"));
+
+        let x_objects = resource(&doc, 1, b"XObject");
+        assert_eq!(x_objects.len(), 1);
+        let (_, image) = x_objects.iter().next().unwrap();
+        let image = doc.get_object(image.as_reference().unwrap()).unwrap();
+        let image = &image.as_stream().unwrap().dict;
+        assert_eq!(image.get(b"Subtype").unwrap().as_name().unwrap(), b"Image");
+        assert_eq!(image.get(b"Width").unwrap().as_i64().unwrap(), 2);
+        assert_eq!(image.get(b"Height").unwrap().as_i64().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_gradients() {
+        let doc = render(
+            "#set page(width: 120pt, height: 60pt)\n\
+             #rect(width: 20pt, height: 10pt, fill: gradient.linear(red, green, blue))\n\
+             #circle(radius: 10pt, fill: gradient.radial(red, blue))",
+        );
+
+        let operations = operations(&doc, 1);
+        assert!(has_operator(&operations, "scn"));
+
+        let patterns = resource(&doc, 1, b"Pattern");
+        let shading_types = patterns
+            .iter()
+            .map(|(_, pattern)| {
+                let pattern = doc.get_dictionary(pattern.as_reference().unwrap()).unwrap();
+                let shading = pattern
+                    .get_deref(b"Shading", &doc)
+                    .unwrap()
+                    .as_dict()
+                    .unwrap();
+                shading.get(b"ShadingType").unwrap().as_i64().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert!(shading_types.contains(&2), "{shading_types:?}");
+        assert!(shading_types.contains(&3), "{shading_types:?}");
+    }
+
+    #[test]
+    fn test_links() {
+        let doc = render(
+            "#set page(width: 120pt, height: 60pt)\n\
+             #link(\"https://example.com\")[Example] #link(<end>)[End]\n\
+             #pagebreak()\n\
+             = End <end>",
+        );
+
+        let annotations = doc.get_page_annotations(page_id(&doc, 1)).unwrap();
+        assert_eq!(annotations.len(), 2);
+
+        let actions = annotations
+            .iter()
+            .map(|annotation| {
+                let subtype = annotation.get(b"Subtype").unwrap().as_name().unwrap();
+                assert_eq!(subtype, b"Link");
+                annotation.get(b"A").unwrap().as_dict().unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let uri = actions.iter().find_map(|action| action.get(b"URI").ok());
+        assert_eq!(uri.unwrap().as_str().unwrap(), b"https://example.com");
+
+        let destination = actions.iter().find_map(|action| action.get(b"D").ok());
+        let destination = destination.unwrap().as_array().unwrap();
+        assert_eq!(
+            destination[0].as_reference().unwrap(),
+            page_id(&doc, 2),
+            "the link points to the second page"
+        );
+    }
+
+    #[test]
+    fn test_deflate() {
+        let data = b"vec2pdf ".repeat(64);
+        let compressed = deflate(&data).unwrap();
+        assert!(compressed.len() < data.len());
+
+        let mut decoder = flate2::read::ZlibDecoder::new(compressed.as_slice());
+        let mut decompressed = vec![];
+        std::io::Read::read_to_end(&mut decoder, &mut decompressed).unwrap();
+        assert_eq!(decompressed, data);
+    }
+}
@@ -0,0 +1,597 @@
+//! Rasterize vector documents into bitmaps on CPU.
+//!
+//! The pages are lowered into canvas elements by [`reflexo_vec2canvas`], the
+//! same as the canvas renderer, but the elements are painted by [`tiny_skia`]
+//! instead of a browser canvas. Therefore it runs natively, e.g. to generate
+//! thumbnails on a server or to compare pages in tests.
+//!
+//! The styles of paths, the sizes of images and the gradients are resolved by
+//! the web-free helpers of [`reflexo_vec2canvas`], e.g. [`PathStyles`], so
+//! that both renderers agree on them. Since
+//! [`reflexo_vec2canvas::CanvasDevice`] is bound to `web_sys`, this crate walks
+//! the lowered [`CanvasElem`] tree by itself.
+
+use std::{collections::HashMap, sync::Arc};
+
+use reflexo::{
+    hash::Fingerprint,
+    vector::{
+        ir::{
+            FlatGlyphItem, GradientKind, Image, ImageItem, LayoutSelectorExpr, Module,
+            MultiVecDocument, Page, Rgba8Item,
+        },
+        vm::RenderVm,
+    },
+};
+use reflexo_vec2canvas::{
+    image_draw_size, CanvasElem, CanvasGlyphElem, CanvasGradientPaint, CanvasPaint, CanvasPathElem,
+    CanvasTask, DefaultExportFeature, FillRule, PathStyles,
+};
+use tiny_skia as sk;
+
+/// The size of the texture sampling a conic gradient.
+const CONIC_TEXTURE_SIZE: u32 = 256;
+
+/// Rasterizes the pages of vector documents.
+pub struct Vec2RasterPass {
+    /// The pixels per point of the rendered pages.
+    pub pixel_per_pt: f32,
+    /// The background color of the pages, or transparent if `None`.
+    pub background: Option<sk::Color>,
+
+    images: HashMap<Fingerprint, Option<Arc<sk::Pixmap>>>,
+}
+
+impl Default for Vec2RasterPass {
+    fn default() -> Self {
+        Self {
+            pixel_per_pt: 3.,
+            background: Some(sk::Color::WHITE),
+            images: HashMap::new(),
+        }
+    }
+}
+
+impl Vec2RasterPass {
+    /// Creates a pass rendering pages at the given pixels per point.
+    pub fn new(pixel_per_pt: f32) -> Self {
+        Self {
+            pixel_per_pt,
+            ..Self::default()
+        }
+    }
+
+    /// Renders all pages of the first layout in the document. See
+    /// [`Self::render_pages`] for the pages that fail to render.
+    pub fn render_document(&mut self, doc: &MultiVecDocument) -> Vec<Option<sk::Pixmap>> {
+        let layout = doc
+            .layouts
+            .first()
+            .and_then(|layout| layout.by_selector(&LayoutSelectorExpr::Any).ok());
+        let Some(pages) = layout.and_then(|layout| layout.pages_meta()) else {
+            return vec![];
+        };
+
+        self.render_pages(&doc.module, pages)
+    }
+
+    /// Renders the pages in the module. The result is indexed by the pages, in
+    /// which the pages that are too large to allocate a pixmap are `None`.
+    pub fn render_pages(&mut self, module: &Module, pages: &[Page]) -> Vec<Option<sk::Pixmap>> {
+        pages
+            .iter()
+            .map(|page| self.render_page(module, page))
+            .collect()
+    }
+
+    /// Renders a page in the module. Returns `None` if the page is too large
+    /// to allocate a pixmap.
+    pub fn render_page(&mut self, module: &Module, page: &Page) -> Option<sk::Pixmap> {
+        let width = (page.size.x.0 * self.pixel_per_pt).ceil() as u32;
+        let height = (page.size.y.0 * self.pixel_per_pt).ceil() as u32;
+        let mut pixmap = sk::Pixmap::new(width.max(1), height.max(1))?;
+        if let Some(background) = self.background {
+            pixmap.fill(background);
+        }
+
+        let mut task = CanvasTask::<DefaultExportFeature>::default();
+        let elem = task
+            .fork_canvas_render_task(module)
+            .render_item(&page.content);
+
+        let ts = sk::Transform::from_scale(self.pixel_per_pt, self.pixel_per_pt);
+        self.paint(&mut pixmap, &elem, ts, None);
+        Some(pixmap)
+    }
+
+    fn paint(
+        &mut self,
+        pixmap: &mut sk::Pixmap,
+        elem: &CanvasElem,
+        ts: sk::Transform,
+        mask: Option<&sk::Mask>,
+    ) {
+        match elem {
+            CanvasElem::Group(group) => {
+                let ts = ts.pre_concat(*group.ts.as_ref());
+                for (pos, inner) in &group.inner {
+                    let ts = ts.pre_translate(pos.x.0, pos.y.0);
+                    self.paint(pixmap, inner, ts, mask);
+                }
+            }
+            CanvasElem::Clip(clip) => {
+                let Some(path) = convert_path(&clip.d) else {
+                    return;
+                };
+
+                let mask = match mask {
+                    Some(mask) => {
+                        let mut mask = mask.clone();
+                        mask.intersect_path(&path, sk::FillRule::Winding, true, ts);
+                        mask
+                    }
+                    None => {
+                        let Some(mut mask) = sk::Mask::new(pixmap.width(), pixmap.height()) else {
+                            return;
+                        };
+                        mask.fill_path(&path, sk::FillRule::Winding, true, ts);
+                        mask
+                    }
+                };
+                self.paint(pixmap, &clip.inner, ts, Some(&mask));
+            }
+            CanvasElem::Path(path) => paint_path(pixmap, path, ts, mask),
+            CanvasElem::Image(image) => self.paint_image(pixmap, &image.image_data, ts, mask),
+            CanvasElem::Glyph(glyph) => self.paint_glyph(pixmap, glyph, ts, mask),
+        }
+    }
+
+    fn paint_glyph(
+        &mut self,
+        pixmap: &mut sk::Pixmap,
+        glyph: &CanvasGlyphElem,
+        ts: sk::Transform,
+        mask: Option<&sk::Mask>,
+    ) {
+        match glyph.glyph_data.as_ref() {
+            FlatGlyphItem::Outline(outline) => {
+                if glyph.fill.is_unsupported() {
+                    return;
+                }
+
+                if let Some(path) = convert_path(&outline.d) {
+                    fill_path(pixmap, &path, &glyph.fill, sk::FillRule::Winding, ts, mask);
+                }
+            }
+            FlatGlyphItem::Image(image) => {
+                let ts = ts.pre_concat(image.ts.into());
+                self.paint_image(pixmap, &image.image, ts, mask);
+            }
+            FlatGlyphItem::None => {}
+        }
+    }
+
+    fn paint_image(
+        &mut self,
+        pixmap: &mut sk::Pixmap,
+        image_data: &ImageItem,
+        ts: sk::Transform,
+        mask: Option<&sk::Mask>,
+    ) {
+        let image = &image_data.image;
+        let decoded = self
+            .images
+            .entry(image.hash)
+            .or_insert_with(|| decode_image(image).map(Arc::new));
+        let Some(decoded) = decoded else {
+            return;
+        };
+
+        // resize image to fit the view, the same as the canvas renderer
+        let (w, h) = image_draw_size(image_data);
+
+        let sx = w / decoded.width() as f32;
+        let sy = h / decoded.height() as f32;
+        if !sx.is_finite() || !sy.is_finite() {
+            return;
+        }
+
+        let paint = sk::PixmapPaint {
+            quality: sk::FilterQuality::Bilinear,
+            ..Default::default()
+        };
+        let decoded = sk::Pixmap::as_ref(decoded);
+        pixmap.draw_pixmap(0, 0, decoded, &paint, ts.pre_scale(sx, sy), mask);
+    }
+}
+
+/// Converts a premultiplied pixmap into straight RGBA pixels.
+pub fn to_rgba(pixmap: &sk::Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let pixel = pixel.demultiply();
+            [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+        })
+        .collect()
+}
+
+fn paint_path(
+    pixmap: &mut sk::Pixmap,
+    elem: &CanvasPathElem,
+    ts: sk::Transform,
+    mask: Option<&sk::Mask>,
+) {
+    let Some(path) = convert_path(&elem.path_data.d) else {
+        return;
+    };
+
+    let styles = PathStyles::resolve(&elem.path_data.styles);
+    let fill_rule = match styles.fill_rule {
+        Some(FillRule::EvenOdd) => sk::FillRule::EvenOdd,
+        Some(FillRule::NonZero) | None => sk::FillRule::Winding,
+    };
+
+    if let Some(fill) = &elem.fill {
+        fill_path(pixmap, &path, fill, fill_rule, ts, mask);
+    }
+
+    if styles.has_stroke() {
+        if let Some(paint) = &elem.stroke {
+            let stroke = convert_stroke(&styles);
+            with_paint(paint, ts, |paint| {
+                pixmap.stroke_path(&path, paint, &stroke, ts, mask);
+            });
+        }
+    }
+}
+
+fn convert_stroke(styles: &PathStyles) -> sk::Stroke {
+    let mut stroke = sk::Stroke {
+        width: styles.stroke_width.unwrap_or_default(),
+        ..Default::default()
+    };
+    stroke.line_cap = match styles.line_cap.as_deref() {
+        Some("round") => sk::LineCap::Round,
+        Some("square") => sk::LineCap::Square,
+        _ => sk::LineCap::Butt,
+    };
+    stroke.line_join = match styles.line_join.as_deref() {
+        Some("round") => sk::LineJoin::Round,
+        Some("bevel") => sk::LineJoin::Bevel,
+        _ => sk::LineJoin::Miter,
+    };
+    if let Some(limit) = styles.miter_limit {
+        stroke.miter_limit = limit;
+    }
+    stroke.dash = styles
+        .dash_array
+        .clone()
+        .and_then(|array| sk::StrokeDash::new(array, styles.dash_offset.unwrap_or_default()));
+    stroke
+}
+
+fn fill_path(
+    pixmap: &mut sk::Pixmap,
+    path: &sk::Path,
+    fill: &CanvasPaint,
+    fill_rule: sk::FillRule,
+    ts: sk::Transform,
+    mask: Option<&sk::Mask>,
+) {
+    with_paint(fill, ts, |paint| {
+        pixmap.fill_path(path, paint, fill_rule, ts, mask);
+    });
+}
+
+/// Creates a paint in the coordinate space `ts`, which lives as long as the
+/// callback.
+fn with_paint(paint: &CanvasPaint, ts: sk::Transform, f: impl FnOnce(&sk::Paint)) {
+    let mut sk_paint = sk::Paint {
+        anti_alias: true,
+        ..Default::default()
+    };
+
+    match paint {
+        CanvasPaint::Solid(color) => {
+            sk_paint.set_color(parse_color(color).unwrap_or(sk::Color::BLACK));
+            f(&sk_paint)
+        }
+        CanvasPaint::Gradient(gradient) => {
+            let texture;
+            let shader = match gradient.gradient.kind {
+                GradientKind::Linear(_) => {
+                    let (x1, y1, x2, y2) = gradient.linear_points().unwrap();
+                    sk::LinearGradient::new(
+                        sk::Point::from_xy(x1 as f32, y1 as f32),
+                        sk::Point::from_xy(x2 as f32, y2 as f32),
+                        gradient_stops(gradient),
+                        sk::SpreadMode::Pad,
+                        ts,
+                    )
+                }
+                GradientKind::Radial(_) => {
+                    // todo: tiny-skia doesn't support the focal radius.
+                    let ((fx, fy, _), (cx, cy, radius)) = gradient.radial_circles().unwrap();
+                    sk::RadialGradient::new(
+                        sk::Point::from_xy(fx, fy),
+                        sk::Point::from_xy(cx, cy),
+                        radius,
+                        gradient_stops(gradient),
+                        sk::SpreadMode::Pad,
+                        ts.pre_concat(gradient_transform(gradient)),
+                    )
+                }
+                GradientKind::Conic(angle) => {
+                    // tiny-skia doesn't support sweep gradients, so the
+                    // gradient is sampled into a texture in the unit space.
+                    texture = conic_texture(gradient, angle.0);
+                    let scale = 1. / CONIC_TEXTURE_SIZE as f32;
+                    texture.as_ref().map(|texture| {
+                        sk::Pattern::new(
+                            texture.as_ref(),
+                            sk::SpreadMode::Pad,
+                            sk::FilterQuality::Bilinear,
+                            1.,
+                            ts.pre_concat(gradient_transform(gradient))
+                                .pre_scale(scale, scale),
+                        )
+                    })
+                }
+            };
+
+            match shader {
+                Some(shader) => sk_paint.shader = shader,
+                // A gradient may degenerate to a single color.
+                None => {
+                    let stops = gradient.color_stops();
+                    let color = stops
+                        .first()
+                        .map_or(sk::Color::BLACK, |(_, c)| to_color(*c));
+                    sk_paint.set_color(color);
+                }
+            }
+            f(&sk_paint)
+        }
+        CanvasPaint::Unsupported => {
+            sk_paint.set_color(sk::Color::BLACK);
+            f(&sk_paint)
+        }
+    }
+}
+
+fn gradient_transform(gradient: &CanvasGradientPaint) -> sk::Transform {
+    gradient
+        .transform
+        .map_or_else(sk::Transform::identity, Into::into)
+}
+
+fn gradient_stops(gradient: &CanvasGradientPaint) -> Vec<sk::GradientStop> {
+    gradient
+        .color_stops()
+        .into_iter()
+        .map(|(offset, color)| sk::GradientStop::new(offset, to_color(color)))
+        .collect()
+}
+
+/// Samples a conic gradient in the unit space, the same as a canvas conic
+/// gradient starting at `PI + angle`.
+fn conic_texture(gradient: &CanvasGradientPaint, angle: f32) -> Option<sk::Pixmap> {
+    let stops = gradient.color_stops();
+    let (cx, cy) = gradient
+        .gradient
+        .styles
+        .iter()
+        .rev()
+        .find_map(|style| match style {
+            reflexo::vector::ir::GradientStyle::Center(c) => Some((c.x.0, c.y.0)),
+            _ => None,
+        })
+        .unwrap_or((0.5, 0.5));
+
+    let size = CONIC_TEXTURE_SIZE;
+    let start = std::f32::consts::PI + angle;
+    let mut pixmap = sk::Pixmap::new(size, size)?;
+    for (idx, pixel) in pixmap.pixels_mut().iter_mut().enumerate() {
+        let x = ((idx as u32 % size) as f32 + 0.5) / size as f32;
+        let y = ((idx as u32 / size) as f32 + 0.5) / size as f32;
+        let theta = (y - cy).atan2(x - cx) - start;
+        let t = theta.rem_euclid(std::f32::consts::TAU) / std::f32::consts::TAU;
+        *pixel = sample_stops(&stops, t).premultiply().to_color_u8();
+    }
+
+    Some(pixmap)
+}
+
+fn sample_stops(stops: &[(f32, Rgba8Item)], t: f32) -> sk::Color {
+    let j = stops.partition_point(|(offset, _)| *offset < t);
+    let (Some(&(t1, c1)), Some(&(t0, c0))) =
+        (stops.get(j), j.checked_sub(1).and_then(|j| stops.get(j)))
+    else {
+        return stops
+            .get(j)
+            .or(stops.last())
+            .map_or(sk::Color::BLACK, |(_, c)| to_color(*c));
+    };
+
+    let t = if t1 > t0 { (t - t0) / (t1 - t0) } else { 0. };
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) / 255.;
+    sk::Color::from_rgba(
+        mix(c0.r, c1.r),
+        mix(c0.g, c1.g),
+        mix(c0.b, c1.b),
+        mix(c0.a, c1.a),
+    )
+    .unwrap_or(sk::Color::BLACK)
+}
+
+fn to_color(color: Rgba8Item) -> sk::Color {
+    sk::Color::from_rgba8(color.r, color.g, color.b, color.a)
+}
+
+fn parse_color(color: &str) -> Option<sk::Color> {
+    let color: svgtypes::Color = color.parse().ok()?;
+    Some(sk::Color::from_rgba8(
+        color.red,
+        color.green,
+        color.blue,
+        color.alpha,
+    ))
+}
+
+fn decode_image(image: &Image) -> Option<sk::Pixmap> {
+    if image.format.contains("svg") {
+        let tree = resvg::usvg::Tree::from_data(&image.data, &Default::default()).ok()?;
+        let size = tree.size().to_int_size();
+        let mut pixmap = sk::Pixmap::new(size.width(), size.height())?;
+        resvg::render(&tree, sk::Transform::identity(), &mut pixmap.as_mut());
+        return Some(pixmap);
+    }
+
+    let decoded = image::load_from_memory(&image.data).ok()?.into_rgba8();
+    let size = sk::IntSize::from_wh(decoded.width(), decoded.height())?;
+    let mut data = decoded.into_raw();
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        for c in &mut pixel[..3] {
+            *c = ((*c as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+    sk::Pixmap::from_vec(data, size)
+}
+
+fn convert_path(path_data: &str) -> Option<sk::Path> {
+    let mut builder = sk::PathBuilder::new();
+
+    for segment in svgtypes::SimplifyingPathParser::from(path_data) {
+        let segment = segment.ok()?;
+
+        match segment {
+            svgtypes::SimplePathSegment::MoveTo { x, y } => builder.move_to(x as f32, y as f32),
+            svgtypes::SimplePathSegment::LineTo { x, y } => builder.line_to(x as f32, y as f32),
+            svgtypes::SimplePathSegment::Quadratic { x1, y1, x, y } => {
+                builder.quad_to(x1 as f32, y1 as f32, x as f32, y as f32)
+            }
+            svgtypes::SimplePathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => builder.cubic_to(
+                x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32,
+            ),
+            svgtypes::SimplePathSegment::ClosePath => builder.close(),
+        }
+    }
+
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use typst_ts_test_common::compile::lower_paged;
+
+    use super::*;
+
+    fn render(source: &str) -> (sk::Pixmap, Vec<u8>) {
+        let (module, pages) = lower_paged(source);
+        let pixmap = Vec2RasterPass::new(1.)
+            .render_page(&module, &pages[0])
+            .expect("failed to allocate the pixmap");
+        let rgba = to_rgba(&pixmap);
+        (pixmap, rgba)
+    }
+
+    fn pixel_at(pixmap: &sk::Pixmap, rgba: &[u8], x: u32, y: u32) -> [u8; 4] {
+        let idx = ((y * pixmap.width() + x) * 4) as usize;
+        rgba[idx..idx + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_render_rect() {
+        let (pixmap, rgba) = render(
+            "#set page(width: 100pt, height: 100pt, margin: 0pt)\n\
+             #place(dx: 20pt, dy: 20pt, rect(width: 40pt, height: 40pt, fill: red))",
+        );
+
+        assert_eq!((pixmap.width(), pixmap.height()), (100, 100));
+        assert_eq!(rgba.len(), 100 * 100 * 4);
+        assert_eq!(pixel_at(&pixmap, &rgba, 40, 40), [255, 0, 0, 255]);
+        assert_eq!(pixel_at(&pixmap, &rgba, 10, 10), [255, 255, 255, 255]);
+        assert_eq!(pixel_at(&pixmap, &rgba, 80, 80), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_render_stroke() {
+        let (pixmap, rgba) = render(
+            "#set page(width: 100pt, height: 100pt, margin: 0pt)\n\
+             #place(dx: 20pt, dy: 20pt, rect(width: 60pt, height: 60pt, stroke: 4pt + blue))",
+        );
+
+        assert_eq!(pixel_at(&pixmap, &rgba, 20, 50), [0, 0, 255, 255]);
+        assert_eq!(pixel_at(&pixmap, &rgba, 50, 50), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let (pixmap, rgba) = render(
+            "#set page(width: 100pt, height: 40pt, margin: 10pt)\n\
+             #text(size: 20pt, fill: rgb(0, 128, 0))[HHHH]",
+        );
+
+        let greens = rgba
+            .chunks_exact(4)
+            .filter(|pixel| pixel[1] > pixel[0] && pixel[1] > pixel[2])
+            .count();
+        assert!(greens > 0, "the text is not painted");
+        // The margins are left blank.
+        assert_eq!(pixel_at(&pixmap, &rgba, 2, 2), [255, 255, 255, 255]);
+        assert_eq!(pixel_at(&pixmap, &rgba, 97, 37), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_render_pages_keep_indices() {
+        let (module, pages) = lower_paged(
+            "#page(width: 10000pt, height: 0.001pt, margin: 0pt)[]\n\
+             #page(width: 0.001pt, height: 0.001pt, margin: 0pt)[]",
+        );
+
+        // The first page is too wide to allocate a pixmap.
+        let pixmaps = Vec2RasterPass::new(200000.).render_pages(&module, &pages);
+        assert_eq!(pixmaps.len(), 2);
+        assert!(pixmaps[0].is_none());
+        assert!(pixmaps[1].is_some());
+    }
+
+    #[test]
+    fn test_sample_stops() {
+        let black = Rgba8Item {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        let white = Rgba8Item {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 255,
+        };
+        let stops = [(0., black), (1., white)];
+
+        assert_eq!(sample_stops(&stops, -1.), to_color(black));
+        assert_eq!(sample_stops(&stops, 2.), to_color(white));
+        let mid = sample_stops(&stops, 0.5).to_color_u8();
+        assert!((127..=128).contains(&mid.red()));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#fff"), Some(sk::Color::WHITE));
+        assert_eq!(parse_color("#0000"), Some(sk::Color::TRANSPARENT));
+        assert_eq!(parse_color("@g0"), None);
+    }
+}
@@ -0,0 +1,575 @@
+//! Accessibility tree of the vector items.
+//!
+//! The tree is built in the order of the vector items, which follows the
+//! reading order of the document. Coordinates are in pt and relative to the
+//! page containing the node.
+
+use std::borrow::Cow;
+
+use reflexo::{
+    escape::{escape_str, AttributeEscapes, PcDataEscapes},
+    hash::Fingerprint,
+    vector::extract::{heading_sizes, round_size, transform_rect},
+    vector::ir::{self, ImageAttr, Module, Page, VecItem},
+};
+use serde::Serialize;
+
+/// The role of a node in the accessibility tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum A11yRole {
+    Document,
+    Page,
+    /// A labelled group, i.e. [`VecItem::Labelled`].
+    Group,
+    /// A labelled group designated as a heading, see [`heading_level`], or a
+    /// text run in a font notably larger than the body text.
+    Heading,
+    /// A run of text on the same line.
+    Text,
+    Link,
+    Image,
+}
+
+/// A node in the accessibility tree.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct A11yNode {
+    pub role: A11yRole,
+    /// The accessible name, i.e. the text of text runs, links and headings, or
+    /// the alternative text of images.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The label of groups and headings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The target of links.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    /// The level of headings, starting from 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<u8>,
+    /// The bounding box in `[x, y, width, height]`.
+    pub rect: [f32; 4],
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<A11yNode>,
+    /// The largest font size of text runs, which is used to infer headings.
+    #[serde(skip)]
+    font_size: f32,
+}
+
+impl A11yNode {
+    fn new(role: A11yRole, rect: [f32; 4]) -> Self {
+        Self {
+            role,
+            name: None,
+            label: None,
+            href: None,
+            level: None,
+            rect,
+            children: vec![],
+            font_size: 0.,
+        }
+    }
+
+    /// Serializes the tree into JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Renders the tree into an ARIA-annotated HTML layer.
+    ///
+    /// Like the semantics layer, the leaves are positioned by the
+    /// `--data-text-width` and `--data-text-height` variables of the page, so
+    /// that the layer can be placed over the canvas.
+    pub fn to_aria_html(&self) -> String {
+        let mut output = vec![];
+        self.render_aria(&mut output);
+        output.concat()
+    }
+
+    fn render_aria<'a>(&'a self, output: &mut Vec<Cow<'a, str>>) {
+        use A11yRole::*;
+
+        let label_attr = |output: &mut Vec<Cow<'a, str>>| {
+            if let Some(label) = &self.label {
+                output.push(Cow::Borrowed(r#" data-typst-label=""#));
+                output.push(escape_str::<AttributeEscapes>(label));
+                output.push(Cow::Borrowed(r#"""#));
+            }
+        };
+
+        match self.role {
+            Document => {
+                output.push(Cow::Borrowed(r#"<div class="typst-a11y" role="document">"#));
+                self.render_aria_children(output);
+                output.push(Cow::Borrowed("</div>"));
+            }
+            Page => {
+                output.push(Cow::Borrowed(
+                    r#"<section class="typst-a11y-page" role="region" aria-label=""#,
+                ));
+                output.push(escape_str::<AttributeEscapes>(
+                    self.name.as_deref().unwrap_or_default(),
+                ));
+                output.push(Cow::Borrowed(r#"">"#));
+                self.render_aria_children(output);
+                output.push(Cow::Borrowed("</section>"));
+            }
+            Group => {
+                output.push(Cow::Borrowed(
+                    r#"<div class="typst-a11y-group" role="group""#,
+                ));
+                label_attr(output);
+                output.push(Cow::Borrowed(">"));
+                self.render_aria_children(output);
+                output.push(Cow::Borrowed("</div>"));
+            }
+            Heading => {
+                output.push(Cow::Owned(format!(
+                    r#"<div class="typst-a11y-heading" role="heading" aria-level="{}""#,
+                    self.level.unwrap_or(1)
+                )));
+                label_attr(output);
+                output.push(Cow::Borrowed(">"));
+                self.render_aria_children(output);
+                output.push(Cow::Borrowed("</div>"));
+            }
+            Text => {
+                output.push(Cow::Owned(format!(
+                    r#"<span class="typst-a11y-text"{}>"#,
+                    self.style()
+                )));
+                output.push(escape_str::<PcDataEscapes>(
+                    self.name.as_deref().unwrap_or_default(),
+                ));
+                output.push(Cow::Borrowed("</span>"));
+            }
+            Link => {
+                let href = self.href.as_deref().unwrap_or_default();
+                output.push(Cow::Borrowed(r#"<a class="typst-a11y-link" href=""#));
+                if let Some(handler) = href.strip_prefix("@typst:") {
+                    output.push(Cow::Borrowed(r##"#" onclick=""##));
+                    output.push(escape_str::<AttributeEscapes>(handler));
+                    output.push(Cow::Borrowed(r#"; return false""#));
+                } else {
+                    output.push(escape_str::<AttributeEscapes>(href));
+                    output.push(Cow::Borrowed(r#"""#));
+                }
+                if self.children.is_empty() {
+                    output.push(Cow::Owned(self.style()));
+                }
+                output.push(Cow::Borrowed(">"));
+                self.render_aria_children(output);
+                output.push(Cow::Borrowed("</a>"));
+            }
+            Image => {
+                match &self.name {
+                    Some(alt) => {
+                        output.push(Cow::Borrowed(
+                            r#"<span class="typst-a11y-image" role="img" aria-label=""#,
+                        ));
+                        output.push(escape_str::<AttributeEscapes>(alt));
+                        output.push(Cow::Borrowed(r#"""#));
+                    }
+                    None => output.push(Cow::Borrowed(
+                        r#"<span class="typst-a11y-image" role="presentation""#,
+                    )),
+                }
+                output.push(Cow::Owned(format!("{}></span>", self.style())));
+            }
+        }
+    }
+
+    fn render_aria_children<'a>(&'a self, output: &mut Vec<Cow<'a, str>>) {
+        for child in &self.children {
+            child.render_aria(output);
+        }
+    }
+
+    fn style(&self) -> String {
+        let [x, y, w, h] = self.rect;
+        format!(
+            r#" style="left: calc(var(--data-text-width) * {x:.5}); top: calc(var(--data-text-height) * {y:.5}); width: calc(var(--data-text-width) * {w:.5}); height: calc(var(--data-text-height) * {h:.5});""#
+        )
+    }
+
+    fn center(&self) -> (f32, f32) {
+        let [x, y, w, h] = self.rect;
+        (x + w / 2., y + h / 2.)
+    }
+
+    fn covers(&self, (x, y): (f32, f32)) -> bool {
+        let [x0, y0, w, h] = self.rect;
+        x0 - EPS <= x && x <= x0 + w + EPS && y0 - EPS <= y && y <= y0 + h + EPS
+    }
+
+    /// Joins the names of the descendant text runs.
+    fn text(&self) -> String {
+        fn walk(node: &A11yNode, text: &mut Vec<String>) {
+            match node.role {
+                A11yRole::Text => text.extend(node.name.clone()),
+                _ => node.children.iter().for_each(|child| walk(child, text)),
+            }
+        }
+
+        let mut text = vec![];
+        walk(self, &mut text);
+        text.join(" ")
+    }
+}
+
+const EPS: f32 = 1e-3;
+
+/// Gets the heading level designated by a label, i.e. `<h1>` to `<h6>`,
+/// `<heading>` for level 1, or `<heading-N>`.
+pub fn heading_level(label: &str) -> Option<u8> {
+    let level = match label {
+        "heading" => return Some(1),
+        _ => label
+            .strip_prefix("heading-")
+            .or_else(|| label.strip_prefix('h'))?,
+    };
+
+    level.parse().ok().filter(|level| (1..=6).contains(level))
+}
+
+/// Builds the accessibility tree of the pages.
+pub fn a11y_tree(module: &Module, pages: &[Page]) -> A11yNode {
+    let mut width = 0f32;
+    let mut height = 0f32;
+
+    let children = pages
+        .iter()
+        .enumerate()
+        .map(|(idx, page)| {
+            width = width.max(page.size.x.0);
+            height += page.size.y.0;
+
+            let mut node = A11yNode::new(A11yRole::Page, [0., 0., page.size.x.0, page.size.y.0]);
+            node.name = Some(format!("Page {}", idx + 1));
+
+            let mut builder = A11yBuilder {
+                module,
+                line_break: false,
+            };
+            let ts = tiny_skia::Transform::identity();
+            builder.walk(ts, page.content, &mut node.children);
+            attach_links(&mut node.children);
+            node
+        })
+        .collect();
+
+    let mut doc = A11yNode::new(A11yRole::Document, [0., 0., width, height]);
+    doc.children = children;
+    infer_headings(&mut doc);
+    doc
+}
+
+struct A11yBuilder<'m> {
+    module: &'m Module,
+    /// Whether a line break is hinted since the last text run.
+    line_break: bool,
+}
+
+impl A11yBuilder<'_> {
+    fn walk(&mut self, ts: tiny_skia::Transform, fg: Fingerprint, output: &mut Vec<A11yNode>) {
+        let Some(item) = self.module.get_item(&fg) else {
+            return;
+        };
+
+        use VecItem::*;
+        match item {
+            Group(t) => {
+                for (pos, child) in t.0.iter() {
+                    let ts = ts.pre_translate(pos.x.0, pos.y.0);
+                    self.walk(ts, *child, output);
+                }
+            }
+            Item(t) => {
+                let trans: ir::Transform = t.0.clone().into();
+                let ts = ts.pre_concat(trans.into());
+                self.walk(ts, t.1, output);
+            }
+            Labelled(t) => {
+                let level = heading_level(&t.0);
+                let role = if level.is_some() {
+                    A11yRole::Heading
+                } else {
+                    A11yRole::Group
+                };
+
+                let mut node = A11yNode::new(role, [0.; 4]);
+                node.label = Some(t.0.to_string());
+                node.level = level;
+
+                self.line_break = true;
+                self.walk(ts, t.1, &mut node.children);
+                self.line_break = true;
+                attach_links(&mut node.children);
+
+                if node.children.is_empty() {
+                    return;
+                }
+                node.rect = union_rect(&node.children);
+                if role == A11yRole::Heading {
+                    node.name = Some(node.text());
+                }
+                output.push(node);
+            }
+            Text(t) => {
+                let Some(font) = self.module.get_font(&t.shape.font) else {
+                    return;
+                };
+                let size = t.shape.size;
+                let cap_height = font.cap_height * size;
+                let rect =
+                    transform_rect(ts, [0., -cap_height.0, t.width().0, size.0 - cap_height.0]);
+                let font_size = size.0 * scale(ts);
+
+                let text = t.content.content.as_ref();
+                let line_break = std::mem::take(&mut self.line_break);
+                match output.last_mut() {
+                    Some(last) if !line_break && on_same_line(last, rect) => {
+                        let name = last.name.get_or_insert_with(String::new);
+                        let gap = rect[0] - (last.rect[0] + last.rect[2]);
+                        if gap > font_size * 0.2
+                            && !name.ends_with(char::is_whitespace)
+                            && !text.starts_with(char::is_whitespace)
+                        {
+                            name.push(' ');
+                        }
+                        name.push_str(text);
+                        last.rect =
+                            union_rect(&[last.clone(), A11yNode::new(A11yRole::Text, rect)]);
+                        last.font_size = last.font_size.max(font_size);
+                    }
+                    _ => {
+                        let mut node = A11yNode::new(A11yRole::Text, rect);
+                        node.name = Some(text.to_owned());
+                        node.font_size = font_size;
+                        output.push(node);
+                    }
+                }
+            }
+            ContentHint(c) => {
+                if *c == '\n' {
+                    self.line_break = true;
+                }
+            }
+            Link(t) => {
+                let mut node = A11yNode::new(A11yRole::Link, transformed_rect(ts, t.size));
+                node.href = Some(t.href.to_string());
+                output.push(node);
+            }
+            Image(t) => {
+                let mut node = A11yNode::new(A11yRole::Image, transformed_rect(ts, t.size));
+                node.name = t.image.attrs.iter().find_map(|attr| match attr {
+                    ImageAttr::Alt(alt) => Some(alt.to_string()),
+                    _ => Option::None,
+                });
+                output.push(node);
+            }
+            Path(..) | SizedRawHtml(..) => {}
+            None | ColorTransform(..) | Gradient(..) | Color32(..) | Pattern(..) | Html(..) => {}
+        }
+    }
+}
+
+/// Whether the text run is on the same line as the last text run.
+fn on_same_line(last: &A11yNode, rect: [f32; 4]) -> bool {
+    if last.role != A11yRole::Text {
+        return false;
+    }
+
+    let [x, y, _, h] = rect;
+    let center = y + h / 2.;
+    let [lx, ly, lw, lh] = last.rect;
+    ly - EPS <= center && center <= ly + lh + EPS && x >= lx + lw - h
+}
+
+/// Moves the text runs covered by links into the links.
+///
+/// The link items are stored after the content they cover, so the link is
+/// placed at the first text run it covers.
+fn attach_links(children: &mut Vec<A11yNode>) {
+    let (links, mut rest): (Vec<_>, Vec<_>) = std::mem::take(children)
+        .into_iter()
+        .partition(|node| node.role == A11yRole::Link);
+
+    for mut link in links {
+        let mut placed = None;
+        let mut next = Vec::with_capacity(rest.len() + 1);
+        for node in rest {
+            if node.role == A11yRole::Text && link.covers(node.center()) {
+                placed.get_or_insert(next.len());
+                link.children.push(node);
+            } else {
+                next.push(node);
+            }
+        }
+
+        if !link.children.is_empty() {
+            link.name = Some(link.text());
+        }
+        next.insert(placed.unwrap_or(next.len()), link);
+        rest = next;
+    }
+
+    *children = rest;
+}
+
+/// Designates the text runs in the fonts notably larger than the body text as
+/// headings, like the Markdown extraction.
+fn infer_headings(doc: &mut A11yNode) {
+    fn runs(node: &A11yNode, output: &mut Vec<(f32, usize)>) {
+        match node.role {
+            A11yRole::Text => {
+                let count = node.name.as_deref().unwrap_or_default().chars().count();
+                output.push((node.font_size, count));
+            }
+            _ => node.children.iter().for_each(|child| runs(child, output)),
+        }
+    }
+
+    let mut output = vec![];
+    runs(doc, &mut output);
+    let headings = heading_sizes(output);
+    if !headings.is_empty() {
+        wrap_headings(&mut doc.children, &headings);
+    }
+}
+
+/// Wraps the text runs in the heading sizes into headings. The consecutive
+/// runs of the same level, e.g. the lines of a long heading, are merged.
+fn wrap_headings(children: &mut Vec<A11yNode>, headings: &[i32]) {
+    let mut output: Vec<A11yNode> = Vec::with_capacity(children.len());
+    for mut node in std::mem::take(children) {
+        let level = match node.role {
+            A11yRole::Text => headings
+                .iter()
+                .position(|s| *s == round_size(node.font_size)),
+            // The links and the designated headings are kept as is.
+            A11yRole::Link | A11yRole::Heading | A11yRole::Image => None,
+            A11yRole::Document | A11yRole::Page | A11yRole::Group => {
+                wrap_headings(&mut node.children, headings);
+                None
+            }
+        };
+        let Some(level) = level.map(|level| level as u8 + 1) else {
+            output.push(node);
+            continue;
+        };
+
+        match output.last_mut() {
+            Some(last)
+                if last.role == A11yRole::Heading
+                    && last.label.is_none()
+                    && last.level == Some(level) =>
+            {
+                last.children.push(node);
+                last.rect = union_rect(&last.children);
+                last.name = Some(last.text());
+            }
+            _ => {
+                let mut heading = A11yNode::new(A11yRole::Heading, node.rect);
+                heading.name = node.name.clone();
+                heading.level = Some(level);
+                heading.children.push(node);
+                output.push(heading);
+            }
+        }
+    }
+
+    *children = output;
+}
+
+/// Gets the scale of the transform, i.e. the ratio of the transformed area.
+fn scale(ts: tiny_skia::Transform) -> f32 {
+    (ts.sx * ts.sy - ts.kx * ts.ky).abs().sqrt()
+}
+
+fn transformed_rect(ts: tiny_skia::Transform, size: ir::Size) -> [f32; 4] {
+    transform_rect(ts, [0., 0., size.x.0, size.y.0])
+}
+
+fn union_rect(nodes: &[A11yNode]) -> [f32; 4] {
+    let mut xs = nodes
+        .iter()
+        .flat_map(|n| [n.rect[0], n.rect[0] + n.rect[2]]);
+    let mut ys = nodes
+        .iter()
+        .flat_map(|n| [n.rect[1], n.rect[1] + n.rect[3]]);
+    let (Some(x), Some(y)) = (xs.next(), ys.next()) else {
+        return [0.; 4];
+    };
+    let (x0, x1) = xs.fold((x, x), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    let (y0, y1) = ys.fold((y, y), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    [x0, y0, x1 - x0, y1 - y0]
+}
+
+#[cfg(test)]
+mod tests {
+    use typst_ts_test_common::compile::lower_paged;
+
+    use super::*;
+
+    fn tree(source: &str) -> A11yNode {
+        let (module, pages) = lower_paged(source);
+        a11y_tree(&module, &pages)
+    }
+
+    fn find<'a>(node: &'a A11yNode, role: A11yRole, name: &str) -> Option<&'a A11yNode> {
+        if node.role == role && node.name.as_deref() == Some(name) {
+            return Some(node);
+        }
+        node.children
+            .iter()
+            .find_map(|child| find(child, role, name))
+    }
+
+    #[test]
+    fn test_heading_level() {
+        assert_eq!(heading_level("heading"), Some(1));
+        assert_eq!(heading_level("heading-3"), Some(3));
+        assert_eq!(heading_level("h6"), Some(6));
+        assert_eq!(heading_level("h7"), None);
+        assert_eq!(heading_level("hello"), None);
+    }
+
+    #[test]
+    fn test_inferred_headings() {
+        let tree = tree("= Introduction\n\n== Usage\n\nSome body text, which is the most used.");
+        let page = &tree.children[0];
+
+        let intro = find(page, A11yRole::Heading, "Introduction").expect("no heading");
+        assert_eq!(intro.level, Some(1));
+        let usage = find(page, A11yRole::Heading, "Usage").expect("no heading");
+        assert_eq!(usage.level, Some(2));
+
+        let body = "Some body text, which is the most used.";
+        assert!(find(page, A11yRole::Text, body).is_some(), "{tree:#?}");
+        assert!(find(page, A11yRole::Heading, body).is_none(), "{tree:#?}");
+    }
+
+    #[test]
+    fn test_links() {
+        let tree = tree("#link(\"https://typst.app\")[typst]\n\nAnother paragraph.");
+        let link = find(&tree, A11yRole::Link, "typst").expect("no link");
+        assert_eq!(link.href.as_deref(), Some("https://typst.app"));
+        assert!(link.children.iter().all(|n| n.role == A11yRole::Text));
+
+        let html = tree.to_aria_html();
+        assert!(html.contains("href=\"https://typst.app\""), "{html}");
+        assert!(html.contains("typst</span></a>"), "{html}");
+    }
+
+    #[test]
+    fn test_rotated_text() {
+        let tree = tree("#rotate(90deg, reflow: true)[Rotated]");
+        let text = find(&tree, A11yRole::Text, "Rotated").expect("no text");
+        let [_, _, w, h] = text.rect;
+        // The text runs vertically after the rotation.
+        assert!(h > w * 2., "{:?}", text.rect);
+    }
+}
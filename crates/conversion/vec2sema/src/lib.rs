@@ -1,4 +1,6 @@
+mod a11y;
 mod incr;
+mod search;
 
 use std::{
     borrow::Cow,
@@ -13,7 +15,9 @@ use reflexo::{
 use reflexo_vec2canvas::BrowserFontMetric;
 use unicode_width::UnicodeWidthChar;
 
+pub use a11y::*;
 pub use incr::*;
+pub use search::*;
 
 pub struct SemaTask {
     heavy: bool,
@@ -0,0 +1,384 @@
+//! Text search over the vector items.
+//!
+//! The text of a page is the concatenation of its text items in the order of
+//! the vector items. Text items on different lines, or separated by a visible
+//! gap, are joined by a space, so that a query may match text spanning several
+//! items.
+
+use std::ops::Range;
+
+use reflexo::{
+    hash::Fingerprint,
+    vector::extract::transform_rect,
+    vector::ir::{self, FlatGlyphItem, Module, Page, TextItem, VecItem},
+};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+/// The options of a text search.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Whether to match the case of the query. Otherwise, both the query and
+    /// the text are case-folded.
+    pub case_sensitive: bool,
+    /// Whether the query is a regular expression.
+    pub regex: bool,
+}
+
+/// A match of a text search.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    /// The index of the page containing the match.
+    pub page: usize,
+    /// The matched text.
+    pub text: String,
+    /// The highlight rectangles in `[x, y, width, height]`, relative to the
+    /// page. A match spanning several lines has a rectangle per line.
+    pub rects: Vec<[f32; 4]>,
+}
+
+/// Compiles the query into a regular expression.
+pub fn search_pattern(query: &str, options: &SearchOptions) -> Result<Regex, regex::Error> {
+    let pattern = if options.regex {
+        query.to_owned()
+    } else {
+        regex::escape(query)
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+}
+
+/// Searches the text of the pages.
+pub fn search_text(
+    module: &Module,
+    pages: &[Page],
+    query: &str,
+    options: &SearchOptions,
+) -> Result<Vec<SearchMatch>, regex::Error> {
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let pattern = search_pattern(query, options)?;
+    let mut matches = vec![];
+    for (idx, page) in pages.iter().enumerate() {
+        let text = PageText::new(module, page.content);
+        matches.extend(text.find(&pattern).map(|(text, rects)| SearchMatch {
+            page: idx,
+            text,
+            rects,
+        }));
+    }
+
+    Ok(matches)
+}
+
+/// A glyph in the text of a page.
+#[derive(Debug, Clone)]
+struct GlyphBox {
+    /// The byte range in the text of the page. A ligature glyph covers several
+    /// characters.
+    range: Range<usize>,
+    /// The index of the text item containing the glyph.
+    item: usize,
+    /// The left and right of the glyph in the coordinate of the text item.
+    x: (f32, f32),
+}
+
+/// A text item in the text of a page.
+#[derive(Debug, Clone)]
+struct TextRun {
+    ts: tiny_skia::Transform,
+    /// The top and bottom of the text item in the coordinate of the text item.
+    y: (f32, f32),
+}
+
+/// The searchable text of a page.
+#[derive(Debug, Default)]
+pub struct PageText {
+    text: String,
+    glyphs: Vec<GlyphBox>,
+    runs: Vec<TextRun>,
+    /// Whether a space is required before the next text item.
+    needs_space: bool,
+}
+
+impl PageText {
+    /// Extracts the text of the page.
+    pub fn new(module: &Module, content: Fingerprint) -> Self {
+        let mut text = Self::default();
+        text.walk(module, tiny_skia::Transform::identity(), content);
+        text
+    }
+
+    /// Gets the text of the page.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Finds the matches of the pattern, with the matched text and the
+    /// highlight rectangles.
+    pub fn find<'a>(
+        &'a self,
+        pattern: &'a Regex,
+    ) -> impl Iterator<Item = (String, Vec<[f32; 4]>)> + 'a {
+        pattern
+            .find_iter(&self.text)
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.as_str().to_owned(), self.rects(m.range())))
+    }
+
+    /// Gets the rectangles covering the byte range of the text, one per text
+    /// item.
+    fn rects(&self, range: Range<usize>) -> Vec<[f32; 4]> {
+        let start = self.glyphs.partition_point(|g| g.range.end <= range.start);
+
+        let mut rects: Vec<(usize, f32, f32)> = vec![];
+        for glyph in &self.glyphs[start..] {
+            if glyph.range.start >= range.end {
+                break;
+            }
+
+            // Takes the part of the glyph by the proportion of the covered
+            // bytes, e.g. the `f` in the `fi` ligature.
+            let len = glyph.range.len().max(1) as f32;
+            let (lo, hi) = glyph.x;
+            let from = range.start.max(glyph.range.start) - glyph.range.start;
+            let to = range.end.min(glyph.range.end) - glyph.range.start;
+            let x0 = lo + (hi - lo) * from as f32 / len;
+            let x1 = lo + (hi - lo) * to as f32 / len;
+
+            match rects.last_mut() {
+                Some((item, lo, hi)) if *item == glyph.item => {
+                    *lo = lo.min(x0);
+                    *hi = hi.max(x1);
+                }
+                _ => rects.push((glyph.item, x0, x1)),
+            }
+        }
+
+        rects
+            .into_iter()
+            .map(|(item, x0, x1)| {
+                let run = &self.runs[item];
+                transform_rect(run.ts, [x0, run.y.0, x1, run.y.1])
+            })
+            .collect()
+    }
+
+    fn walk(&mut self, module: &Module, ts: tiny_skia::Transform, fg: Fingerprint) {
+        let Some(item) = module.get_item(&fg) else {
+            return;
+        };
+
+        use VecItem::*;
+        match item {
+            Group(t) => {
+                for (pos, child) in t.0.iter() {
+                    let ts = ts.pre_translate(pos.x.0, pos.y.0);
+                    self.walk(module, ts, *child);
+                }
+            }
+            Item(t) => {
+                let trans: ir::Transform = t.0.clone().into();
+                let ts = ts.pre_concat(trans.into());
+                self.walk(module, ts, t.1);
+            }
+            Labelled(t) => self.walk(module, ts, t.1),
+            Text(t) => self.push_text(module, ts, t),
+            ContentHint(c) => {
+                if c.is_whitespace() {
+                    self.needs_space = true;
+                }
+            }
+            Image(..) | Link(..) | Path(..) | SizedRawHtml(..) => {}
+            None | ColorTransform(..) | Gradient(..) | Color32(..) | Pattern(..) | Html(..) => {}
+        }
+    }
+
+    fn push_text(&mut self, module: &Module, ts: tiny_skia::Transform, t: &TextItem) {
+        let Some(font) = module.get_font(&t.shape.font) else {
+            return;
+        };
+        let content = t.content.content.as_ref();
+        if content.is_empty() {
+            return;
+        }
+
+        let cap_height = (font.cap_height * t.shape.size).0;
+        let run = TextRun {
+            ts,
+            y: (-cap_height, t.shape.size.0 - cap_height),
+        };
+
+        // Separates the text item from the last one if it is on another line,
+        // or there is a gap between them.
+        if let Some(last) = self.glyphs.last() {
+            let last_run = &self.runs[last.item];
+            let [lx, ly, lw, lh] = transform_rect(
+                last_run.ts,
+                [last.x.1, last_run.y.0, last.x.1, last_run.y.1],
+            );
+            let [x, y, _, h] = transform_rect(ts, [0., run.y.0, 0., run.y.1]);
+            let center = y + h / 2.;
+            let same_line = ly <= center && center <= ly + lh;
+            let gap = x - (lx + lw);
+            if !same_line || gap > t.shape.size.0 * 0.2 || gap < -t.shape.size.0 {
+                self.needs_space = true;
+            }
+        }
+        if std::mem::take(&mut self.needs_space)
+            && !self.text.is_empty()
+            && !self.text.ends_with(char::is_whitespace)
+            && !content.starts_with(char::is_whitespace)
+        {
+            self.text.push(' ');
+        }
+
+        let item = self.runs.len();
+        self.runs.push(run);
+
+        let base = self.text.len();
+        self.text.push_str(content);
+
+        let advances = t.content.glyphs.iter().map(|(_, advance, _)| advance.x.0);
+        let ranges = glyph_ranges(font, t);
+        let glyphs = match ranges {
+            Some(ranges) => {
+                let mut x = 0.;
+                ranges
+                    .into_iter()
+                    .zip(advances)
+                    .map(|(range, advance)| {
+                        let lo = x;
+                        x += advance;
+                        (range, (lo, x))
+                    })
+                    .collect::<Vec<_>>()
+            }
+            // Distributes the width evenly to the characters if the glyphs
+            // cannot be mapped to the characters.
+            None => {
+                let width = t.width().0;
+                let count = content.chars().count() as f32;
+                content
+                    .char_indices()
+                    .enumerate()
+                    .map(|(idx, (start, c))| {
+                        let lo = width * idx as f32 / count;
+                        let hi = width * (idx + 1) as f32 / count;
+                        (start..start + c.len_utf8(), (lo, hi))
+                    })
+                    .collect()
+            }
+        };
+
+        self.glyphs
+            .extend(glyphs.into_iter().map(|(range, x)| GlyphBox {
+                range: base + range.start..base + range.end,
+                item,
+                x,
+            }));
+    }
+}
+
+/// Maps the glyphs of the text item to the byte ranges of its content.
+///
+/// A ligature glyph lowered by `typst2vec` covers `ligature_len` bytes, and
+/// any other glyph covers a character. Returns `None` if the glyphs don't
+/// cover the content exactly, e.g. a character is shaped into several glyphs.
+fn glyph_ranges(font: &ir::FontItem, t: &TextItem) -> Option<Vec<Range<usize>>> {
+    let content = t.content.content.as_ref();
+
+    let mut pos = 0;
+    let mut ranges = Vec::with_capacity(t.content.glyphs.len());
+    for (_, _, glyph) in t.content.glyphs.iter() {
+        let ligature_len = match font.get_glyph(*glyph).map(|g| g.as_ref()) {
+            Some(FlatGlyphItem::Outline(g)) => g.ligature_len as usize,
+            Some(FlatGlyphItem::Image(g)) => g.ligature_len as usize,
+            _ => 0,
+        };
+
+        let c = content.get(pos..)?.chars().next()?;
+        let end = (pos + ligature_len.max(c.len_utf8())).min(content.len());
+        if !content.is_char_boundary(end) {
+            return None;
+        }
+        ranges.push(pos..end);
+        pos = end;
+    }
+
+    (pos == content.len()).then_some(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use typst_ts_test_common::compile::lower_paged;
+
+    use super::*;
+
+    fn search(source: &str, query: &str, options: &SearchOptions) -> Vec<SearchMatch> {
+        let (module, pages) = lower_paged(source);
+        search_text(&module, &pages, query, options).unwrap()
+    }
+
+    fn width(m: &SearchMatch) -> f32 {
+        m.rects.iter().map(|[_, _, w, _]| w).sum()
+    }
+
+    #[test]
+    fn test_search_ligature() {
+        // The `fi` in Libertinus is shaped into a ligature.
+        let source = "find";
+        let options = SearchOptions::default();
+
+        let f = search(source, "F", &options);
+        let fi = search(source, "fi", &options);
+        assert_eq!(f.len(), 1);
+        assert_eq!(fi.len(), 1);
+        assert_eq!(fi[0].text, "fi");
+        // The `f` takes a part of the ligature glyph.
+        assert!(
+            0. < width(&f[0]) && width(&f[0]) < width(&fi[0]),
+            "{f:?} {fi:?}"
+        );
+    }
+
+    #[test]
+    fn test_search_across_items() {
+        let options = SearchOptions {
+            case_sensitive: true,
+            regex: true,
+        };
+
+        let matches = search("Hello *world*", r"lo\s+wor", &options);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].page, 0);
+        assert_eq!(matches[0].text, "lo wor");
+        // The regular and the bold text are different items.
+        assert_eq!(matches[0].rects.len(), 2);
+    }
+
+    #[test]
+    fn test_search_pages() {
+        let source = "Hello\n#pagebreak()\nHello";
+        let matches = search(source, "hello", &SearchOptions::default());
+        let pages = matches.iter().map(|m| m.page).collect::<Vec<_>>();
+        assert_eq!(pages, [0, 1]);
+    }
+
+    #[test]
+    fn test_search_rotated() {
+        let source = "#rotate(90deg, reflow: true)[Rotated]";
+        let matches = search(source, "Rotated", &SearchOptions::default());
+        assert_eq!(matches.len(), 1);
+        let [[_, _, w, h]] = matches[0].rects[..] else {
+            panic!("{matches:?}");
+        };
+        // The text runs vertically after the rotation.
+        assert!(h > w * 2., "{matches:?}");
+    }
+}
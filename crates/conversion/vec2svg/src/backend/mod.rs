@@ -37,6 +37,10 @@ pub trait DynExportFeature {
     fn should_attach_debug_info(&self) -> bool;
 
     fn should_aware_html_entity(&self) -> bool;
+
+    /// The url of the document defining the glyphs, which is empty if the
+    /// glyphs are defined in the same document.
+    fn glyph_href_base(&self) -> &str;
 }
 
 /// A generated text content.
@@ -304,6 +308,7 @@ impl SvgTextBuilder {
         glyph: u32,
         fill: Option<Arc<PaintObj>>,
         stroke: Option<Arc<PaintObj>>,
+        glyph_href_base: &str,
         mut gradient_with_aspect_ratio: impl FnMut(Fingerprint, f32) -> Fingerprint,
     ) {
         let adjusted_x_offset = (pos.x.0 * 2.).round();
@@ -369,7 +374,7 @@ impl SvgTextBuilder {
 
         self.content.push(SvgText::Plain(format!(
             // r##"<typst-glyph x="{}" href="#{}"/>"##,
-            r##"<use x="{}" y="{}" href="#{}"{fill_id}{stroke_id}/>"##,
+            r##"<use x="{}" y="{}" href="{glyph_href_base}#{}"{fill_id}{stroke_id}/>"##,
             adjusted_x_offset / 2.,
             adjusted_y_offset / 2.,
             glyph_id
@@ -575,7 +580,7 @@ impl<
         })));
     }
 
-    fn render_glyph(&mut self, _ctx: &mut C, pos: Axes<Scalar>, font: &FontItem, glyph: u32) {
+    fn render_glyph(&mut self, ctx: &mut C, pos: Axes<Scalar>, font: &FontItem, glyph: u32) {
         let adjusted_x_offset = (pos.x.0 * 2.).round() / 2.;
         let adjusted_y_offset = (pos.y.0 * 2.).round() / 2.;
 
@@ -589,7 +594,8 @@ impl<
 
         self.content.push(SvgText::Plain(format!(
             // r##"<typst-glyph x="{}" href="#{}"/>"##,
-            r##"<use x="{adjusted_x_offset}" y="{adjusted_y_offset}" href="#{glyph_id}"/>"##
+            r##"<use x="{adjusted_x_offset}" y="{adjusted_y_offset}" href="{}#{glyph_id}"/>"##,
+            ctx.glyph_href_base()
         )));
     }
 
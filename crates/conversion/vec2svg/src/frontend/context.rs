@@ -62,6 +62,11 @@ pub struct RenderContext<'m, 't, Feat: ExportFeature> {
     pub use_stable_glyph_id: bool,
     /// See [`ExportFeature`].
     pub should_rasterize_text: bool,
+    /// See [`ExportFeature`].
+    pub aware_html_entity: bool,
+    /// The url of the document defining the glyphs, which is empty if the
+    /// glyphs are defined in the same document.
+    pub glyph_href_base: ImmutStr,
 
     pub _feat_phantom: std::marker::PhantomData<Feat>,
 }
@@ -69,27 +74,32 @@ pub struct RenderContext<'m, 't, Feat: ExportFeature> {
 impl<Feat: ExportFeature> DynExportFeature for RenderContext<'_, '_, Feat> {
     #[inline]
     fn should_render_text_element(&self) -> bool {
-        Feat::SHOULD_RENDER_TEXT_ELEMENT && self.should_render_text_element
+        self.should_render_text_element
     }
 
     #[inline]
     fn use_stable_glyph_id(&self) -> bool {
-        Feat::USE_STABLE_GLYPH_ID && self.use_stable_glyph_id
+        self.use_stable_glyph_id
     }
 
     #[inline]
     fn should_rasterize_text(&self) -> bool {
-        Feat::SHOULD_RASTERIZE_TEXT && self.should_rasterize_text
+        self.should_rasterize_text
     }
 
     #[inline]
     fn should_attach_debug_info(&self) -> bool {
-        Feat::SHOULD_ATTACH_DEBUG_INFO && self.should_attach_debug_info
+        self.should_attach_debug_info
     }
 
     #[inline]
     fn should_aware_html_entity(&self) -> bool {
-        Feat::AWARE_HTML_ENTITY
+        self.aware_html_entity
+    }
+
+    #[inline]
+    fn glyph_href_base(&self) -> &str {
+        &self.glyph_href_base
     }
 }
 
@@ -259,6 +269,7 @@ impl<Feat: ExportFeature> RenderContext<'_, '_, Feat> {
                 let mut size = Axes { x: 0f32, y: 0f32 };
                 let fill = fill.clone();
                 let stroke = stroke.clone();
+                let glyph_href_base = self.glyph_href_base.clone();
                 for (s, g) in text.render_glyphs(upem, &mut size) {
                     group_ctx.render_glyph_slow(
                         s,
@@ -266,6 +277,7 @@ impl<Feat: ExportFeature> RenderContext<'_, '_, Feat> {
                         g,
                         fill.clone(),
                         Some(stroke.clone()),
+                        &glyph_href_base,
                         |item, aspect_ratio| self.gradient_with_aspect_ratio(item, aspect_ratio),
                     );
                 }
@@ -283,6 +295,7 @@ impl<Feat: ExportFeature> RenderContext<'_, '_, Feat> {
             (Some(fill), None) => {
                 let mut size = Axes { x: 0f32, y: 0f32 };
                 let fill = fill.clone();
+                let glyph_href_base = self.glyph_href_base.clone();
                 for (s, g) in text.render_glyphs(upem, &mut size) {
                     group_ctx.render_glyph_slow(
                         s,
@@ -290,6 +303,7 @@ impl<Feat: ExportFeature> RenderContext<'_, '_, Feat> {
                         g,
                         Some(fill.clone()),
                         None,
+                        &glyph_href_base,
                         |item, aspect_ratio| self.gradient_with_aspect_ratio(item, aspect_ratio),
                     );
                 }
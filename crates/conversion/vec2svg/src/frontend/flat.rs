@@ -10,7 +10,7 @@ use reflexo_typst2vec::pass::Typst2VecPass;
 
 use crate::{
     backend::{generate_text, SvgText, SvgTextNode},
-    ExportFeature, SvgDataSelection, SvgExporter, SvgTask,
+    ExportFeature, SvgDataSelection, SvgExportOptions, SvgExporter, SvgTask,
 };
 
 impl<Feat: ExportFeature> SvgTask<'_, Feat> {
@@ -66,6 +66,16 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
         VecDocument { pages, module }
     }
 
+    /// Lowers the pages at the given (zero-based) indices of the document.
+    /// The module only contains the items reachable from these pages.
+    pub fn svg_doc_by_indices(output: &TypstPagedDocument, indices: &[usize]) -> VecDocument {
+        let typst2vec = Typst2VecPass::default();
+        let pages = typst2vec.paged_by_indices(output, indices);
+
+        let module = typst2vec.finalize();
+        VecDocument { pages, module }
+    }
+
     pub fn render_flat_svg(
         module: &Module,
         pages: &[Page],
@@ -73,4 +83,13 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
     ) -> String {
         generate_text(Self::render(module, pages, parts))
     }
+
+    pub fn render_flat_svg_with_options(
+        module: &Module,
+        pages: &[Page],
+        parts: Option<SvgDataSelection>,
+        options: SvgExportOptions,
+    ) -> String {
+        generate_text(Self::render_with_options(module, pages, parts, options))
+    }
 }
@@ -2,17 +2,19 @@ pub(crate) mod context;
 pub(crate) mod dynamic_layout;
 pub(crate) mod flat;
 pub(crate) mod incremental;
+pub(crate) mod paged;
 
 pub use dynamic_layout::DynamicLayoutSvgExporter;
 pub use incremental::{IncrSvgDocClient, IncrSvgDocServer, IncrementalRenderContext};
+pub use paged::{SvgGlyphSprite, SvgPages};
 
 use std::{collections::HashSet, f32::consts::TAU, fmt::Write, sync::Arc};
 
 use reflexo::hash::{item_hash128, Fingerprint, FingerprintBuilder};
 use reflexo_typst2vec::{
     ir::{
-        self, Axes, FlatGlyphItem, GlyphRef, GradientItem, GradientKind, GradientStyle, Module,
-        Page, Scalar, Size, VecItem,
+        self, Axes, FlatGlyphItem, GlyphRef, GradientItem, GradientKind, GradientStyle, ImmutStr,
+        Module, Page, Scalar, Size, VecItem,
     },
     utils::ToCssExt,
     IntoTypst, TryIntoTypst,
@@ -25,7 +27,7 @@ use typst::{
 
 use crate::{
     backend::{SvgGlyphBuilder, SvgText, SvgTextNode},
-    ExportFeature, SvgDataSelection,
+    ExportFeature, SvgDataSelection, SvgExportOptions,
 };
 use context::{PaintFillMap, RenderContext, StyleDefMap};
 
@@ -323,18 +325,47 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
         module: &Module,
         pages: &[Page],
         parts: Option<SvgDataSelection>,
+    ) -> Vec<SvgText> {
+        Self::render_with_options(module, pages, parts, SvgExportOptions::of::<Feat>())
+    }
+
+    /// Render pages into the entire SVG with the runtime options, which
+    /// override the options of `Feat`.
+    pub fn render_with_options(
+        module: &Module,
+        pages: &[Page],
+        parts: Option<SvgDataSelection>,
+        options: SvgExportOptions,
+    ) -> Vec<SvgText> {
+        Self::render_with_glyph_href(module, pages, parts, options, "")
+    }
+
+    /// Render pages into the entire SVG, referencing the glyphs defined by
+    /// the document at `glyph_href_base`, e.g. `glyphs.svg`. The glyphs are
+    /// defined in the SVG itself if `glyph_href_base` is empty.
+    pub fn render_with_glyph_href(
+        module: &Module,
+        pages: &[Page],
+        parts: Option<SvgDataSelection>,
+        options: SvgExportOptions,
+        glyph_href_base: &str,
     ) -> Vec<SvgText> {
         if !module.glyphs.is_empty() {
             panic!("Glyphs should be loaded before rendering.");
         }
 
-        let mut t = SvgTask::<Feat>::default();
+        let mut t = SvgTask::<Feat>::with_options(options);
+        t.glyph_href_base = glyph_href_base.into();
         let mut svg_body = vec![];
         t.render(module, pages, &mut svg_body);
         let patterns = t.render_patterns(module);
 
         // note in order!: pattern may use glyphs
-        let glyphs = t.render_glyphs(module.glyphs_all());
+        let glyphs = if glyph_href_base.is_empty() {
+            t.render_glyphs(module.glyphs_all())
+        } else {
+            vec![]
+        };
 
         let gradients = t
             .gradients
@@ -352,7 +383,7 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
             // base style
         ];
 
-        if Feat::WITH_BUILTIN_CSS && with_css {
+        if options.with_builtin_css && with_css {
             svg.push(r#"<style type="text/css">"#.into());
             svg.push(include_str!("./typst.svg.css").into());
             svg.push("</style>".into());
@@ -375,7 +406,7 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
             svg.append(&mut svg_body);
         }
 
-        if Feat::WITH_RESPONSIVE_JS && with_js {
+        if options.with_responsive_js && with_js {
             // attach the javascript for animations
             svg.push(r#"<script type="text/javascript">"#.into());
             svg.push(include_str!("./typst.svg.js").into());
@@ -476,6 +507,11 @@ pub struct SvgTask<'a, Feat: ExportFeature> {
     pub gradients: GradientDefMap,
     /// Stores the patterns used in the document.
     pub patterns: PaintFillMap,
+    /// The options of the task, which default to the options of `Feat`.
+    pub options: SvgExportOptions,
+    /// The url of the document defining the glyphs, which is empty if the
+    /// glyphs are defined in the same document.
+    pub glyph_href_base: ImmutStr,
 
     _feat_phantom: std::marker::PhantomData<&'a Feat>,
 }
@@ -483,19 +519,26 @@ pub struct SvgTask<'a, Feat: ExportFeature> {
 /// Unfortunately, `Default` derive does not work for generic structs.
 impl<Feat: ExportFeature> Default for SvgTask<'_, Feat> {
     fn default() -> Self {
+        Self::with_options(SvgExportOptions::of::<Feat>())
+    }
+}
+
+impl<Feat: ExportFeature> SvgTask<'_, Feat> {
+    /// Creates a task with the runtime options.
+    pub fn with_options(options: SvgExportOptions) -> Self {
         Self {
             fingerprint_builder: FingerprintBuilder::default(),
 
             style_defs: StyleDefMap::default(),
             gradients: GradientDefMap::default(),
             patterns: PaintFillMap::default(),
+            options,
+            glyph_href_base: ImmutStr::from(""),
 
             _feat_phantom: std::marker::PhantomData,
         }
     }
-}
 
-impl<Feat: ExportFeature> SvgTask<'_, Feat> {
     /// Return integral page size for showing document.
     pub(crate) fn page_size(sz: Size) -> Axes<u32> {
         let (width_px, height_px) = {
@@ -522,10 +565,12 @@ impl<Feat: ExportFeature> SvgTask<'_, Feat> {
             gradients: &mut self.gradients,
             patterns: &mut self.patterns,
 
-            should_attach_debug_info: Feat::SHOULD_ATTACH_DEBUG_INFO,
-            should_render_text_element: true,
-            use_stable_glyph_id: true,
-            should_rasterize_text: true,
+            should_attach_debug_info: self.options.should_attach_debug_info,
+            should_render_text_element: self.options.should_render_text_element,
+            use_stable_glyph_id: self.options.use_stable_glyph_id,
+            should_rasterize_text: self.options.should_rasterize_text,
+            aware_html_entity: self.options.aware_html_entity,
+            glyph_href_base: self.glyph_href_base.clone(),
 
             _feat_phantom: Default::default(),
         }
@@ -789,4 +834,30 @@ mod tests {
         );
         assert!(theta2 < theta1);
     }
+
+    #[test]
+    fn render_with_runtime_options() {
+        type Exporter = SvgExporter<crate::SvgExportFeature>;
+        let module = Module::default();
+        let render = |options| {
+            crate::backend::generate_text(Exporter::render_with_options(
+                &module,
+                &[],
+                None,
+                options,
+            ))
+        };
+
+        let plain = render(SvgExportOptions::default());
+        assert!(plain.contains(include_str!("./typst.svg.css")));
+        assert!(!plain.contains("<script"));
+
+        let rich = render(SvgExportOptions {
+            with_builtin_css: false,
+            with_responsive_js: true,
+            ..SvgExportOptions::default()
+        });
+        assert!(!rich.contains(include_str!("./typst.svg.css")));
+        assert!(rich.contains("<script"));
+    }
 }
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use reflexo::typst::TypstPagedDocument;
+use reflexo_typst2vec::ir::{FlatGlyphItem, GlyphRef, ImmutStr};
+
+use crate::{
+    backend::{generate_text, SvgText},
+    transform, ExportFeature, SvgExportOptions, SvgExporter, SvgMinifyOptions, SvgTask,
+};
+
+/// Where the glyphs of the standalone page SVGs are defined.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum SvgGlyphSprite {
+    /// Each page defines the glyphs it uses.
+    #[default]
+    Inline,
+    /// The pages reference the glyphs defined by a shared sprite sheet at
+    /// the url, e.g. `glyphs.svg`.
+    External(ImmutStr),
+}
+
+/// The standalone SVGs of a document.
+#[derive(Debug, Clone, Default)]
+pub struct SvgPages {
+    /// The SVG of each page, paired with the (zero-based) page index.
+    pub pages: Vec<(usize, String)>,
+    /// The sprite sheet defining the glyphs used by the pages, which is
+    /// only present if the glyph sprite is [`SvgGlyphSprite::External`].
+    pub glyphs: Option<String>,
+}
+
+impl<Feat: ExportFeature> SvgExporter<Feat> {
+    /// Render the pages at the given (zero-based) indices into standalone
+    /// SVGs.
+    ///
+    /// Each page is lowered on its own, so that a page only carries the items
+    /// it uses. The glyphs are placed according to `sprite`.
+    pub fn render_pages_standalone(
+        output: &TypstPagedDocument,
+        indices: &[usize],
+        options: SvgExportOptions,
+        sprite: &SvgGlyphSprite,
+    ) -> SvgPages {
+        let glyph_href_base = match sprite {
+            SvgGlyphSprite::Inline => "",
+            SvgGlyphSprite::External(url) => url.as_ref(),
+        };
+
+        let mut seen = HashSet::new();
+        let mut glyphs = vec![];
+        let pages = indices
+            .iter()
+            .map(|&idx| {
+                let mut doc = Self::svg_doc_by_indices(output, &[idx]);
+                doc.module.prepare_glyphs();
+                if !glyph_href_base.is_empty() {
+                    for (glyph_ref, item) in doc.module.glyphs_all() {
+                        if seen.insert(glyph_ref) {
+                            glyphs.push((glyph_ref, item.clone()));
+                        }
+                    }
+                }
+
+                let svg = Self::render_with_glyph_href(
+                    &doc.module,
+                    &doc.pages,
+                    None,
+                    options,
+                    glyph_href_base,
+                );
+                (
+                    idx,
+                    generate_text(transform::minify_with_options(svg, &options.minify)),
+                )
+            })
+            .collect();
+
+        let glyphs = (!glyph_href_base.is_empty()).then(|| {
+            let glyphs = glyphs.iter().map(|(r, item)| (*r, item));
+            Self::render_glyph_sprite(glyphs, options)
+        });
+
+        SvgPages { pages, glyphs }
+    }
+
+    /// Render the glyphs into a sprite sheet, whose glyphs are referenced by
+    /// `<use href="sprite.svg#id"/>`.
+    ///
+    /// The `options` should be the ones of the pages referencing the sprite,
+    /// except that the glyph ids are never shortened.
+    pub fn render_glyph_sprite<'a>(
+        glyphs: impl Iterator<Item = (GlyphRef, &'a FlatGlyphItem)>,
+        options: SvgExportOptions,
+    ) -> String {
+        let mut t = SvgTask::<Feat>::with_options(options);
+
+        let mut svg = vec![
+            SvgText::Plain(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">"#
+                    .to_owned(),
+            ),
+            r#"<defs class="glyph">"#.into(),
+        ];
+        svg.extend(t.render_glyphs(glyphs));
+        svg.push("</defs>".into());
+        svg.push("</svg>".into());
+
+        // The ids of the glyphs are referenced by the pages, so they are kept.
+        let minify = SvgMinifyOptions {
+            shorten_ids: false,
+            ..options.minify
+        };
+        generate_text(transform::minify_with_options(svg, &minify))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SvgExportFeature;
+
+    #[test]
+    fn glyph_sprite_is_standalone_svg() {
+        let sprite = SvgExporter::<SvgExportFeature>::render_glyph_sprite(
+            std::iter::empty(),
+            SvgExportOptions::default(),
+        );
+        assert!(sprite.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(sprite.ends_with("<defs class=\"glyph\"></defs></svg>"));
+    }
+}
@@ -4,6 +4,7 @@
 // color export
 
 use reflexo::typst::{TypstDocumentTrait, TypstPagedDocument};
+use serde::{Deserialize, Serialize};
 
 /// re-export the core types.
 pub use reflexo_typst2vec::font::{FontGlyphProvider, GlyphProvider, IGlyphProvider};
@@ -26,6 +27,7 @@ pub use frontend::{
 pub use frontend::{
     DynamicLayoutSvgExporter, IncrSvgDocClient, IncrSvgDocServer, IncrementalRenderContext,
 };
+pub use frontend::{SvgGlyphSprite, SvgPages};
 
 /// Useful transform for SVG Items.
 pub(crate) mod transform;
@@ -74,6 +76,101 @@ pub trait ExportFeature: Send + Sync + 'static {
     const AWARE_HTML_ENTITY: bool;
 }
 
+/// The options of svg export, which are decided at runtime instead of by an
+/// [`ExportFeature`]. See [`ExportFeature`] for the meaning of each option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SvgExportOptions {
+    /// Whether to attach debug info to svg elements.
+    pub should_attach_debug_info: bool,
+    /// Whether to render text element.
+    pub should_render_text_element: bool,
+    /// Whether to use stable glyph id.
+    pub use_stable_glyph_id: bool,
+    /// Whether to rasterize text.
+    pub should_rasterize_text: bool,
+    /// Whether to include builtin css.
+    pub with_builtin_css: bool,
+    /// Whether to include js for interactive and responsive actions.
+    pub with_responsive_js: bool,
+    /// Also escape html entity.
+    pub aware_html_entity: bool,
+    /// The minification passes applied to the svg.
+    pub minify: SvgMinifyOptions,
+}
+
+impl SvgExportOptions {
+    /// Gets the options of a feature set.
+    pub const fn of<Feat: ExportFeature>() -> Self {
+        Self {
+            should_attach_debug_info: Feat::SHOULD_ATTACH_DEBUG_INFO,
+            should_render_text_element: Feat::SHOULD_RENDER_TEXT_ELEMENT,
+            use_stable_glyph_id: Feat::USE_STABLE_GLYPH_ID,
+            should_rasterize_text: Feat::SHOULD_RASTERIZE_TEXT,
+            with_builtin_css: Feat::WITH_BUILTIN_CSS,
+            with_responsive_js: Feat::WITH_RESPONSIVE_JS,
+            aware_html_entity: Feat::AWARE_HTML_ENTITY,
+            minify: SvgMinifyOptions::none(),
+        }
+    }
+}
+
+/// The minification passes applied to the rendered svg, in addition to folding
+/// the nested transforms, which is always done.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SvgMinifyOptions {
+    /// The number of fraction digits kept in coordinates, i.e. path data,
+    /// positions, sizes and translations. The coordinates are kept as is if
+    /// not set.
+    pub precision: Option<u8>,
+    /// Whether to compact path data, e.g. `M 0 0 L 0.50 0` to `M0 0 .5 0`.
+    pub compact_path: bool,
+    /// Whether to drop attributes without effects, e.g. identity transforms.
+    pub drop_redundant_attributes: bool,
+    /// Whether to remove empty groups and to inline groups without
+    /// attributes.
+    pub collapse_groups: bool,
+    /// Whether to move the paths used more than once into `<defs>` and to
+    /// reference them by `<use>`.
+    pub dedup_paths: bool,
+    /// Whether to rename the ids to short ones.
+    pub shorten_ids: bool,
+}
+
+impl SvgMinifyOptions {
+    /// Runs no additional pass.
+    pub const fn none() -> Self {
+        Self {
+            precision: None,
+            compact_path: false,
+            drop_redundant_attributes: false,
+            collapse_groups: false,
+            dedup_paths: false,
+            shorten_ids: false,
+        }
+    }
+
+    /// Runs all the passes, keeping two fraction digits in coordinates.
+    pub const fn full() -> Self {
+        Self {
+            precision: Some(2),
+            compact_path: true,
+            drop_redundant_attributes: true,
+            collapse_groups: true,
+            dedup_paths: true,
+            shorten_ids: true,
+        }
+    }
+}
+
+/// Defaults to the options of [`SvgExportFeature`].
+impl Default for SvgExportOptions {
+    fn default() -> Self {
+        Self::of::<SvgExportFeature>()
+    }
+}
+
 /// The default feature set which is used for exporting full-fledged svg.
 pub struct DefaultExportFeature;
 pub type DefaultSvgTask = SvgTask<'static, DefaultExportFeature>;
@@ -108,9 +205,47 @@ impl ExportFeature for SvgExportFeature {
 
 /// Render SVG wrapped with html for [`TypstPagedDocument`].
 pub fn render_svg_html<Feat: ExportFeature>(output: &TypstPagedDocument) -> String {
-    let mut doc = SvgExporter::<Feat>::svg_doc(output);
+    render_svg_html_with_options(output, SvgExportOptions::of::<Feat>())
+}
+
+/// Render SVG wrapped with html for the pages at the given (zero-based)
+/// indices of [`TypstPagedDocument`].
+pub fn render_svg_html_by_indices<Feat: ExportFeature>(
+    output: &TypstPagedDocument,
+    indices: &[usize],
+) -> String {
+    render_svg_html_by_indices_with_options(output, indices, SvgExportOptions::of::<Feat>())
+}
+
+/// Render SVG wrapped with html for [`TypstPagedDocument`] with the runtime
+/// options.
+pub fn render_svg_html_with_options(
+    output: &TypstPagedDocument,
+    options: SvgExportOptions,
+) -> String {
+    let doc = SvgExporter::<SvgExportFeature>::svg_doc(output);
+    render_svg_html_doc(output, doc, options)
+}
+
+/// Render SVG wrapped with html for the pages at the given (zero-based)
+/// indices of [`TypstPagedDocument`] with the runtime options.
+pub fn render_svg_html_by_indices_with_options(
+    output: &TypstPagedDocument,
+    indices: &[usize],
+    options: SvgExportOptions,
+) -> String {
+    let doc = SvgExporter::<SvgExportFeature>::svg_doc_by_indices(output, indices);
+    render_svg_html_doc(output, doc, options)
+}
+
+fn render_svg_html_doc(
+    output: &TypstPagedDocument,
+    mut doc: VecDocument,
+    options: SvgExportOptions,
+) -> String {
+    type UsingExporter = SvgExporter<SvgExportFeature>;
     doc.module.prepare_glyphs();
-    let mut svg = SvgExporter::<Feat>::render(&doc.module, &doc.pages, None);
+    let mut svg = UsingExporter::render_with_options(&doc.module, &doc.pages, None, options);
 
     // wrap SVG with html
     let mut html: Vec<SvgText> = Vec::with_capacity(svg.len() + 3);
@@ -126,14 +261,52 @@ pub fn render_svg_html<Feat: ExportFeature>(output: &TypstPagedDocument) -> Stri
     html.push(r#"</title></head><body>"#.into());
     html.append(&mut svg);
     html.push(r#"</body></html>"#.into());
-    generate_text(transform::minify(html))
+    generate_text(transform::minify_with_options(html, &options.minify))
 }
 
 /// Render SVG for [`TypstPagedDocument`].
 pub fn render_svg(output: &TypstPagedDocument) -> String {
+    render_svg_with_options(output, SvgExportOptions::default())
+}
+
+/// Render SVG for the pages at the given (zero-based) indices of
+/// [`TypstPagedDocument`].
+pub fn render_svg_by_indices(output: &TypstPagedDocument, indices: &[usize]) -> String {
+    render_svg_by_indices_with_options(output, indices, SvgExportOptions::default())
+}
+
+/// Render SVG for [`TypstPagedDocument`] with the runtime options.
+pub fn render_svg_with_options(output: &TypstPagedDocument, options: SvgExportOptions) -> String {
+    let doc = SvgExporter::<SvgExportFeature>::svg_doc(output);
+    render_svg_doc(doc, options)
+}
+
+/// Render SVG for the pages at the given (zero-based) indices of
+/// [`TypstPagedDocument`] with the runtime options.
+pub fn render_svg_by_indices_with_options(
+    output: &TypstPagedDocument,
+    indices: &[usize],
+    options: SvgExportOptions,
+) -> String {
+    let doc = SvgExporter::<SvgExportFeature>::svg_doc_by_indices(output, indices);
+    render_svg_doc(doc, options)
+}
+
+/// Render a standalone SVG for each page at the given (zero-based) indices
+/// of [`TypstPagedDocument`] with the runtime options. The glyphs are either
+/// defined by each page or by a shared sprite sheet, see [`SvgGlyphSprite`].
+pub fn render_svg_pages(
+    output: &TypstPagedDocument,
+    indices: &[usize],
+    options: SvgExportOptions,
+    sprite: &SvgGlyphSprite,
+) -> SvgPages {
+    SvgExporter::<SvgExportFeature>::render_pages_standalone(output, indices, options, sprite)
+}
+
+fn render_svg_doc(mut doc: VecDocument, options: SvgExportOptions) -> String {
     type UsingExporter = SvgExporter<SvgExportFeature>;
-    let mut doc = UsingExporter::svg_doc(output);
     doc.module.prepare_glyphs();
-    let svg_text = UsingExporter::render(&doc.module, &doc.pages, None);
-    generate_text(transform::minify(svg_text))
+    let svg_text = UsingExporter::render_with_options(&doc.module, &doc.pages, None, options);
+    generate_text(transform::minify_with_options(svg_text, &options.minify))
 }
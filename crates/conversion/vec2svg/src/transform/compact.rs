@@ -0,0 +1,276 @@
+//! Compaction of numbers, path data and transforms in attribute values.
+
+use std::borrow::Cow;
+
+/// Formats a number with at most `precision` fraction digits if given, and
+/// drops the redundant zeros, e.g. `-0.50` to `-.5`.
+pub fn compact_number(num: &str, precision: Option<u8>) -> Cow<'_, str> {
+    let reformat = precision.is_some() || num.contains(['e', 'E']);
+    let mut num = if reformat {
+        let Ok(value) = num.parse::<f64>() else {
+            return Cow::Borrowed(num);
+        };
+        match precision {
+            Some(precision) => format!("{value:.0$}", precision as usize),
+            None => value.to_string(),
+        }
+    } else {
+        num.to_owned()
+    };
+
+    if num.contains('.') {
+        num.truncate(num.trim_end_matches('0').trim_end_matches('.').len());
+    }
+    if num.is_empty() || num == "-0" || num == "-" {
+        return Cow::Borrowed("0");
+    }
+    if let Some(frac) = num.strip_prefix("0.") {
+        num = format!(".{frac}");
+    } else if let Some(frac) = num.strip_prefix("-0.") {
+        num = format!("-.{frac}");
+    }
+
+    Cow::Owned(num)
+}
+
+/// Appends a compacted number to a list of numbers, inserting a separator
+/// only if necessary.
+fn push_number(out: &mut String, num: &str, after_number: bool) {
+    if after_number {
+        // `1.5.5` is read as `1.5 .5`, and `1-2` as `1 -2`
+        let prev_has_dot = out
+            .rsplit(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .next()
+            .is_some_and(|prev| prev.contains('.'));
+        let need_sep = !(num.starts_with('-') || num.starts_with('.') && prev_has_dot);
+        if need_sep {
+            out.push(' ');
+        }
+    }
+    out.push_str(num);
+}
+
+/// Returns the length of the number at the beginning of `s`.
+fn number_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut pos = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        pos += 1;
+    }
+    let mut seen_dot = false;
+    while let Some(&c) = bytes.get(pos) {
+        match c {
+            b'0'..=b'9' => {}
+            b'.' if !seen_dot => seen_dot = true,
+            _ => break,
+        }
+        pos += 1;
+    }
+    if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+        let mut exp = pos + 1;
+        if matches!(bytes.get(exp), Some(b'+' | b'-')) {
+            exp += 1;
+        }
+        if bytes.get(exp).is_some_and(u8::is_ascii_digit) {
+            pos = exp;
+            while bytes.get(pos).is_some_and(u8::is_ascii_digit) {
+                pos += 1;
+            }
+        }
+    }
+    pos
+}
+
+/// Compacts path data, e.g. `M 0 0 L 10.50 0 L 10 10 Z` to
+/// `M0 0 10.5 0 10 10Z`. Returns `None` if the path data is malformed.
+pub fn compact_path(d: &str, precision: Option<u8>) -> Option<String> {
+    let mut out = String::with_capacity(d.len());
+    let mut rest = d;
+    // The command that is implied if the command letter is omitted.
+    let mut implicit = None;
+    let mut after_number = false;
+    // The index of the next argument of an arc command.
+    let mut arc_arg = 0;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        let Some(c) = rest.chars().next() else {
+            break;
+        };
+
+        if c.is_ascii_alphabetic() {
+            if !"MmLlHhVvCcSsQqTtAaZz".contains(c) {
+                return None;
+            }
+            rest = &rest[1..];
+            arc_arg = 0;
+            if implicit == Some(c) && !matches!(c, 'M' | 'm') {
+                continue;
+            }
+            out.push(c);
+            after_number = false;
+            implicit = match c {
+                'M' => Some('L'),
+                'm' => Some('l'),
+                'Z' | 'z' => None,
+                c => Some(c),
+            };
+            continue;
+        }
+
+        implicit?;
+        let is_arc = matches!(implicit, Some('A' | 'a'));
+        // the flags of an arc may be written without separators, e.g. `a1 1 0 00 1 1`
+        let len = if is_arc && matches!(arc_arg, 3 | 4) {
+            matches!(c, '0' | '1').then_some(1)?
+        } else {
+            Some(number_len(rest)).filter(|&len| len > 0)?
+        };
+        let num = if is_arc && (2..=4).contains(&arc_arg) {
+            // the rotation and flags are not coordinates
+            compact_number(&rest[..len], None)
+        } else {
+            compact_number(&rest[..len], precision)
+        };
+        push_number(&mut out, &num, after_number);
+        after_number = true;
+        arc_arg = (arc_arg + 1) % 7;
+        rest = &rest[len..];
+    }
+
+    Some(out)
+}
+
+/// Compacts a list of numbers, e.g. a `viewBox`.
+pub fn compact_numbers(value: &str, precision: Option<u8>) -> Option<String> {
+    let mut out = String::with_capacity(value.len());
+    for (idx, num) in value
+        .split(|c: char| c.is_ascii_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .enumerate()
+    {
+        if number_len(num) != num.len() {
+            return None;
+        }
+        if idx > 0 {
+            out.push(' ');
+        }
+        out.push_str(&compact_number(num, precision));
+    }
+    Some(out)
+}
+
+/// Compacts a transform list, e.g. `translate(0.500, 0) scale(1.0,-1.0)` to
+/// `translate(.5,0) scale(1,-1)`. Only the translations are rounded to the
+/// `precision`. The identity transforms are dropped if `drop_identity` is
+/// set. Returns `None` if the transform list is malformed.
+pub fn compact_transform(
+    value: &str,
+    precision: Option<u8>,
+    drop_identity: bool,
+) -> Option<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        let open = rest.find('(')?;
+        let close = rest.find(')')?;
+        if close < open {
+            return None;
+        }
+        let name = rest[..open].trim_end();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let args = rest[open + 1..close]
+            .split(|c: char| c.is_ascii_whitespace() || c == ',')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        rest = &rest[close + 1..];
+
+        let mut compacted = Vec::with_capacity(args.len());
+        for (idx, arg) in args.iter().enumerate() {
+            if number_len(arg) != arg.len() {
+                return None;
+            }
+            let is_translation = match name {
+                "translate" => true,
+                "matrix" => idx >= 4,
+                _ => false,
+            };
+            let precision = if is_translation { precision } else { None };
+            compacted.push(compact_number(arg, precision));
+        }
+
+        let is_identity = match (name, compacted.as_slice()) {
+            ("translate", [x]) | ("rotate", [x]) | ("skewX", [x]) | ("skewY", [x]) => x == "0",
+            ("translate", [x, y]) => x == "0" && y == "0",
+            ("scale", [s]) => s == "1",
+            ("scale", [x, y]) => x == "1" && y == "1",
+            ("matrix", [a, b, c, d, e, f]) => {
+                a == "1" && b == "0" && c == "0" && d == "1" && e == "0" && f == "0"
+            }
+            _ => false,
+        };
+        if drop_identity && is_identity {
+            continue;
+        }
+
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(name);
+        out.push('(');
+        out.push_str(&compacted.join(","));
+        out.push(')');
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_number() {
+        assert_eq!(compact_number("0.500", None), ".5");
+        assert_eq!(compact_number("-0.5", None), "-.5");
+        assert_eq!(compact_number("10.0", None), "10");
+        assert_eq!(compact_number("100", None), "100");
+        assert_eq!(compact_number("1e2", None), "100");
+        assert_eq!(compact_number("1.23456", Some(2)), "1.23");
+        assert_eq!(compact_number("-0.001", Some(2)), "0");
+        assert_eq!(compact_number("2.999", Some(2)), "3");
+    }
+
+    #[test]
+    fn test_compact_path() {
+        let path = compact_path("M 0 0 L 10.50 0 L 10 10 Z", None);
+        assert_eq!(path.as_deref(), Some("M0 0 10.5 0 10 10Z"));
+        let path = compact_path("M 0.5 0.5 L -0.5 0.26 L 1.333 0", Some(1));
+        assert_eq!(path.as_deref(), Some("M.5.5-.5.3 1.3 0"));
+        let path = compact_path("M 0 0 A 5 5 0 0 1 10 0 M 1 1", None);
+        assert_eq!(path.as_deref(), Some("M0 0A5 5 0 0 1 10 0M1 1"));
+        let path = compact_path("M0 0a1 1 0 00 1 1", None);
+        assert_eq!(path.as_deref(), Some("M0 0a1 1 0 0 0 1 1"));
+        assert_eq!(compact_path("M 0 0 X 1", None), None);
+        assert_eq!(compact_path("0 0", None), None);
+    }
+
+    #[test]
+    fn test_compact_transform() {
+        let transform = compact_transform("translate(0.500, 0) scale(1.0,-1.0)", None, false);
+        assert_eq!(transform.as_deref(), Some("translate(.5,0) scale(1,-1)"));
+        let transform = compact_transform("translate(0.001, 0), scale(0.001)", Some(2), true);
+        assert_eq!(transform.as_deref(), Some("scale(.001)"));
+        let transform = compact_transform("matrix(1,0,0,1,0,0)", None, true);
+        assert_eq!(transform.as_deref(), Some(""));
+        assert_eq!(compact_transform("translate(1", None, true), None);
+    }
+}
@@ -1,8 +1,11 @@
-use std::sync::Arc;
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
 use reflexo::TakeAs;
 
+use super::compact::{compact_numbers, compact_path, compact_transform};
+use super::tags::{Tag, TagScanner, Token};
 use crate::backend::{SvgText, SvgTextNode};
+use crate::SvgMinifyOptions;
 
 pub fn minify_one(text: &mut SvgText) -> bool {
     let content = match text {
@@ -74,3 +77,524 @@ pub fn minify(mut svg: Vec<SvgText>) -> Vec<SvgText> {
     // eprintln!("minify_svg after: {:#?}", svg);
     svg
 }
+
+/// Do semantic-aware minification of SVG, followed by the passes selected by
+/// the options.
+pub fn minify_with_options(svg: Vec<SvgText>, options: &SvgMinifyOptions) -> Vec<SvgText> {
+    let mut svg = minify(svg);
+
+    if options.precision.is_some() || options.compact_path || options.drop_redundant_attributes {
+        visit(&mut svg, &mut CompactAttributes::new(options));
+    }
+    if options.collapse_groups {
+        collapse_groups(&mut svg);
+    }
+    if options.dedup_paths {
+        dedup_paths(&mut svg);
+    }
+    if options.shorten_ids {
+        shorten_ids(&mut svg);
+    }
+
+    svg
+}
+
+/// Visits the markup of a document in order.
+trait Visitor {
+    /// Visits a plain fragment.
+    fn plain(&mut self, text: &mut String);
+
+    /// Visits the attributes of a group, before its content.
+    fn enter(&mut self, _attributes: &mut Vec<(&'static str, String)>) {}
+
+    /// Leaves a group, after its content.
+    fn leave(&mut self) {}
+}
+
+fn visit(svg: &mut [SvgText], v: &mut impl Visitor) {
+    for text in svg {
+        match text {
+            SvgText::Plain(text) => v.plain(text),
+            SvgText::Content(node) => {
+                let node = Arc::make_mut(node);
+                v.enter(&mut node.attributes);
+                visit(&mut node.content, v);
+                v.leave();
+            }
+        }
+    }
+}
+
+/// Compacts the numbers in attributes and drops the redundant attributes.
+struct CompactAttributes<'a> {
+    options: &'a SvgMinifyOptions,
+    scanner: TagScanner,
+    /// The number of the enclosing `<pattern>` elements, whose content may be
+    /// in a unit box and is not rounded.
+    pattern_depth: usize,
+}
+
+impl<'a> CompactAttributes<'a> {
+    fn new(options: &'a SvgMinifyOptions) -> Self {
+        Self {
+            options,
+            scanner: TagScanner::default(),
+            pattern_depth: 0,
+        }
+    }
+}
+
+impl Visitor for CompactAttributes<'_> {
+    fn plain(&mut self, text: &mut String) {
+        if !text.contains('<') {
+            return;
+        }
+
+        let Self {
+            options,
+            scanner,
+            pattern_depth,
+        } = self;
+
+        let mut out = String::with_capacity(text.len());
+        for token in scanner.tokens(text) {
+            match token {
+                Token::Text(text) => out.push_str(text),
+                Token::EndTag(name, text) => {
+                    if name == "pattern" {
+                        *pattern_depth = pattern_depth.saturating_sub(1);
+                    }
+                    out.push_str(text);
+                }
+                Token::Tag(mut tag) => {
+                    let name = tag.name;
+                    for (key, value) in tag.attributes.iter_mut() {
+                        if let Some(v) = compact_value(options, *pattern_depth > 0, key, value) {
+                            *value = Cow::Owned(v);
+                        }
+                    }
+                    if options.drop_redundant_attributes {
+                        let href = tag.get("href").map(str::to_owned);
+                        let href = href.as_deref();
+                        tag.attributes
+                            .retain(|(key, value)| !is_redundant(name, key, value, href));
+                    }
+                    if name == "pattern" && !tag.self_closing {
+                        *pattern_depth += 1;
+                    }
+                    tag.write(&mut out);
+                }
+            }
+        }
+        *text = out;
+    }
+
+    fn enter(&mut self, attributes: &mut Vec<(&'static str, String)>) {
+        for (key, value) in attributes.iter_mut() {
+            if let Some(v) = compact_value(self.options, self.pattern_depth > 0, key, value) {
+                *value = v;
+            }
+        }
+        if self.options.drop_redundant_attributes {
+            attributes.retain(|(key, value)| !is_redundant("g", key, value, None));
+        }
+    }
+}
+
+/// Compacts the value of an attribute, returning `None` if it is kept as is.
+fn compact_value(
+    options: &SvgMinifyOptions,
+    in_pattern: bool,
+    key: &str,
+    value: &str,
+) -> Option<String> {
+    let compact = options.compact_path || options.precision.is_some();
+    let precision = if in_pattern { None } else { options.precision };
+
+    match key {
+        "d" if compact => compact_path(value, precision),
+        "x" | "y" | "width" | "height" | "viewBox" if compact => compact_numbers(value, precision),
+        "transform" | "patternTransform" | "gradientTransform" => {
+            compact_transform(value, precision, options.drop_redundant_attributes)
+        }
+        _ => None,
+    }
+}
+
+/// Whether an attribute has no effect on the rendering, given the `href` of
+/// the element.
+fn is_redundant(tag: &str, key: &str, value: &str, href: Option<&str>) -> bool {
+    match key {
+        "transform" | "patternTransform" | "gradientTransform" | "class" | "style" => {
+            value.trim().is_empty()
+        }
+        "opacity" => value == "1",
+        "x" | "y" => matches!(tag, "use" | "image" | "foreignObject" | "rect") && value == "0",
+        "xlink:href" => href == Some(value),
+        _ => false,
+    }
+}
+
+/// Whether an attribute carries semantics, e.g. is used by scripts or styles.
+fn is_semantic_attribute(key: &str) -> bool {
+    matches!(key, "class" | "id") || key.starts_with("data-")
+}
+
+/// Removes the empty groups and splices the content of the groups without
+/// attributes into their parents.
+fn collapse_groups(svg: &mut Vec<SvgText>) {
+    for mut text in std::mem::take(svg) {
+        if let SvgText::Content(node) = &mut text {
+            let node = Arc::make_mut(node);
+            collapse_groups(&mut node.content);
+
+            let is_semantic = node
+                .attributes
+                .iter()
+                .any(|(k, _)| is_semantic_attribute(k));
+            if node.content.is_empty() && !is_semantic {
+                continue;
+            }
+            if node.attributes.is_empty() {
+                svg.append(&mut node.content);
+                continue;
+            }
+        }
+        svg.push(text);
+    }
+}
+
+/// The minimal length of path data to deduplicate, since a short path is
+/// cheaper than a reference to it.
+const MIN_DEDUP_PATH_LEN: usize = 24;
+
+/// Whether the path element could be replaced by a `<use>` element.
+fn dedup_candidate<'a>(tag: &'a Tag<'_>) -> Option<&'a str> {
+    if tag.name != "path" || !tag.self_closing || tag.get("id").is_some() {
+        return None;
+    }
+    tag.get("d").filter(|d| d.len() >= MIN_DEDUP_PATH_LEN)
+}
+
+/// Collects or rewrites the path elements outside of text, since the glyphs
+/// of a text are inspected by the responsive script.
+struct DedupPaths {
+    scanner: TagScanner,
+    /// Whether each enclosing group is a text.
+    groups: Vec<bool>,
+    text_depth: usize,
+    /// The paths in the order of appearance, and the number of appearances.
+    paths: HashMap<String, (usize, usize)>,
+    /// The ids of the deduplicated paths, if rewriting.
+    ids: Option<HashMap<String, String>>,
+}
+
+impl Visitor for DedupPaths {
+    fn plain(&mut self, text: &mut String) {
+        if !text.contains('<') {
+            return;
+        }
+
+        let in_text = self.text_depth > 0;
+        if let Some(ids) = &self.ids {
+            *text = self.scanner.rewrite(text, |tag| {
+                let Some(id) = dedup_candidate(tag)
+                    .filter(|_| !in_text)
+                    .and_then(|d| ids.get(d))
+                else {
+                    return;
+                };
+                let href = format!("#{id}");
+                tag.name = "use";
+                tag.attributes.retain(|(key, _)| *key != "d");
+                tag.attributes.insert(0, ("href", Cow::Owned(href)));
+            });
+        } else {
+            for token in self.scanner.tokens(text) {
+                let Token::Tag(tag) = token else {
+                    continue;
+                };
+                if let Some(d) = dedup_candidate(&tag).filter(|_| !in_text) {
+                    let order = self.paths.len();
+                    self.paths.entry(d.to_owned()).or_insert((order, 0)).1 += 1;
+                }
+            }
+        }
+    }
+
+    fn enter(&mut self, attributes: &mut Vec<(&'static str, String)>) {
+        let is_text = attributes
+            .iter()
+            .any(|(k, v)| *k == "class" && v.split_whitespace().any(|c| c == "typst-text"));
+        self.groups.push(is_text);
+        self.text_depth += is_text as usize;
+    }
+
+    fn leave(&mut self) {
+        if self.groups.pop().unwrap_or_default() {
+            self.text_depth -= 1;
+        }
+    }
+}
+
+/// Moves the paths appearing more than once into `<defs>`, and references
+/// them by `<use>` elements.
+fn dedup_paths(svg: &mut Vec<SvgText>) {
+    // the paths are defined before the first defs, or at the start of the svg
+    let Some(defs_at) = svg
+        .iter()
+        .position(|text| matches!(text, SvgText::Plain(text) if text.starts_with("<defs")))
+        .or_else(|| {
+            let svg_at = svg
+                .iter()
+                .position(|text| matches!(text, SvgText::Plain(text) if text.starts_with("<svg")));
+            svg_at.map(|at| at + 1)
+        })
+    else {
+        return;
+    };
+
+    let mut v = DedupPaths {
+        scanner: TagScanner::default(),
+        groups: vec![],
+        text_depth: 0,
+        paths: HashMap::new(),
+        ids: None,
+    };
+    visit(svg, &mut v);
+
+    let mut paths = v
+        .paths
+        .into_iter()
+        .filter(|(_, (_, cnt))| *cnt > 1)
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        return;
+    }
+    paths.sort_by_key(|(_, (order, _))| *order);
+
+    let mut defs = String::from(r#"<defs class="path">"#);
+    let mut ids = HashMap::with_capacity(paths.len());
+    for (idx, (d, _)) in paths.into_iter().enumerate() {
+        // `_` is never used by the generated ids
+        let id = format!("_p{idx}");
+        defs.push_str(&format!(r#"<path id="{id}" d="{d}"/>"#));
+        ids.insert(d, id);
+    }
+    defs.push_str("</defs>");
+
+    let mut v = DedupPaths {
+        scanner: TagScanner::default(),
+        groups: vec![],
+        text_depth: 0,
+        paths: HashMap::new(),
+        ids: Some(ids),
+    };
+    visit(svg, &mut v);
+
+    svg.insert(defs_at, SvgText::Plain(defs));
+}
+
+/// Collects or renames the ids and the references to them.
+struct ShortenIds {
+    scanner: TagScanner,
+    ids: HashMap<String, String>,
+    rename: bool,
+}
+
+impl ShortenIds {
+    fn collect(&mut self, id: &str) {
+        if !self.ids.contains_key(id) {
+            let short = short_id(self.ids.len());
+            self.ids.insert(id.to_owned(), short);
+        }
+    }
+}
+
+impl Visitor for ShortenIds {
+    fn plain(&mut self, text: &mut String) {
+        if !text.contains('<') {
+            return;
+        }
+
+        if self.rename {
+            let ids = &self.ids;
+            *text = self.scanner.rewrite(text, |tag| {
+                for (key, value) in tag.attributes.iter_mut() {
+                    if let Some(v) = rename_refs(ids, key, value) {
+                        *value = Cow::Owned(v);
+                    }
+                }
+            });
+        } else {
+            let mut found = vec![];
+            for token in self.scanner.tokens(text) {
+                if let Token::Tag(tag) = token {
+                    found.extend(tag.get("id").map(str::to_owned));
+                }
+            }
+            for id in found {
+                self.collect(&id);
+            }
+        }
+    }
+
+    fn enter(&mut self, attributes: &mut Vec<(&'static str, String)>) {
+        if self.rename {
+            for (key, value) in attributes.iter_mut() {
+                if let Some(v) = rename_refs(&self.ids, key, value) {
+                    *value = v;
+                }
+            }
+        } else if let Some((_, id)) = attributes.iter().find(|(k, _)| *k == "id") {
+            self.collect(id);
+        }
+    }
+}
+
+/// Renames the ids defined in the document to short ones.
+fn shorten_ids(svg: &mut [SvgText]) {
+    let mut v = ShortenIds {
+        scanner: TagScanner::default(),
+        ids: HashMap::new(),
+        rename: false,
+    };
+    visit(svg, &mut v);
+    if v.ids.is_empty() {
+        return;
+    }
+
+    v.scanner = TagScanner::default();
+    v.rename = true;
+    visit(svg, &mut v);
+}
+
+/// Gets the `n`-th short id, i.e. `a`, .., `Z`, `aa`, `ba`, ...
+fn short_id(n: usize) -> String {
+    const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+    let mut id = String::with_capacity(4);
+    id.push(FIRST[n % FIRST.len()] as char);
+    let mut n = n / FIRST.len();
+    while n > 0 {
+        n -= 1;
+        id.push(REST[n % REST.len()] as char);
+        n /= REST.len();
+    }
+    id
+}
+
+/// Renames the id or the references to ids in an attribute, returning `None`
+/// if it is kept as is. References to other documents, e.g. an external glyph
+/// sprite, are kept.
+fn rename_refs(ids: &HashMap<String, String>, key: &str, value: &str) -> Option<String> {
+    if key.starts_with("data-") {
+        return None;
+    }
+    if key == "id" {
+        return ids.get(value).cloned();
+    }
+    if matches!(key, "href" | "xlink:href") {
+        let id = value.strip_prefix('#')?;
+        return ids.get(id).map(|short| format!("#{short}"));
+    }
+    if !value.contains("url(#") {
+        return None;
+    }
+
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(at) = rest.find("url(#") {
+        out.push_str(&rest[..at + 5]);
+        rest = &rest[at + 5..];
+        let end = rest.find(')').unwrap_or(rest.len());
+        let id = &rest[..end];
+        out.push_str(ids.get(id).map_or(id, String::as_str));
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(attributes: Vec<(&'static str, &str)>, content: Vec<SvgText>) -> SvgText {
+        SvgText::Content(Arc::new(SvgTextNode {
+            attributes: attributes
+                .into_iter()
+                .map(|(k, v)| (k, v.to_owned()))
+                .collect(),
+            content,
+        }))
+    }
+
+    fn minify_text(svg: Vec<SvgText>, options: &SvgMinifyOptions) -> String {
+        crate::backend::generate_text(minify_with_options(svg, options))
+    }
+
+    #[test]
+    fn test_short_id() {
+        assert_eq!(short_id(0), "a");
+        assert_eq!(short_id(51), "Z");
+        assert_eq!(short_id(52), "aa");
+        assert_eq!(short_id(53), "ba");
+        assert_eq!(short_id(52 * 2), "ab");
+        assert_eq!(short_id(52 * 63), "aaa");
+    }
+
+    #[test]
+    fn test_compact_and_collapse() {
+        let svg = vec![
+            SvgText::Plain(r#"<svg viewBox="0 0 10.000 10.000">"#.into()),
+            group(
+                vec![("transform", "translate(0.000, 0.000)")],
+                vec![
+                    SvgText::Plain(r#"<path class="typst-shape" d="M 0.123 0 L 1 1 " />"#.into()),
+                    group(vec![("transform", "scale(1, 1)")], vec![]),
+                ],
+            ),
+            SvgText::Plain("</svg>".into()),
+        ];
+        let options = SvgMinifyOptions {
+            precision: Some(1),
+            ..SvgMinifyOptions::full()
+        };
+        assert_eq!(
+            minify_text(svg, &options),
+            r#"<svg viewBox="0 0 10 10"><path class="typst-shape" d="M.1 0 1 1"/></svg>"#
+        );
+    }
+
+    #[test]
+    fn test_dedup_and_shorten_ids() {
+        let path = r#"<path d="M 0 0 L 100 0 L 100 100 L 0 100 Z" fill="red"/>"#;
+        let svg = vec![
+            SvgText::Plain("<svg>".into()),
+            SvgText::Plain(r#"<defs class="clip-path"><clipPath id="cABCD">"#.into()),
+            SvgText::Plain(path.into()),
+            SvgText::Plain("</clipPath></defs>".into()),
+            group(
+                vec![("clip-path", "url(#cABCD)")],
+                vec![SvgText::Plain(path.into())],
+            ),
+            SvgText::Plain(r##"<use href="glyphs.svg#gABCD"/></svg>"##.into()),
+        ];
+        let options = SvgMinifyOptions {
+            dedup_paths: true,
+            shorten_ids: true,
+            ..SvgMinifyOptions::none()
+        };
+        assert_eq!(
+            minify_text(svg, &options),
+            concat!(
+                r##"<svg><defs class="path"><path id="a" d="M 0 0 L 100 0 L 100 100 L 0 100 Z"/></defs>"##,
+                r##"<defs class="clip-path"><clipPath id="b"><use href="#a" fill="red"/></clipPath></defs>"##,
+                r##"<g clip-path="url(#b)"><use href="#a" fill="red"/></g>"##,
+                r##"<use href="glyphs.svg#gABCD"/></svg>"##,
+            )
+        );
+    }
+}
@@ -1,2 +1,4 @@
+mod compact;
 mod minify;
-pub use minify::minify;
+mod tags;
+pub use minify::minify_with_options;
@@ -0,0 +1,257 @@
+//! A lenient tokenizer over the markup of [`crate::SvgText::Plain`] fragments.
+//!
+//! Only start tags are parsed, everything else, e.g. text, end tags and
+//! comments, is passed through as is. The content of `<style>` and `<script>`
+//! elements is never interpreted as markup.
+
+use std::borrow::Cow;
+
+/// A start tag, e.g. `<path d="M0 0"/>`.
+#[derive(Debug, Clone)]
+pub struct Tag<'a> {
+    /// The tag name, e.g. `path`.
+    pub name: &'a str,
+    /// The attributes, whose values are kept escaped.
+    pub attributes: Vec<(&'a str, Cow<'a, str>)>,
+    /// Whether the tag is self-closing, e.g. `<path/>`.
+    pub self_closing: bool,
+}
+
+impl Tag<'_> {
+    /// Gets the value of an attribute.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_ref())
+    }
+
+    /// Writes the tag back to markup.
+    pub fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(self.name);
+        for (key, value) in &self.attributes {
+            let quote = if value.contains('"') { '\'' } else { '"' };
+            out.push(' ');
+            out.push_str(key);
+            out.push('=');
+            out.push(quote);
+            out.push_str(value);
+            out.push(quote);
+        }
+        out.push_str(if self.self_closing { "/>" } else { ">" });
+    }
+}
+
+/// A token of markup.
+#[derive(Debug)]
+pub enum Token<'a> {
+    /// Text that is passed through as is.
+    Text(&'a str),
+    /// An end tag, e.g. `</g>`, with the tag name.
+    EndTag(&'a str, &'a str),
+    /// A start tag.
+    Tag(Tag<'a>),
+}
+
+/// The state of tokenization, which is carried across the fragments of a
+/// document.
+#[derive(Debug, Default)]
+pub struct TagScanner {
+    /// The end tag of the raw text element we are in, if any.
+    raw_end: Option<&'static str>,
+}
+
+impl TagScanner {
+    /// Tokenizes a fragment of markup.
+    pub fn tokens<'a, 's>(&'s mut self, text: &'a str) -> Tokens<'a, 's> {
+        Tokens {
+            rest: text,
+            scanner: self,
+        }
+    }
+
+    /// Rewrites the start tags of a fragment by `f`.
+    pub fn rewrite(&mut self, text: &str, mut f: impl FnMut(&mut Tag)) -> String {
+        let mut out = String::with_capacity(text.len());
+        for token in self.tokens(text) {
+            match token {
+                Token::Text(text) | Token::EndTag(_, text) => out.push_str(text),
+                Token::Tag(mut tag) => {
+                    f(&mut tag);
+                    tag.write(&mut out);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// The iterator of tokens, see [`TagScanner::tokens`].
+pub struct Tokens<'a, 's> {
+    rest: &'a str,
+    scanner: &'s mut TagScanner,
+}
+
+impl<'a> Tokens<'a, '_> {
+    /// Splits the text at `at`, returning the first part.
+    fn take(&mut self, at: usize) -> &'a str {
+        let (head, rest) = self.rest.split_at(at);
+        self.rest = rest;
+        head
+    }
+
+    /// Takes the text until (inclusive) the `pat`, or the entire rest.
+    fn take_through(&mut self, pat: &str) -> &'a str {
+        let at = self
+            .rest
+            .find(pat)
+            .map_or(self.rest.len(), |i| i + pat.len());
+        self.take(at)
+    }
+}
+
+impl<'a> Iterator for Tokens<'a, '_> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        if let Some(raw_end) = self.scanner.raw_end {
+            match self.rest.find(raw_end) {
+                Some(0) => self.scanner.raw_end = None,
+                Some(at) => return Some(Token::Text(self.take(at))),
+                None => return Some(Token::Text(self.take(self.rest.len()))),
+            }
+        }
+
+        if !self.rest.starts_with('<') {
+            let at = self.rest.find('<').unwrap_or(self.rest.len());
+            return Some(Token::Text(self.take(at)));
+        }
+
+        if self.rest.starts_with("<!--") {
+            return Some(Token::Text(self.take_through("-->")));
+        }
+
+        if let Some(name) = self.rest.strip_prefix("</") {
+            let len = name.find(['>', ' ']).unwrap_or(name.len());
+            let name = &name[..len];
+            return Some(Token::EndTag(name, self.take_through(">")));
+        }
+
+        match parse_tag(self.rest) {
+            Some((tag, len)) => {
+                self.take(len);
+                if !tag.self_closing {
+                    self.scanner.raw_end = match tag.name {
+                        "style" => Some("</style"),
+                        "script" => Some("</script"),
+                        _ => None,
+                    };
+                }
+                Some(Token::Tag(tag))
+            }
+            // e.g. `<!DOCTYPE html>`
+            None => Some(Token::Text(self.take_through(">"))),
+        }
+    }
+}
+
+/// Parses a start tag at the beginning of `text`, returning the tag and its
+/// length in bytes.
+fn parse_tag(text: &str) -> Option<(Tag<'_>, usize)> {
+    let is_name_end = |c: char| c.is_ascii_whitespace() || matches!(c, '/' | '>' | '=');
+
+    let mut pos = 1;
+    let name_len = text[pos..].find(is_name_end)?;
+    let name = &text[pos..pos + name_len];
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    pos += name_len;
+
+    let mut attributes = vec![];
+    loop {
+        pos += text[pos..].len() - text[pos..].trim_start().len();
+        let rest = &text[pos..];
+        if rest.starts_with("/>") {
+            let tag = Tag {
+                name,
+                attributes,
+                self_closing: true,
+            };
+            return Some((tag, pos + 2));
+        }
+        if rest.starts_with('>') {
+            let tag = Tag {
+                name,
+                attributes,
+                self_closing: false,
+            };
+            return Some((tag, pos + 1));
+        }
+
+        let key_len = rest.find(is_name_end)?;
+        if key_len == 0 {
+            return None;
+        }
+        let key = &rest[..key_len];
+        pos += key_len;
+
+        // a boolean attribute, e.g. `<input disabled>`
+        let rest = text[pos..].trim_start();
+        let Some(rest) = rest.strip_prefix('=') else {
+            attributes.push((key, Cow::Borrowed("")));
+            continue;
+        };
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let value_len = rest[1..].find(quote)?;
+        let value = &rest[1..1 + value_len];
+        attributes.push((key, Cow::Borrowed(value)));
+
+        pos = text.len() - rest.len() + value_len + 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_tags() {
+        let mut scanner = TagScanner::default();
+        let text = r#"<g class="a" ><path  d='M 0 0' /></g>text"#;
+        let out = scanner.rewrite(text, |tag| {
+            if tag.name == "path" {
+                tag.attributes.push(("fill", "none".into()));
+            }
+        });
+        assert_eq!(out, r#"<g class="a"><path d="M 0 0" fill="none"/></g>text"#);
+    }
+
+    #[test]
+    fn test_raw_text_across_fragments() {
+        let mut scanner = TagScanner::default();
+        let mut tags = vec![];
+        for text in [r#"<style type="text/css">"#, "a<b>c", "</style><use/>"] {
+            for token in scanner.tokens(text) {
+                if let Token::Tag(tag) = token {
+                    tags.push(tag.name.to_owned());
+                }
+            }
+        }
+        assert_eq!(tags, ["style", "use"]);
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let mut scanner = TagScanner::default();
+        let text = "<!DOCTYPE html><!-- <g> --><p>&lt;</p>";
+        let out = scanner.rewrite(text, |_| {});
+        assert_eq!(out, text);
+    }
+}
@@ -4,4 +4,4 @@ pub mod workspace;
 
 pub use compiler::CompileFontOpts;
 pub use compiler::CompileOpts;
-pub use workspace::WorkspaceConfig;
+pub use workspace::{EntryConfig, WorkspaceConfig};
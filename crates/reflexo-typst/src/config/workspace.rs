@@ -1,15 +1,76 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
+/// The project manifest, which is usually stored in a `typst-ts.toml` or
+/// `typst-ts.json` file. Relative paths are resolved against the directory
+/// containing the manifest.
 #[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct WorkspaceConfig {
+    #[serde(default)]
     pub version: String,
 
     /// Path to typst workspace.
+    #[serde(default)]
     pub workspace: String,
 
-    /// Path to entries
+    /// Path to entries, which are exported with the default [`Self::formats`].
+    #[serde(default)]
     pub files: Vec<String>,
 
-    #[serde(rename = "fontPaths")]
+    #[serde(rename = "fontPaths", default)]
     pub font_paths: Vec<String>,
+
+    /// Default output format(s) of entries.
+    #[serde(default)]
+    pub formats: Vec<String>,
+
+    /// Entries with their own export options.
+    #[serde(default)]
+    pub entries: Vec<EntryConfig>,
+}
+
+impl WorkspaceConfig {
+    /// Gets all entries, including the ones listed in [`Self::files`].
+    pub fn all_entries(&self) -> impl Iterator<Item = EntryConfig> + '_ {
+        let files = self.files.iter().map(|file| EntryConfig {
+            entry: file.clone(),
+            ..EntryConfig::default()
+        });
+
+        files.chain(self.entries.iter().cloned()).map(|mut entry| {
+            if entry.formats.is_empty() {
+                entry.formats = self.formats.clone();
+            }
+            entry
+        })
+    }
+}
+
+/// An entry of the project manifest.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct EntryConfig {
+    /// Path to the entry file.
+    pub entry: String,
+
+    /// Output format(s), defaults to [`WorkspaceConfig::formats`].
+    #[serde(default)]
+    pub formats: Vec<String>,
+
+    /// String key-value pairs visible through `sys.inputs`.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, String>,
+
+    /// Output directory, defaults to the directory containing the entry file.
+    #[serde(default)]
+    pub output: Option<String>,
+
+    /// Pages to export, e.g. `1,3-5`. When unspecified, all pages are
+    /// exported.
+    #[serde(default)]
+    pub pages: Option<String>,
+
+    /// The resolution of `png` output, in pixels per inch.
+    #[serde(default)]
+    pub ppi: Option<f32>,
 }
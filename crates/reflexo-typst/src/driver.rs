@@ -1,15 +1,40 @@
-use typst::diag::{SourceDiagnostic, SourceResult, Warned};
+use std::io::Write;
+
+use serde::Serialize;
+use typst::diag::{Severity, SourceDiagnostic, SourceResult, Warned};
 
 use crate::{
     diag::print_diagnostics,
+    error::{resolve_source_span, JsonRange, PosFmt},
     world::{CompilerFeat, CompilerWorld},
     CompileReport, DiagnosticFormat,
 };
 
+/// The format to emit diagnostics in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticOutputFormat {
+    /// Human readable diagnostics with source snippets.
+    #[default]
+    Human,
+    /// One line per diagnostic.
+    Short,
+    /// One JSON object per line, see [`JsonDiagnostic`].
+    Json,
+}
+
+impl From<DiagnosticFormat> for DiagnosticOutputFormat {
+    fn from(fmt: DiagnosticFormat) -> Self {
+        match fmt {
+            DiagnosticFormat::Human => Self::Human,
+            DiagnosticFormat::Short => Self::Short,
+        }
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct DiagnosticHandler {
     /// The diagnostic format to use.
-    pub diagnostic_format: DiagnosticFormat,
+    pub diagnostic_format: DiagnosticOutputFormat,
     /// Whether to print the compile status.
     pub print_compile_status: bool,
 }
@@ -42,7 +67,18 @@ impl DiagnosticHandler {
         world: &CompilerWorld<F>,
         diagnostics: impl Iterator<Item = &'d SourceDiagnostic>,
     ) {
-        let _err = print_diagnostics(world, diagnostics, self.diagnostic_format);
+        let format = match self.diagnostic_format {
+            DiagnosticOutputFormat::Human => DiagnosticFormat::Human,
+            DiagnosticOutputFormat::Short => DiagnosticFormat::Short,
+            DiagnosticOutputFormat::Json => {
+                if let Err(err) = print_json_diagnostics(world, diagnostics) {
+                    log::error!("failed to print diagnostics: {err:?}");
+                }
+                return;
+            }
+        };
+
+        let _err = print_diagnostics(world, diagnostics, format);
         // todo: log in browser compiler
         #[cfg(feature = "system-compile")]
         if _err.is_err() {
@@ -51,6 +87,104 @@ impl DiagnosticHandler {
     }
 }
 
+/// A diagnostic emitted by [`DiagnosticOutputFormat::Json`].
+///
+/// The lines and columns are zero-based, and the columns are counted in
+/// characters.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonDiagnostic {
+    /// Either `error` or `warning`.
+    pub severity: &'static str,
+    /// The diagnostic message.
+    pub message: String,
+    /// The package containing the file, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// The path to the file, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The range in the file, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<JsonRange>,
+    /// The hints to fix the diagnostic.
+    pub hints: Vec<String>,
+    /// The trace of the diagnostic, from the innermost to the outermost.
+    pub trace: Vec<JsonTracepoint>,
+}
+
+/// A tracepoint of a [`JsonDiagnostic`].
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonTracepoint {
+    /// The message of the tracepoint, e.g. `while calling foo`.
+    pub message: String,
+    /// The package containing the file, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// The path to the file, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The range in the file, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<JsonRange>,
+}
+
+impl JsonDiagnostic {
+    /// Converts a diagnostic, resolving the spans through the world.
+    pub fn from_std(diag: &SourceDiagnostic, world: &dyn typst::World) -> Self {
+        let (package, path, range) = resolve_source_span(diag.span, Some(world));
+
+        Self {
+            severity: match diag.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            },
+            message: diag.message.to_string(),
+            package: non_empty(package),
+            path: non_empty(path),
+            range: range.map(From::from),
+            hints: diag.hints.iter().map(|hint| hint.v.to_string()).collect(),
+            trace: diag
+                .trace
+                .iter()
+                .map(|trace| {
+                    let (package, path, range) = resolve_source_span(trace.span, Some(world));
+                    JsonTracepoint {
+                        message: PosFmt(&trace.v).to_string(),
+                        package: non_empty(package),
+                        path: non_empty(path),
+                        range: range.map(From::from),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+fn non_empty(s: String) -> Option<String> {
+    (!s.is_empty()).then_some(s)
+}
+
+/// Prints diagnostics to the stderr, one JSON object per line.
+fn print_json_diagnostics<'d>(
+    world: &dyn typst::World,
+    diagnostics: impl Iterator<Item = &'d SourceDiagnostic>,
+) -> std::io::Result<()> {
+    write_json_diagnostics(&mut std::io::stderr().lock(), world, diagnostics)
+}
+
+fn write_json_diagnostics<'d>(
+    w: &mut impl Write,
+    world: &dyn typst::World,
+    diagnostics: impl Iterator<Item = &'d SourceDiagnostic>,
+) -> std::io::Result<()> {
+    for diag in diagnostics {
+        serde_json::to_writer(&mut *w, &JsonDiagnostic::from_std(diag, world))?;
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
 // todo: Print that a package downloading is happening.
 // fn print_downloading(_spec: &PackageSpec) -> std::io::Result<()> {
 // let mut w = color_stream();
@@ -63,3 +197,50 @@ impl DiagnosticHandler {
 // writeln!(w, " {spec}")
 // Ok(())
 // }
+
+#[cfg(all(test, feature = "system-compile"))]
+mod tests {
+    use reflexo::typst::TypstPagedDocument;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::exporter::tests::compile_graph;
+
+    #[test]
+    fn test_json_diagnostics() {
+        let graph = compile_graph("#let f() = a-b\n#f()\n");
+        let world = &graph.snap.world;
+        let diag = typst::compile::<TypstPagedDocument>(world)
+            .output
+            .expect_err("the source should fail to compile");
+
+        let mut out = vec![];
+        write_json_diagnostics(&mut out, world, diag.iter()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), diag.len(), "one object per line: {out}");
+
+        let diag: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(diag["severity"], "error");
+        assert_eq!(diag["message"], "unknown variable: a-b");
+        assert!(diag["path"].is_string(), "{diag}");
+        assert_eq!(diag["range"]["start"]["line"], 0);
+        assert_eq!(diag["range"]["end"]["line"], 0);
+        assert_eq!(diag["range"]["start"]["column"], 11);
+        assert_eq!(diag["range"]["end"]["column"], 14);
+
+        let hints = diag["hints"].as_array().unwrap();
+        assert!(
+            hints.iter().any(|hint| hint
+                .as_str()
+                .is_some_and(|hint| hint.contains("subtraction"))),
+            "{diag}"
+        );
+
+        let trace = diag["trace"].as_array().unwrap();
+        assert_eq!(trace.len(), 1, "{diag}");
+        assert_eq!(trace[0]["message"], "while calling f");
+        assert_eq!(trace[0]["range"]["start"]["line"], 1);
+    }
+}
@@ -7,6 +7,7 @@ use core::fmt;
 use ecow::eco_format;
 use reflexo::debug_loc::{LspPosition, LspRange};
 use reflexo::path::unix_slash;
+use serde::Serialize;
 use typst::syntax::{DiagSpan, FileId, Source, VirtualRoot};
 use typst::WorldExt;
 
@@ -104,7 +105,36 @@ impl fmt::Display for DiagMsgFmt<'_> {
     }
 }
 
-struct PosFmt<'a>(&'a typst::diag::Tracepoint);
+/// A zero-based range in a source file, which is serialized in JSON outputs.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JsonRange {
+    pub start: JsonPosition,
+    pub end: JsonPosition,
+}
+
+/// A zero-based position in a source file, see [`JsonRange`].
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JsonPosition {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl From<LspRange> for JsonRange {
+    fn from(range: LspRange) -> Self {
+        Self {
+            start: JsonPosition {
+                line: range.start.line,
+                column: range.start.character,
+            },
+            end: JsonPosition {
+                line: range.end.line,
+                column: range.end.character,
+            },
+        }
+    }
+}
+
+pub(crate) struct PosFmt<'a>(pub &'a typst::diag::Tracepoint);
 
 impl fmt::Display for PosFmt<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -118,7 +148,7 @@ impl fmt::Display for PosFmt<'_> {
     }
 }
 
-fn resolve_source_span(
+pub(crate) fn resolve_source_span(
     s: impl Into<DiagSpan>,
     world: Option<&dyn typst::World>,
 ) -> (String, String, Option<LspRange>) {
@@ -11,9 +11,17 @@ pub mod ast;
 pub mod dyn_svg;
 #[cfg(feature = "html")]
 pub mod html;
+#[cfg(feature = "html")]
+pub mod html_site;
+pub mod pages;
+#[cfg(feature = "png")]
+pub mod png;
 #[cfg(feature = "svg")]
 pub mod svg;
 
 pub mod text;
 
+#[cfg(all(test, feature = "system-compile"))]
+pub(crate) mod tests;
+
 pub type DynComputation<F> = Arc<dyn Fn(&Arc<WorldComputeGraph<F>>) -> Result<()> + Send + Sync>;
@@ -14,7 +14,64 @@ use typst_html::{attr, charsets, property, tag, HtmlElement, HtmlFrame, HtmlNode
 
 pub type ExportStaticHtmlTask = tinymist_task::ExportHtmlTask;
 pub type StaticHtmlExport = tinymist_task::HtmlExport;
-pub type ExportHtmlTask = tinymist_task::ExportHtmlTask;
+
+/// The task to export a document to [`HtmlOutput`].
+#[derive(Debug, Clone, Default)]
+pub struct ExportHtmlTask {
+    /// The common options of the html export.
+    pub export: tinymist_task::ExportHtmlTask,
+    /// The options of the html writer.
+    pub writer: HtmlWriterOptions,
+}
+
+/// The options of the html writer.
+#[derive(Debug, Clone, Default)]
+pub struct HtmlWriterOptions {
+    /// Whether to write the html without indents and newlines.
+    pub minify: bool,
+    /// The raw html fragments appended to the `<head>` element, e.g. stylesheet
+    /// links, scripts and meta tags.
+    pub head: Vec<EcoString>,
+    /// The base url to resolve relative links in the document, which is
+    /// written as a `<base>` element.
+    pub base_url: Option<EcoString>,
+    /// The hook to rewrite the embedded assets.
+    pub assets: Option<HtmlAssetHook>,
+}
+
+/// An asset embedded in the html.
+#[derive(Debug, Clone, Copy)]
+pub enum HtmlAsset<'a> {
+    /// An image whose source is a data url.
+    Image {
+        /// The data url of the image, e.g. `data:image/png;base64,...`.
+        data_url: &'a str,
+    },
+    /// A laid out frame, rendered as an inline svg.
+    Frame {
+        /// The svg of the frame.
+        svg: &'a str,
+    },
+}
+
+/// A hook to rewrite the embedded assets, e.g. to extract them into separate
+/// files. It returns the url of the asset to refer to, or `None` to keep the
+/// asset embedded.
+#[derive(Clone)]
+pub struct HtmlAssetHook(pub Arc<dyn Fn(HtmlAsset<'_>) -> Option<EcoString> + Send + Sync>);
+
+impl HtmlAssetHook {
+    /// Creates a hook from a closure.
+    pub fn new(f: impl Fn(HtmlAsset<'_>) -> Option<EcoString> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+impl std::fmt::Debug for HtmlAssetHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HtmlAssetHook(..)")
+    }
+}
 
 pub struct HtmlOutputExport;
 
@@ -23,19 +80,27 @@ impl<F: CompilerFeat> ExportComputation<F, TypstHtmlDocument> for HtmlOutputExpo
     type Config = ExportHtmlTask;
 
     fn run(
-        _graph: &Arc<WorldComputeGraph<F>>,
+        graph: &Arc<WorldComputeGraph<F>>,
         doc: &Arc<TypstHtmlDocument>,
-        _config: &ExportHtmlTask,
+        config: &ExportHtmlTask,
     ) -> Result<HtmlOutput> {
-        Ok(static_html(doc)?)
+        let output = static_html(doc)?.with_options(config.writer.clone());
+        #[cfg(feature = "hast")]
+        let output = output.with_world(Arc::new(graph.snap.world.clone()));
+        #[cfg(not(feature = "hast"))]
+        let _ = graph;
+        Ok(output)
     }
 }
 
 pub struct HtmlOutput {
-    pretty: bool,
+    options: HtmlWriterOptions,
     document: Arc<TypstHtmlDocument>,
     head_idx: Option<usize>,
     body_idx: Option<usize>,
+    /// The world to resolve source positions in the Hast.
+    #[cfg(feature = "hast")]
+    world: Option<Arc<dyn typst::World>>,
 
     body: OnceLock<SourceResult<String>>,
     html: OnceLock<SourceResult<String>>,
@@ -101,7 +166,7 @@ impl HtmlOutput {
             .get_or_init(|| {
                 let link_resolver =
                     LateLinkResolver::new(None, self.document.introspector().as_ref());
-                let mut w = Writer::new(link_resolver.track(), self.pretty);
+                let mut w = Writer::new(link_resolver.track(), &self.options);
                 write_indent(&mut w);
                 if let Some(body) = self.root_child(self.body_idx) {
                     write_element_with_tag(&mut w, body, "div")?;
@@ -123,27 +188,40 @@ impl HtmlOutput {
             .get_or_init(|| {
                 let link_resolver =
                     LateLinkResolver::new(None, self.document.introspector().as_ref());
-                let mut w = Writer::new(link_resolver.track(), self.pretty);
-                w.buf.push_str("<!DOCTYPE html>");
-                write_indent(&mut w);
-                write_element(&mut w, self.document.root())?;
-                if w.pretty {
-                    w.buf.push('\n');
-                }
-                Ok(w.buf)
+                write_html(
+                    self.document.root(),
+                    link_resolver.track(),
+                    None,
+                    &self.options,
+                )
             })
             .as_ref()
             .map(|s| s.as_str())
             .map_err(|e| e.clone())
     }
 
+    /// Sets the options of the html writer.
+    ///
+    /// The options must be set before the html is written.
+    pub fn with_options(mut self, options: HtmlWriterOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the world to resolve source positions in the Hast.
+    #[cfg(feature = "hast")]
+    pub fn with_world(mut self, world: Arc<dyn typst::World>) -> Self {
+        self.world = Some(world);
+        self
+    }
+
     #[cfg(feature = "hast")]
     pub fn hast(&self) -> SourceResult<reflexo_typst2hast::hast::HastElementContent> {
-        reflexo_typst2hast::hast(&self.document)
+        reflexo_typst2hast::hast(&self.document, self.world.as_deref())
     }
 }
 
-fn find_tag_child(element: &HtmlElement, tag: HtmlTag) -> Option<usize> {
+pub(crate) fn find_tag_child(element: &HtmlElement, tag: HtmlTag) -> Option<usize> {
     element.children.iter().position(|node| match node {
         HtmlNode::Element(e) => e.tag == tag,
         _ => false,
@@ -156,15 +234,42 @@ pub fn static_html(document: &Arc<TypstHtmlDocument>) -> SourceResult<HtmlOutput
     let body_idx = find_tag_child(document.root(), tag::body);
 
     Ok(HtmlOutput {
-        pretty: true,
+        options: HtmlWriterOptions::default(),
         document: document.clone(),
         head_idx,
         body_idx,
+        #[cfg(feature = "hast")]
+        world: None,
         body: OnceLock::new(),
         html: OnceLock::new(),
     })
 }
 
+/// Rewrites a link to an anchor, e.g. `#intro`, into the url to refer to, or
+/// returns `None` to keep the link.
+pub(crate) type LinkRewriter<'a> = &'a dyn Fn(&str) -> Option<EcoString>;
+
+/// Encodes a root element into a complete html document.
+///
+/// The `frame_links` rewrites the links inside frames, which are resolved
+/// against the whole document by the `link_resolver`.
+pub(crate) fn write_html<'a>(
+    root: &HtmlElement,
+    link_resolver: Tracked<'a, LateLinkResolver<'a>>,
+    frame_links: Option<LinkRewriter<'a>>,
+    options: &'a HtmlWriterOptions,
+) -> SourceResult<String> {
+    let mut w = Writer::new(link_resolver, options);
+    w.frame_links = frame_links;
+    w.buf.push_str("<!DOCTYPE html>");
+    write_indent(&mut w);
+    write_element(&mut w, root)?;
+    if w.pretty {
+        w.buf.push('\n');
+    }
+    Ok(w.buf)
+}
+
 struct Writer<'a> {
     /// The output buffer.
     buf: String,
@@ -172,20 +277,43 @@ struct Writer<'a> {
     level: usize,
     /// Used to resolve links between the document and contained frames.
     link_resolver: Tracked<'a, LateLinkResolver<'a>>,
+    /// Rewrites the links inside frames, if any.
+    frame_links: Option<LinkRewriter<'a>>,
     /// Whether pretty printing is enabled.
     pretty: bool,
+    /// The options of the writer.
+    options: &'a HtmlWriterOptions,
 }
 
 impl<'a> Writer<'a> {
     /// Creates a new writer.
-    fn new(link_resolver: Tracked<'a, LateLinkResolver<'a>>, pretty: bool) -> Self {
+    fn new(
+        link_resolver: Tracked<'a, LateLinkResolver<'a>>,
+        options: &'a HtmlWriterOptions,
+    ) -> Self {
         Self {
             buf: String::new(),
             level: 0,
             link_resolver,
-            pretty,
+            frame_links: None,
+            pretty: !options.minify,
+            options,
         }
     }
+
+    /// Whether the writer injects extra children into the element.
+    fn injects_into(&self, element: &HtmlElement) -> bool {
+        element.tag == tag::head
+            && (self.options.base_url.is_some() || !self.options.head.is_empty())
+    }
+
+    /// Rewrites an embedded asset by the hook, if any.
+    fn rewrite_asset(&self, asset: HtmlAsset) -> Option<EcoString> {
+        self.options
+            .assets
+            .as_ref()
+            .and_then(|hook| (hook.0)(asset))
+    }
 }
 
 /// Writes a newline and indent, if pretty printing is enabled.
@@ -204,7 +332,7 @@ fn write_node(w: &mut Writer, node: &HtmlNode, escape_text: bool) -> SourceResul
         HtmlNode::Tag(_) => {}
         HtmlNode::Text(text, span) => write_text(w, text, *span, escape_text)?,
         HtmlNode::Element(element) => write_element(w, element)?,
-        HtmlNode::Frame(frame) => write_frame(w, frame),
+        HtmlNode::Frame(frame) => write_frame(w, frame)?,
     }
     Ok(())
 }
@@ -235,19 +363,18 @@ fn write_element_with_tag(w: &mut Writer, element: &HtmlElement, tag: &str) -> S
         w.buf.push(' ');
         w.buf.push_str(&attr.resolve());
 
+        let rewritten = match value.as_str() {
+            data_url if *attr == attr::src && data_url.starts_with("data:") => {
+                w.rewrite_asset(HtmlAsset::Image { data_url })
+            }
+            _ => None,
+        };
+        let value = rewritten.as_ref().unwrap_or(value);
+
         // If the string is empty, we can use shorthand syntax.
         // `<elem attr="">..</div` is equivalent to `<elem attr>..</div>`
         if !value.is_empty() {
-            w.buf.push('=');
-            w.buf.push('"');
-            for c in value.chars() {
-                if charsets::is_valid_in_attribute_value(c) {
-                    w.buf.push(c);
-                } else {
-                    write_escape(w, c).at(element.span)?;
-                }
-            }
-            w.buf.push('"');
+            write_attr_value(w, value).at(element.span)?;
         }
     }
 
@@ -273,7 +400,7 @@ fn write_element_with_tag(w: &mut Writer, element: &HtmlElement, tag: &str) -> S
         write_raw(w, element)?;
     } else if tag::is_escapable_raw(element.tag) {
         write_escapable_raw(w, element)?;
-    } else if !element.children.is_empty() {
+    } else if !element.children.is_empty() || w.injects_into(element) {
         write_children(w, element)?;
     }
 
@@ -287,17 +414,28 @@ fn write_element_with_tag(w: &mut Writer, element: &HtmlElement, tag: &str) -> S
 /// Encodes the children of an element.
 fn write_children(w: &mut Writer, element: &HtmlElement) -> SourceResult<()> {
     let pretty = w.pretty;
+    let options = w.options;
+    let injects = w.injects_into(element);
     let pretty_inside = allows_pretty_inside(element.tag)
-        && element.children.iter().any(|node| match node {
-            HtmlNode::Element(child) => wants_pretty_around(child),
-            HtmlNode::Frame(_) => true,
-            _ => false,
-        });
+        && (injects
+            || element.children.iter().any(|node| match node {
+                HtmlNode::Element(child) => wants_pretty_around(child),
+                HtmlNode::Frame(_) => true,
+                _ => false,
+            }));
 
     w.pretty &= pretty_inside;
     let mut indent = w.pretty;
 
     w.level += 1;
+    // The base url must precede any element referring to urls.
+    if let Some(base_url) = injects.then_some(options.base_url.as_ref()).flatten() {
+        write_indent(w);
+        w.buf.push_str("<base href");
+        write_attr_value(w, base_url).at(element.span)?;
+        w.buf.push('>');
+        indent = w.pretty;
+    }
     for c in &element.children {
         let pretty_around = match c {
             HtmlNode::Tag(_) => continue,
@@ -311,6 +449,12 @@ fn write_children(w: &mut Writer, element: &HtmlElement) -> SourceResult<()> {
         write_node(w, c, element.pre_span)?;
         indent = pretty_around;
     }
+    if injects {
+        for fragment in &options.head {
+            write_indent(w);
+            w.buf.push_str(fragment);
+        }
+    }
     w.level -= 1;
 
     write_indent(w);
@@ -495,6 +639,21 @@ fn wants_pretty_around(element: &HtmlElement) -> bool {
     }
 }
 
+/// Encodes an attribute value, including the leading `=`, into the writer.
+fn write_attr_value(w: &mut Writer, value: &str) -> StrResult<()> {
+    w.buf.push('=');
+    w.buf.push('"');
+    for c in value.chars() {
+        if charsets::is_valid_in_attribute_value(c) {
+            w.buf.push(c);
+        } else {
+            write_escape(w, c)?;
+        }
+    }
+    w.buf.push('"');
+    Ok(())
+}
+
 /// Escape a character.
 fn write_escape(w: &mut Writer, c: char) -> StrResult<()> {
     // See <https://html.spec.whatwg.org/multipage/syntax.html#syntax-charref>
@@ -519,15 +678,295 @@ fn unencodable(c: char) -> EcoString {
 }
 
 /// Encode a laid out frame into the writer.
-fn write_frame(w: &mut Writer, frame: &HtmlFrame) {
+fn write_frame(w: &mut Writer, frame: &HtmlFrame) -> SourceResult<()> {
+    let css = eco_format!("{}", frame.css.to_inline());
     let svg = typst_svg::svg_in_html(
         &frame.inner,
         frame.text_size,
         w.pretty,
         frame.id.as_deref(),
-        &eco_format!("{}", frame.css.to_inline()),
+        &css,
         &frame.anchors,
         w.link_resolver,
     );
-    w.buf.push_str(&svg);
+    let svg = match w.frame_links {
+        Some(rewrite) => rewrite_svg_links(&svg, rewrite),
+        None => svg,
+    };
+
+    // The extracted frame is referred by an image, which loses the links and
+    // anchors inside the frame.
+    let Some(url) = w.rewrite_asset(HtmlAsset::Frame { svg: &svg }) else {
+        w.buf.push_str(&svg);
+        return Ok(());
+    };
+
+    w.buf.push_str("<img class=\"typst-frame\" src");
+    write_attr_value(w, &url).at(frame.span)?;
+    if let Some(id) = &frame.id {
+        w.buf.push_str(" id");
+        write_attr_value(w, id).at(frame.span)?;
+    }
+    if !css.is_empty() {
+        w.buf.push_str(" style");
+        write_attr_value(w, &css).at(frame.span)?;
+    }
+    w.buf.push('>');
+    Ok(())
+}
+
+/// Rewrites the links of the `<a>` elements in an svg.
+///
+/// The other references, e.g. the `<use>` elements of glyphs, are kept.
+fn rewrite_svg_links(svg: &str, rewrite: LinkRewriter) -> String {
+    let mut out = String::with_capacity(svg.len());
+    let mut rest = svg;
+    while let Some(start) = rest.find("<a ") {
+        let (head, tail) = rest.split_at(start);
+        let end = tail.find('>').map_or(tail.len(), |idx| idx + 1);
+        let (tag, tail) = tail.split_at(end);
+        out.push_str(head);
+
+        // Both `href` and `xlink:href` are matched.
+        let href = tag.find("href=\"").map(|idx| idx + "href=\"".len());
+        let value = href.and_then(|start| Some(start..start + tag[start..].find('"')?));
+        match value.and_then(|range| Some((rewrite(&tag[range.clone()])?, range))) {
+            Some((url, range)) => {
+                out.push_str(&tag[..range.start]);
+                // The attribute value is always quoted by double quotes.
+                out.push_str(&url.replace('&', "&amp;").replace('"', "&quot;"));
+                out.push_str(&tag[range.end..]);
+            }
+            None => out.push_str(tag),
+        }
+        rest = tail;
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(all(test, feature = "system-compile"))]
+pub(crate) mod tests {
+    use std::borrow::Cow;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::config::{entry::EntryOpts, CompileOpts};
+    use crate::{Bytes, TypstSystemUniverse};
+
+    const FONT: &[u8] =
+        include_bytes!("../../../../assets/data/LibertinusSerif-Regular-subset.otf");
+
+    /// A red png image of 2x2 pixels.
+    const RED_PNG: &[u8] = &[
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 2, 0, 0, 0, 2, 8, 2,
+        0, 0, 0, 253, 212, 154, 115, 0, 0, 0, 16, 73, 68, 65, 84, 120, 156, 99, 248, 207, 192, 0,
+        68, 12, 16, 10, 0, 31, 238, 3, 253, 139, 95, 20, 212, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66,
+        96, 130,
+    ];
+
+    /// Compiles a typst source into an html document.
+    pub(crate) fn compile_html(source: &str) -> Arc<TypstHtmlDocument> {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let verse = TypstSystemUniverse::new(CompileOpts {
+            entry: EntryOpts::new_workspace(root.into()),
+            no_system_fonts: true,
+            with_embedded_fonts: vec![Cow::Borrowed(FONT)],
+            ..CompileOpts::default()
+        })
+        .unwrap();
+        let verse = verse.with_entry_file(root.join("__test__.typ"));
+
+        let world = verse.snapshot_with_entry_content(Bytes::from_string(source.to_owned()), None);
+        let doc = typst::compile::<TypstHtmlDocument>(&world).output;
+        Arc::new(doc.unwrap_or_else(|err| panic!("failed to compile the source: {err:?}")))
+    }
+
+    fn write(source: &str, options: HtmlWriterOptions) -> String {
+        let output = static_html(&compile_html(source)).unwrap();
+        output.with_options(options).html().unwrap().to_owned()
+    }
+
+    fn position(html: &str, pattern: &str) -> usize {
+        html.find(pattern)
+            .unwrap_or_else(|| panic!("{pattern:?} is not found in {html}"))
+    }
+
+    #[test]
+    fn test_minify() {
+        let source = "= Title\n\nHello *world*\n\n- a\n- b";
+
+        let pretty = write(source, HtmlWriterOptions::default());
+        assert!(pretty.contains('\n'));
+
+        let minified = write(
+            source,
+            HtmlWriterOptions {
+                minify: true,
+                ..HtmlWriterOptions::default()
+            },
+        );
+        assert!(!minified.contains('\n'), "{minified}");
+        assert!(minified.contains("<strong>world</strong>"));
+        assert!(minified.contains("<li>a</li><li>b</li>"), "{minified}");
+        assert!(minified.len() < pretty.len());
+    }
+
+    #[test]
+    fn test_head_injection() {
+        let link = EcoString::from("<link rel=\"stylesheet\" href=\"style.css\">");
+        let script = EcoString::from("<script src=\"main.js\"></script>");
+        let html = write(
+            "Hello",
+            HtmlWriterOptions {
+                head: vec![link.clone(), script.clone()],
+                ..HtmlWriterOptions::default()
+            },
+        );
+
+        let head = position(&html, "<head>");
+        let link = position(&html, &link);
+        let script = position(&html, &script);
+        let head_end = position(&html, "</head>");
+        assert!(head < link && link < script && script < head_end, "{html}");
+        // The fragments are appended after the children of the head.
+        assert!(position(&html, "<meta") < link, "{html}");
+    }
+
+    #[test]
+    fn test_base_placement() {
+        let html = write(
+            "#link(\"guide/index.html\")[Guide]",
+            HtmlWriterOptions {
+                base_url: Some("https://example.com/docs/".into()),
+                head: vec!["<link rel=\"icon\" href=\"favicon.ico\">".into()],
+                ..HtmlWriterOptions::default()
+            },
+        );
+
+        let base = position(&html, "<base href=\"https://example.com/docs/\">");
+        assert_eq!(html.matches("<base").count(), 1);
+        // The base must precede any element referring to urls.
+        assert!(position(&html, "<head>") < base, "{html}");
+        assert!(base < position(&html, "<meta"), "{html}");
+        assert!(base < position(&html, "favicon.ico"), "{html}");
+        assert!(base < position(&html, "guide/index.html"), "{html}");
+    }
+
+    #[test]
+    fn test_no_injection_by_default() {
+        let html = write("Hello", HtmlWriterOptions::default());
+        assert!(!html.contains("<base"));
+    }
+
+    #[test]
+    fn test_asset_hook() {
+        let assets = Arc::new(Mutex::new(vec![]));
+        let hook = {
+            let assets = assets.clone();
+            HtmlAssetHook::new(move |asset| {
+                let mut assets = assets.lock().unwrap();
+                let url = match asset {
+                    HtmlAsset::Image { data_url } => {
+                        assert!(data_url.starts_with("data:image/png;base64,"));
+                        eco_format!("assets/{}.png", assets.len())
+                    }
+                    HtmlAsset::Frame { svg } => {
+                        assert!(svg.contains("<svg"));
+                        eco_format!("assets/{}.svg", assets.len())
+                    }
+                };
+                assets.push(url.clone());
+                Some(url)
+            })
+        };
+
+        let png = RED_PNG.iter().map(u8::to_string).collect::<Vec<_>>();
+        let source = format!(
+            "#image(bytes(({})), alt: \"red\")\n\n#html.frame[Framed]",
+            png.join(", ")
+        );
+        let html = write(
+            &source,
+            HtmlWriterOptions {
+                assets: Some(hook),
+                ..HtmlWriterOptions::default()
+            },
+        );
+
+        assert_eq!(
+            assets.lock().unwrap().as_slice(),
+            ["assets/0.png", "assets/1.svg"]
+        );
+        assert!(html.contains("src=\"assets/0.png\""), "{html}");
+        assert!(
+            html.contains("<img class=\"typst-frame\" src=\"assets/1.svg\""),
+            "{html}"
+        );
+        assert!(!html.contains("data:image"), "{html}");
+        assert!(!html.contains("<svg"), "{html}");
+    }
+
+    #[test]
+    fn test_asset_hook_keeps_assets() {
+        let source = "#html.frame[Framed]";
+        let html = write(
+            source,
+            HtmlWriterOptions {
+                assets: Some(HtmlAssetHook::new(|_| None)),
+                ..HtmlWriterOptions::default()
+            },
+        );
+
+        assert!(html.contains("<svg"), "{html}");
+        assert!(!html.contains("typst-frame\" src"), "{html}");
+    }
+
+    #[test]
+    #[cfg(feature = "hast")]
+    fn test_incr_hast_shifted_positions() {
+        use reflexo_typst2hast::incr::{HastPatch, IncrHastClient, IncrHastServer};
+
+        use crate::exporter::tests::compile_graph;
+
+        let mut server = IncrHastServer::default();
+        let mut client = IncrHastClient::default();
+        let mut pack = |source: &str| {
+            let graph = compile_graph(source);
+            let world: &dyn typst::World = &graph.snap.world;
+            let doc = typst::compile::<TypstHtmlDocument>(world).output;
+            let doc = Arc::new(doc.unwrap_or_else(|err| panic!("failed to compile: {err:?}")));
+            server.pack_delta(&doc, Some(world)).unwrap()
+        };
+
+        client
+            .merge_delta(pack("= Title\n\nHello\n\nWorld"))
+            .unwrap();
+
+        // A one-character edit shifts the positions of the paragraphs after
+        // it, which are not replaced.
+        let delta = pack("= Titles\n\nHello\n\nWorld");
+        let replaced = delta.patches.iter().any(|patch| {
+            matches!(
+                patch,
+                HastPatch::Replace { .. } | HastPatch::Insert { .. } | HastPatch::Remove { .. }
+            )
+        });
+        assert!(!replaced, "{:?}", delta.patches);
+        let shifted = delta
+            .patches
+            .iter()
+            .filter(|patch| matches!(patch, HastPatch::UpdatePosition { .. }))
+            .count();
+        assert!(shifted >= 2, "{:?}", delta.patches);
+
+        // The client ends up with the same Hast as a new client.
+        client.merge_delta(delta).unwrap();
+        let mut new_client = IncrHastClient::default();
+        new_client
+            .merge_delta(server.pack_current().unwrap())
+            .unwrap();
+        assert_eq!(client.root(), new_client.root());
+    }
 }
@@ -0,0 +1,505 @@
+//! Splits an html document into the pages of a static site.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use comemo::Track;
+use ecow::{eco_format, EcoString};
+use reflexo::error::prelude::*;
+use reflexo::typst::TypstHtmlDocument;
+use serde::Serialize;
+use tinymist_world::{CompilerFeat, ExportComputation, WorldComputeGraph};
+use typst::diag::SourceResult;
+use typst::introspection::Tag;
+use typst::model::LateLinkResolver;
+use typst::syntax::Span;
+use typst_html::{attr, tag, HtmlElement, HtmlNode, HtmlTag};
+
+use super::html::{find_tag_child, write_html, ExportHtmlTask, HtmlWriterOptions};
+
+/// The task to export a document to [`HtmlSite`].
+#[derive(Debug, Clone, Default)]
+pub struct ExportHtmlSiteTask {
+    /// The options of the html export.
+    pub html: ExportHtmlTask,
+    /// Where to split the document into pages.
+    pub split: HtmlSplit,
+}
+
+/// Where to split the document into pages.
+///
+/// Only the direct children of the `<body>` element are considered, so that a
+/// split point wrapped in another element doesn't start a new page.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HtmlSplit {
+    /// Splits at the headings of the given level or a higher level. A level-N
+    /// heading is written as an `<h{N+1}>` element, since `<h1>` is reserved
+    /// for the title of the document.
+    Heading(usize),
+    /// Splits at the elements carrying the given label.
+    Label(EcoString),
+}
+
+impl Default for HtmlSplit {
+    fn default() -> Self {
+        Self::Heading(1)
+    }
+}
+
+pub struct HtmlSiteExport;
+
+impl<F: CompilerFeat> ExportComputation<F, TypstHtmlDocument> for HtmlSiteExport {
+    type Output = HtmlSite;
+    type Config = ExportHtmlSiteTask;
+
+    fn run(
+        _graph: &Arc<WorldComputeGraph<F>>,
+        doc: &Arc<TypstHtmlDocument>,
+        config: &ExportHtmlSiteTask,
+    ) -> Result<HtmlSite> {
+        Ok(static_html_site(doc, &config.split, &config.html.writer)?)
+    }
+}
+
+/// The pages of a static site.
+#[derive(Debug, Clone)]
+pub struct HtmlSite {
+    /// The pages in the order of the document. The first page is always
+    /// named `index`.
+    pub pages: Vec<HtmlSitePage>,
+}
+
+/// A page of a static site.
+#[derive(Debug, Clone)]
+pub struct HtmlSitePage {
+    /// The slug of the page, which is unique in the site.
+    pub slug: EcoString,
+    /// The title of the page.
+    pub title: EcoString,
+    /// The encoded html of the page.
+    pub html: String,
+}
+
+impl HtmlSitePage {
+    /// The path of the page relative to the root of the site.
+    pub fn path(&self) -> EcoString {
+        eco_format!("{}.html", self.slug)
+    }
+}
+
+/// The navigation manifest of a static site.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HtmlSiteManifest {
+    pub pages: Vec<HtmlSiteEntry>,
+}
+
+/// An entry of the navigation manifest.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HtmlSiteEntry {
+    pub title: String,
+    pub slug: String,
+    pub path: String,
+    /// The slug of the previous page, if any.
+    pub prev: Option<String>,
+    /// The slug of the next page, if any.
+    pub next: Option<String>,
+}
+
+impl HtmlSite {
+    /// Gets the navigation manifest of the site.
+    pub fn manifest(&self) -> HtmlSiteManifest {
+        let slug_at = |idx: Option<usize>| Some(self.pages.get(idx?)?.slug.to_string());
+        let pages = self
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(idx, page)| HtmlSiteEntry {
+                title: page.title.to_string(),
+                slug: page.slug.to_string(),
+                path: page.path().to_string(),
+                prev: slug_at(idx.checked_sub(1)),
+                next: slug_at(Some(idx + 1)),
+            });
+
+        HtmlSiteManifest {
+            pages: pages.collect(),
+        }
+    }
+
+    /// Gets the navigation manifest of the site in JSON.
+    pub fn manifest_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.manifest())
+            .context_ut("failed to serialize site manifest")
+    }
+}
+
+/// Encodes an HTML document into the pages of a static site.
+pub fn static_html_site(
+    document: &Arc<TypstHtmlDocument>,
+    split: &HtmlSplit,
+    options: &HtmlWriterOptions,
+) -> SourceResult<HtmlSite> {
+    let root = document.root();
+    let head_idx = find_tag_child(root, tag::head);
+    let doc_title = head_idx
+        .and_then(|idx| match &root.children[idx] {
+            HtmlNode::Element(head) => find_tag_child(head, tag::title).map(|i| &head.children[i]),
+            _ => None,
+        })
+        .and_then(|title| match title {
+            HtmlNode::Element(title) => Some(text_of(title)),
+            _ => None,
+        })
+        .filter(|title| !title.is_empty());
+
+    // A document without body is written as a single page.
+    let body = find_tag_child(root, tag::body).and_then(|idx| match &root.children[idx] {
+        HtmlNode::Element(body) => Some((idx, body)),
+        _ => None,
+    });
+    let sections = match body {
+        Some((_, body)) => split_sections(&body.children, split),
+        None => vec![vec![]],
+    };
+
+    let titles = sections
+        .iter()
+        .enumerate()
+        .map(|(idx, section)| {
+            let heading = section.iter().find_map(|node| match node {
+                HtmlNode::Element(e) if heading_level(e.tag).is_some() => Some(text_of(e)),
+                _ => None,
+            });
+            match heading.filter(|title| !title.is_empty()) {
+                Some(title) => title,
+                None if idx == 0 => doc_title.clone().unwrap_or_else(|| "Index".into()),
+                None => eco_format!("Section {}", idx + 1),
+            }
+        })
+        .collect::<Vec<_>>();
+    let slugs = make_slugs(&titles);
+
+    // Maps the ids of the elements to the pages containing them, so that the
+    // links between pages can be resolved.
+    let mut targets = HashMap::new();
+    for (idx, section) in sections.iter().enumerate() {
+        collect_ids(section, &mut |id| {
+            targets.entry(id.clone()).or_insert(idx);
+        });
+    }
+
+    // The links inside frames are resolved against the whole document, and
+    // then rewritten to the pages containing their targets.
+    let link_resolver = LateLinkResolver::new(None, document.introspector().as_ref());
+
+    let mut pages = Vec::with_capacity(sections.len());
+    for (idx, section) in sections.iter().enumerate() {
+        let resolve = |href: &str| -> Option<EcoString> {
+            let id = href.strip_prefix('#')?;
+            let page = *targets.get(id)?;
+            (page != idx).then(|| eco_format!("{}.html#{id}", slugs[page]))
+        };
+
+        let mut page_root = root.clone();
+        page_root.children = root
+            .children
+            .iter()
+            .enumerate()
+            .map(|(child_idx, child)| match child {
+                HtmlNode::Element(e) if Some(child_idx) == head_idx => {
+                    let title = match &doc_title {
+                        Some(doc_title) if idx != 0 => eco_format!("{} - {doc_title}", titles[idx]),
+                        _ => titles[idx].clone(),
+                    };
+                    HtmlNode::Element(with_title(e, title))
+                }
+                HtmlNode::Element(e) if body.is_some_and(|(body_idx, _)| body_idx == child_idx) => {
+                    let mut body = e.clone();
+                    body.children = section.iter().map(|n| rewrite_links(n, &resolve)).collect();
+                    HtmlNode::Element(body)
+                }
+                _ => child.clone(),
+            })
+            .collect();
+
+        pages.push(HtmlSitePage {
+            slug: slugs[idx].clone(),
+            title: titles[idx].clone(),
+            html: write_html(&page_root, link_resolver.track(), Some(&resolve), options)?,
+        });
+    }
+
+    Ok(HtmlSite { pages })
+}
+
+/// Splits the children of the body into sections.
+fn split_sections(children: &[HtmlNode], split: &HtmlSplit) -> Vec<Vec<HtmlNode>> {
+    let mut sections = vec![];
+    let mut current: Vec<HtmlNode> = vec![];
+
+    for node in children {
+        let starts_page = match (split, node) {
+            (HtmlSplit::Heading(level), HtmlNode::Element(e)) => {
+                heading_level(e.tag).is_some_and(|l| l <= level + 1)
+            }
+            (HtmlSplit::Label(label), HtmlNode::Tag(Tag::Start(elem, _))) => elem
+                .label()
+                .is_some_and(|l| l.resolve().as_str() == label.as_str()),
+            _ => false,
+        };
+
+        // Doesn't start an empty page, e.g. when the split point is the first
+        // content of the document.
+        let has_content = current.iter().any(|n| !matches!(n, HtmlNode::Tag(_)));
+        if starts_page && has_content {
+            sections.push(std::mem::take(&mut current));
+        }
+        current.push(node.clone());
+    }
+
+    sections.push(current);
+    sections
+}
+
+/// Gets the level of the heading tag, in which `<h1>` is level 1.
+fn heading_level(tag: HtmlTag) -> Option<usize> {
+    Some(match tag {
+        tag::h1 => 1,
+        tag::h2 => 2,
+        tag::h3 => 3,
+        tag::h4 => 4,
+        tag::h5 => 5,
+        tag::h6 => 6,
+        _ => return None,
+    })
+}
+
+/// Collects the text content of an element.
+fn text_of(element: &HtmlElement) -> EcoString {
+    fn walk(element: &HtmlElement, text: &mut EcoString) {
+        for child in &element.children {
+            match child {
+                HtmlNode::Text(t, _) => text.push_str(t),
+                HtmlNode::Element(e) => walk(e, text),
+                HtmlNode::Tag(_) | HtmlNode::Frame(_) => {}
+            }
+        }
+    }
+
+    let mut text = EcoString::new();
+    walk(element, &mut text);
+    text.trim().into()
+}
+
+/// Makes unique slugs for the pages.
+fn make_slugs(titles: &[EcoString]) -> Vec<EcoString> {
+    let mut used = HashMap::<EcoString, usize>::new();
+    titles
+        .iter()
+        .enumerate()
+        .map(|(idx, title)| {
+            let base = if idx == 0 {
+                "index".into()
+            } else {
+                slugify(title)
+            };
+            let base = if base.is_empty() {
+                eco_format!("section-{}", idx + 1)
+            } else {
+                base
+            };
+
+            let count = used.entry(base.clone()).or_default();
+            *count += 1;
+            if *count == 1 {
+                base
+            } else {
+                eco_format!("{base}-{count}")
+            }
+        })
+        .collect()
+}
+
+/// Converts a title into a url-safe slug.
+fn slugify(title: &str) -> EcoString {
+    let mut slug = EcoString::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').into()
+}
+
+/// Collects the ids of the elements and frames.
+fn collect_ids(nodes: &[HtmlNode], f: &mut impl FnMut(&EcoString)) {
+    for node in nodes {
+        match node {
+            HtmlNode::Element(e) => {
+                if let Some(id) = e.attrs.get(attr::id) {
+                    f(id);
+                }
+                collect_ids(&e.children, f);
+            }
+            HtmlNode::Frame(frame) => {
+                if let Some(id) = &frame.id {
+                    f(id);
+                }
+            }
+            HtmlNode::Tag(_) | HtmlNode::Text(..) => {}
+        }
+    }
+}
+
+/// Rewrites the links to the elements in other pages.
+fn rewrite_links(node: &HtmlNode, resolve: &impl Fn(&str) -> Option<EcoString>) -> HtmlNode {
+    let HtmlNode::Element(e) = node else {
+        return node.clone();
+    };
+
+    let mut e = e.clone();
+    if e.tag == tag::a {
+        e.attrs.0 = e
+            .attrs
+            .0
+            .iter()
+            .map(|(key, value)| match resolve(value) {
+                Some(href) if *key == attr::href => (*key, href),
+                _ => (*key, value.clone()),
+            })
+            .collect();
+    }
+    e.children = e
+        .children
+        .iter()
+        .map(|n| rewrite_links(n, resolve))
+        .collect();
+    HtmlNode::Element(e)
+}
+
+/// Replaces the title of the head element.
+fn with_title(head: &HtmlElement, title: EcoString) -> HtmlElement {
+    let mut head = head.clone();
+    head.children = head
+        .children
+        .iter()
+        .map(|node| match node {
+            HtmlNode::Element(e) if e.tag == tag::title => {
+                let mut e = e.clone();
+                e.children =
+                    std::iter::once(HtmlNode::Text(title.clone(), Span::detached())).collect();
+                HtmlNode::Element(e)
+            }
+            _ => node.clone(),
+        })
+        .collect();
+    head
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugs() {
+        let titles = [
+            "Manual",
+            "Getting Started",
+            "Getting started!",
+            "",
+            "Über uns",
+        ];
+        let titles = titles.map(EcoString::from);
+        let slugs = make_slugs(&titles);
+        assert_eq!(
+            slugs.iter().map(EcoString::as_str).collect::<Vec<_>>(),
+            [
+                "index",
+                "getting-started",
+                "getting-started-2",
+                "section-4",
+                "über-uns"
+            ]
+        );
+    }
+
+    #[cfg(feature = "system-compile")]
+    const SOURCE: &str = r#"= Intro <intro>
+See #link(<usage>)[usage].
+
+Some text.
+
+= Usage <usage>
+Back to #link(<intro>)[intro].
+
+#html.frame[#link(<intro>)[Framed]]
+"#;
+
+    #[cfg(feature = "system-compile")]
+    fn write_site(split: HtmlSplit) -> HtmlSite {
+        let document = crate::exporter::html::tests::compile_html(SOURCE);
+        static_html_site(&document, &split, &HtmlWriterOptions::default()).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "system-compile")]
+    fn test_split_pages() {
+        let site = write_site(HtmlSplit::Heading(1));
+        let slugs = site
+            .pages
+            .iter()
+            .map(|p| p.slug.as_str())
+            .collect::<Vec<_>>();
+        let titles = site
+            .pages
+            .iter()
+            .map(|p| p.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(slugs, ["index", "usage"]);
+        assert_eq!(titles, ["Intro", "Usage"]);
+
+        let [intro, usage] = &site.pages[..] else {
+            unreachable!()
+        };
+        assert!(intro.html.contains("Some text."), "{}", intro.html);
+        assert!(!intro.html.contains("Back to"), "{}", intro.html);
+        assert!(usage.html.contains("Back to"), "{}", usage.html);
+        assert!(!usage.html.contains("Some text."), "{}", usage.html);
+        assert!(
+            usage.html.contains("<title>Usage</title>"),
+            "{}",
+            usage.html
+        );
+
+        let manifest = site.manifest();
+        assert_eq!(manifest.pages[0].prev, None);
+        assert_eq!(manifest.pages[0].next.as_deref(), Some("usage"));
+        assert_eq!(manifest.pages[1].path, "usage.html");
+        assert_eq!(manifest.pages[1].prev.as_deref(), Some("index"));
+
+        // Splitting at a label gives the same pages.
+        let by_label = write_site(HtmlSplit::Label("usage".into()));
+        let slugs = by_label.pages.iter().map(|p| p.slug.as_str());
+        assert_eq!(slugs.collect::<Vec<_>>(), ["index", "usage"]);
+    }
+
+    #[test]
+    #[cfg(feature = "system-compile")]
+    fn test_rewrite_links() {
+        let site = write_site(HtmlSplit::Heading(1));
+        let [intro, usage] = &site.pages[..] else {
+            unreachable!()
+        };
+
+        assert!(intro.html.contains("href=\"usage.html#"), "{}", intro.html);
+        // Both the link in the text and the link in the frame refer to the
+        // first page.
+        let (text, frame) = usage.html.split_once("<svg").expect("frame is not written");
+        assert!(text.contains("href=\"index.html#"), "{}", usage.html);
+        assert!(frame.contains("href=\"index.html#"), "{}", usage.html);
+    }
+}
@@ -0,0 +1,123 @@
+use tinymist_task::{ExportTask, ExportTransform, Pages};
+
+/// Collects the zero-based indices of the pages selected by the `pages`
+/// transform of an export task.
+///
+/// All pages are selected if the task has no `pages` transform.
+pub fn exported_page_indices(export: &ExportTask, page_count: usize) -> Vec<usize> {
+    let ranges = export.transform.iter().find_map(|t| match t {
+        ExportTransform::Pages { ranges } => Some(ranges),
+        _ => None,
+    });
+
+    let Some(ranges) = ranges else {
+        return (0..page_count).collect();
+    };
+
+    (0..page_count)
+        .filter(|idx| ranges.iter().any(|range| contains_page(range, idx + 1)))
+        .collect()
+}
+
+/// Whether the one-based page number is in the range.
+fn contains_page(range: &Pages, page: usize) -> bool {
+    let start = range.0.start().is_none_or(|start| start.get() <= page);
+    let end = range.0.end().is_none_or(|end| page <= end.get());
+    start && end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(ranges: &str) -> ExportTask {
+        let ranges = ranges
+            .split(',')
+            .map(|range| range.parse::<Pages>().unwrap())
+            .collect();
+        ExportTask {
+            transform: vec![ExportTransform::Pages { ranges }],
+            ..ExportTask::default()
+        }
+    }
+
+    #[test]
+    fn test_all_pages() {
+        assert_eq!(exported_page_indices(&ExportTask::default(), 3), [0, 1, 2]);
+        assert_eq!(
+            exported_page_indices(&ExportTask::default(), 0),
+            [] as [usize; 0]
+        );
+    }
+
+    #[test]
+    fn test_open_ended_range() {
+        assert_eq!(exported_page_indices(&export("8-"), 10), [7, 8, 9]);
+        assert_eq!(exported_page_indices(&export("-2"), 10), [0, 1]);
+    }
+
+    #[test]
+    fn test_multiple_ranges() {
+        assert_eq!(exported_page_indices(&export("2,5"), 6), [1, 4]);
+        assert_eq!(
+            exported_page_indices(&export("2,3-4,6-"), 7),
+            [1, 2, 3, 5, 6]
+        );
+        // overlapping ranges select a page only once
+        assert_eq!(exported_page_indices(&export("1-3,2-4"), 5), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_out_of_range_pages() {
+        assert_eq!(exported_page_indices(&export("5-"), 3), [] as [usize; 0]);
+        assert_eq!(exported_page_indices(&export("2-9"), 3), [1, 2]);
+        assert_eq!(exported_page_indices(&export("4"), 3), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn test_contains_page() {
+        let range = "3-5".parse::<Pages>().unwrap();
+        assert!(!contains_page(&range, 2));
+        assert!(contains_page(&range, 3));
+        assert!(contains_page(&range, 5));
+        assert!(!contains_page(&range, 6));
+    }
+
+    #[test]
+    #[cfg(feature = "system-compile")]
+    fn test_lower_selected_pages() {
+        use reflexo_typst2vec::{ir::Module, pass::Typst2VecPass};
+
+        use crate::exporter::tests::compile_paged;
+
+        let (_, doc) = compile_paged(
+            "#set page(width: 100pt, height: 100pt)\n\
+             #rect(fill: rgb(\"#123456\"))[ABCDEFG]\n\
+             #pagebreak()\n\
+             #set page(width: 80pt, height: 60pt)\n\
+             #circle(fill: rgb(\"#abcdef\"))[Z]",
+        );
+        let lower = |indices: &[usize]| {
+            let pass = Typst2VecPass::default();
+            let pages = pass.paged_by_indices(&doc, indices);
+            (pages, pass.finalize())
+        };
+        let uses_color = |module: &Module, color: &str| {
+            let items = format!("{:?}", module.items);
+            items.contains(color)
+        };
+
+        let (all_pages, all) = lower(&[0, 1]);
+        assert_eq!(all_pages.len(), 2);
+        assert!(uses_color(&all, "123456") && uses_color(&all, "abcdef"));
+
+        let (pages, selected) = lower(&[1]);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].size, all_pages[1].size);
+        // The rect and the glyphs of the first page are left out.
+        assert!(!uses_color(&selected, "123456"));
+        assert!(uses_color(&selected, "abcdef"));
+        assert!(selected.items.len() < all.items.len());
+        assert!(selected.glyphs.len() < all.glyphs.len());
+    }
+}
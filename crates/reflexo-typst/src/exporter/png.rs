@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use reflexo::error::prelude::*;
+use reflexo::typst::{Bytes, TypstPagedDocument};
+use serde::{Deserialize, Serialize};
+use tinymist_task::ExportTask;
+
+use super::pages::exported_page_indices;
+use crate::world::{CompilerFeat, ExportComputation, WorldComputeGraph};
+
+/// The task to rasterize each page of a document into a separate png file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportPngPagesTask {
+    #[serde(flatten)]
+    pub export: ExportTask,
+    /// The resolution of the output images, in pixels per inch.
+    pub ppi: f32,
+}
+
+impl Default for ExportPngPagesTask {
+    fn default() -> Self {
+        Self {
+            export: ExportTask::default(),
+            ppi: 144.0,
+        }
+    }
+}
+
+/// A rasterized page.
+#[derive(Debug, Clone)]
+pub struct PngPage {
+    /// The zero-based index of the page in the document.
+    pub index: usize,
+    /// The encoded png image.
+    pub data: Bytes,
+}
+
+pub struct PngPagesExport;
+
+impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for PngPagesExport {
+    type Output = Vec<PngPage>;
+    type Config = ExportPngPagesTask;
+
+    fn run(
+        _g: &Arc<WorldComputeGraph<F>>,
+        doc: &Arc<TypstPagedDocument>,
+        config: &Self::Config,
+    ) -> Result<Vec<PngPage>> {
+        if !(config.ppi.is_finite() && config.ppi > 0.0) {
+            return Err(error_once!("invalid ppi", ppi: config.ppi));
+        }
+
+        let pages = doc.pages();
+        let pixel_per_pt = config.ppi / 72.0;
+
+        exported_page_indices(&config.export, pages.len())
+            .into_iter()
+            .map(|index| {
+                let pixmap = typst_render::render(&pages[index], pixel_per_pt);
+                let data = pixmap.encode_png().context_ut("failed to encode png")?;
+
+                Ok(PngPage {
+                    index,
+                    data: Bytes::new(data),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "system-compile"))]
+mod tests {
+    use tinymist_task::{ExportTransform, Pages};
+
+    use super::*;
+    use crate::exporter::tests::compile_paged;
+
+    /// Reads the size of an encoded png from its `IHDR` chunk.
+    fn png_size(data: &[u8]) -> (u32, u32) {
+        let read = |offset: usize| u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap());
+        (read(16), read(20))
+    }
+
+    const SOURCE: &str =
+        "#set page(width: 100pt, height: 50pt)\nA\n#pagebreak()\nB\n#pagebreak()\nC";
+
+    #[test]
+    fn test_ppi_sets_pixel_size() {
+        let (graph, doc) = compile_paged(SOURCE);
+
+        for (ppi, size) in [(72., (100, 50)), (144., (200, 100))] {
+            let config = ExportPngPagesTask {
+                ppi,
+                ..ExportPngPagesTask::default()
+            };
+            let pages = PngPagesExport::run(&graph, &doc, &config).unwrap();
+            assert_eq!(pages.len(), 3);
+            for page in pages {
+                assert_eq!(png_size(&page.data), size, "ppi: {ppi}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_selected_pages_keep_their_indices() {
+        let (graph, doc) = compile_paged(SOURCE);
+        let config = ExportPngPagesTask {
+            export: ExportTask {
+                transform: vec![ExportTransform::Pages {
+                    ranges: vec!["2-".parse::<Pages>().unwrap()],
+                }],
+                ..ExportTask::default()
+            },
+            ..ExportPngPagesTask::default()
+        };
+
+        let pages = PngPagesExport::run(&graph, &doc, &config).unwrap();
+        let indices = pages.iter().map(|page| page.index).collect::<Vec<_>>();
+        assert_eq!(indices, [1, 2]);
+    }
+
+    #[test]
+    fn test_invalid_ppi() {
+        let (graph, doc) = compile_paged(SOURCE);
+        for ppi in [0., -1., f32::NAN] {
+            let config = ExportPngPagesTask {
+                ppi,
+                ..ExportPngPagesTask::default()
+            };
+            assert!(PngPagesExport::run(&graph, &doc, &config).is_err());
+        }
+    }
+}
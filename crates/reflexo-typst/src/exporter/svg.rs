@@ -4,10 +4,14 @@ use reflexo::error::prelude::*;
 
 use reflexo::typst::Bytes;
 use reflexo::typst::TypstPagedDocument;
-use reflexo_vec2svg::{render_svg, render_svg_html, ExportFeature, SvgExporter};
+use reflexo_vec2svg::{
+    render_svg_by_indices_with_options, render_svg_html_by_indices_with_options, render_svg_pages,
+    ExportFeature, SvgExportOptions, SvgExporter, SvgGlyphSprite,
+};
 use serde::{Deserialize, Serialize};
 use tinymist_task::{ExportSvgTask, ExportTask};
 
+use super::pages::exported_page_indices;
 use crate::world::{CompilerFeat, ExportComputation, WorldComputeGraph};
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -28,9 +32,11 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
     fn run(
         _g: &Arc<WorldComputeGraph<F>>,
         doc: &Arc<TypstPagedDocument>,
-        _config: &Self::Config,
+        config: &Self::Config,
     ) -> Result<Bytes> {
-        Ok(Bytes::new(SvgExporter::<EF>::svg_doc(doc).to_bytes()))
+        let indices = exported_page_indices(&config.export, doc.pages().len());
+        let doc = SvgExporter::<EF>::svg_doc_by_indices(doc, &indices);
+        Ok(Bytes::new(doc.to_bytes()))
     }
 }
 
@@ -39,6 +45,9 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
 pub struct ExportWebSvgTask {
     #[serde(flatten)]
     pub base: ExportSvgTask,
+    /// The options of the svg, which default to the plain svg options.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<SvgExportOptions>,
 }
 
 pub struct WebSvgExport<EF>(std::marker::PhantomData<EF>);
@@ -52,9 +61,11 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
     fn run(
         _g: &Arc<WorldComputeGraph<F>>,
         doc: &Arc<TypstPagedDocument>,
-        _config: &Self::Config,
+        config: &Self::Config,
     ) -> Result<String> {
-        Ok(render_svg(doc))
+        let indices = exported_page_indices(&config.base.export, doc.pages().len());
+        let options = config.options.unwrap_or_default();
+        Ok(render_svg_by_indices_with_options(doc, &indices, options))
     }
 }
 
@@ -63,6 +74,10 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
 pub struct ExportWebSvgHtmlTask {
     #[serde(flatten)]
     pub base: ExportSvgTask,
+    /// The options of the svg, which default to the options of the export
+    /// feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<SvgExportOptions>,
 }
 
 pub struct WebSvgHtmlExport<EF>(std::marker::PhantomData<EF>);
@@ -76,8 +91,190 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
     fn run(
         _g: &Arc<WorldComputeGraph<F>>,
         doc: &Arc<TypstPagedDocument>,
-        _config: &Self::Config,
+        config: &Self::Config,
     ) -> Result<String> {
-        Ok(render_svg_html::<EF>(doc))
+        let indices = exported_page_indices(&config.base.export, doc.pages().len());
+        let options = config.options.unwrap_or(SvgExportOptions::of::<EF>());
+        Ok(render_svg_html_by_indices_with_options(
+            doc, &indices, options,
+        ))
+    }
+}
+
+/// The task to export each page of a document into a standalone svg file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportWebSvgPagesTask {
+    #[serde(flatten)]
+    pub export: ExportTask,
+    /// The options of the svg, which default to the plain svg options.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<SvgExportOptions>,
+    /// The url of the shared sprite sheet defining the glyphs, e.g.
+    /// `glyphs.svg`. Each page defines its own glyphs if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glyph_sprite: Option<String>,
+}
+
+/// The standalone svgs of the exported pages.
+#[derive(Debug, Clone)]
+pub struct SvgPagesOutput {
+    /// The svg of each exported page.
+    pub pages: Vec<SvgPage>,
+    /// The sprite sheet defining the glyphs, which is only present if
+    /// [`ExportWebSvgPagesTask::glyph_sprite`] is set.
+    pub glyphs: Option<String>,
+}
+
+/// A standalone svg page.
+#[derive(Debug, Clone)]
+pub struct SvgPage {
+    /// The zero-based index of the page in the document.
+    pub index: usize,
+    /// The svg content.
+    pub data: String,
+}
+
+pub struct WebSvgPagesExport;
+
+impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for WebSvgPagesExport {
+    type Output = SvgPagesOutput;
+    type Config = ExportWebSvgPagesTask;
+
+    fn run(
+        _g: &Arc<WorldComputeGraph<F>>,
+        doc: &Arc<TypstPagedDocument>,
+        config: &Self::Config,
+    ) -> Result<SvgPagesOutput> {
+        let indices = exported_page_indices(&config.export, doc.pages().len());
+        let options = config.options.unwrap_or_default();
+        let sprite = match &config.glyph_sprite {
+            Some(url) => SvgGlyphSprite::External(url.as_str().into()),
+            None => SvgGlyphSprite::Inline,
+        };
+
+        let output = render_svg_pages(doc, &indices, options, &sprite);
+        Ok(SvgPagesOutput {
+            pages: output
+                .pages
+                .into_iter()
+                .map(|(index, data)| SvgPage { index, data })
+                .collect(),
+            glyphs: output.glyphs,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "system-compile"))]
+mod tests {
+    use std::borrow::Cow;
+    use std::path::Path;
+
+    use reflexo_vec2svg::SvgMinifyOptions;
+
+    use super::*;
+    use crate::config::{entry::EntryOpts, CompileOpts};
+    use crate::TypstSystemUniverse;
+
+    const FONT: &[u8] =
+        include_bytes!("../../../../assets/data/LibertinusSerif-Regular-subset.otf");
+
+    fn compile_paged(source: &str) -> TypstPagedDocument {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let verse = TypstSystemUniverse::new(CompileOpts {
+            entry: EntryOpts::new_workspace(root.into()),
+            no_system_fonts: true,
+            with_embedded_fonts: vec![Cow::Borrowed(FONT)],
+            ..CompileOpts::default()
+        })
+        .unwrap();
+        let verse = verse.with_entry_file(root.join("__test__.typ"));
+
+        let world = verse.snapshot_with_entry_content(Bytes::from_string(source.to_owned()), None);
+        let doc = typst::compile::<TypstPagedDocument>(&world).output;
+        doc.unwrap_or_else(|err| panic!("failed to compile the source: {err:?}"))
+    }
+
+    /// Gets the ids of the glyphs referenced from the sprite at `url`.
+    fn sprite_refs<'a>(page: &'a str, url: &str) -> Vec<&'a str> {
+        let prefix = format!("href=\"{url}#");
+        page.split(prefix.as_str())
+            .skip(1)
+            .map(|rest| &rest[..rest.find('"').unwrap()])
+            .collect()
+    }
+
+    #[test]
+    fn test_pages_reference_external_sprite() {
+        let doc = compile_paged("Hello\n#pagebreak()\nWorld");
+        let sprite = SvgGlyphSprite::External("glyphs.svg".into());
+
+        let all_options = [
+            SvgExportOptions::default(),
+            SvgExportOptions {
+                use_stable_glyph_id: false,
+                minify: SvgMinifyOptions::full(),
+                ..SvgExportOptions::default()
+            },
+        ];
+        for options in all_options {
+            let output = render_svg_pages(&doc, &[0, 1], options, &sprite);
+            let glyphs = output.glyphs.expect("the sprite is not rendered");
+            assert_eq!(output.pages.len(), 2);
+
+            for (idx, page) in &output.pages {
+                assert!(
+                    !page.contains("class=\"outline_glyph\""),
+                    "page {idx} defines glyphs"
+                );
+                let refs = sprite_refs(page, "glyphs.svg");
+                assert!(!refs.is_empty(), "page {idx} doesn't use the sprite");
+                for id in refs {
+                    assert!(
+                        glyphs.contains(&format!("id=\"{id}\"")),
+                        "glyph {id} of page {idx} is missing in the sprite, options: {options:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_without_text_element() {
+        let doc = compile_paged("Hello");
+        let render = |should_render_text_element| {
+            let options = SvgExportOptions {
+                should_render_text_element,
+                ..SvgExportOptions::default()
+            };
+            render_svg_by_indices_with_options(&doc, &[0], options)
+        };
+
+        let svg = render(true);
+        assert!(svg.contains("class=\"tsel\""));
+        assert!(svg.contains("Hello"));
+
+        // The glyphs are still rendered without the selectable text.
+        let svg = render(false);
+        assert!(!svg.contains("<foreignObject"));
+        assert!(!svg.contains("class=\"tsel\""));
+        assert!(!svg.contains("Hello"));
+        assert!(svg.contains("<use "));
+    }
+
+    #[test]
+    fn test_pages_define_inline_glyphs() {
+        let doc = compile_paged("Hello");
+        let output = render_svg_pages(
+            &doc,
+            &[0],
+            SvgExportOptions::default(),
+            &SvgGlyphSprite::Inline,
+        );
+
+        assert!(output.glyphs.is_none());
+        let (_, page) = &output.pages[0];
+        assert!(page.contains("class=\"outline_glyph\""));
+        assert!(page.contains("href=\"#"));
     }
 }
@@ -0,0 +1,40 @@
+//! Helpers to test the exporters on compiled documents.
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Arc;
+
+use reflexo::typst::{Bytes, TypstPagedDocument};
+
+use crate::config::{entry::EntryOpts, CompileOpts};
+use crate::system::SystemWorldComputeGraph;
+use crate::{CompileSnapshot, TypstSystemUniverse};
+
+const FONT: &[u8] = include_bytes!("../../../../assets/data/LibertinusSerif-Regular-subset.otf");
+
+/// Creates the computation graph of a typst source, using the embedded test
+/// font only.
+pub(crate) fn compile_graph(source: &str) -> Arc<SystemWorldComputeGraph> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let verse = TypstSystemUniverse::new(CompileOpts {
+        entry: EntryOpts::new_workspace(root.into()),
+        no_system_fonts: true,
+        with_embedded_fonts: vec![Cow::Borrowed(FONT)],
+        ..CompileOpts::default()
+    })
+    .unwrap();
+    let verse = verse.with_entry_file(root.join("__test__.typ"));
+
+    let world = verse.snapshot_with_entry_content(Bytes::from_string(source.to_owned()), None);
+    SystemWorldComputeGraph::new(CompileSnapshot::from_world(world))
+}
+
+/// Compiles a typst source into a paged document.
+pub(crate) fn compile_paged(
+    source: &str,
+) -> (Arc<SystemWorldComputeGraph>, Arc<TypstPagedDocument>) {
+    let graph = compile_graph(source);
+    let doc = typst::compile::<TypstPagedDocument>(&graph.snap.world).output;
+    let doc = doc.unwrap_or_else(|err| panic!("failed to compile the source: {err:?}"));
+    (graph, Arc::new(doc))
+}
@@ -1 +1,55 @@
+use std::sync::Arc;
+
+use reflexo::error::prelude::*;
+use reflexo::typst::TypstPagedDocument;
 pub use tinymist_task::text::TextExport;
+use tinymist_task::ExportTextTask;
+use typst::layout::{Frame, FrameItem};
+
+use super::pages::exported_page_indices;
+use crate::world::{CompilerFeat, ExportComputation, WorldComputeGraph};
+
+/// Exports the text of the pages selected by the `pages` transform of the
+/// task. It falls back to [`TextExport`] if all pages are selected.
+pub struct PagedTextExport;
+
+impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for PagedTextExport {
+    type Output = String;
+    type Config = ExportTextTask;
+
+    fn run(
+        g: &Arc<WorldComputeGraph<F>>,
+        doc: &Arc<TypstPagedDocument>,
+        config: &Self::Config,
+    ) -> Result<String> {
+        let pages = doc.pages();
+        let indices = exported_page_indices(&config.export, pages.len());
+        if indices.len() == pages.len() {
+            return <TextExport as ExportComputation<F, TypstPagedDocument>>::run(g, doc, config);
+        }
+
+        let mut text = String::new();
+        for idx in indices {
+            write_frame_text(&mut text, &pages[idx].frame);
+        }
+        Ok(text)
+    }
+}
+
+fn write_frame_text(text: &mut String, frame: &Frame) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => write_frame_text(text, &group.frame),
+            FrameItem::Text(item) => text.push_str(item.text.as_str()),
+            _ => {}
+        }
+    }
+
+    #[cfg(not(feature = "no-content-hint"))]
+    {
+        let c = frame.content_hint();
+        if c != '\0' {
+            text.push(c);
+        }
+    }
+}
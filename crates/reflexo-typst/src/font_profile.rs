@@ -0,0 +1,195 @@
+//! Font profiles, which describe fonts without parsing their data.
+//!
+//! A profile is usually measured by `typst-ts-cli font measure` ahead of time,
+//! so that a compiler can register fonts by the measured [`FontInfo`] and
+//! load the font data lazily on first use.
+
+use serde::{Deserialize, Serialize};
+use typst::text::FontInfo;
+
+/// The version of the font profile format.
+pub const FONT_PROFILE_VERSION: &str = "v1";
+
+/// A serialized profile of fonts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FontProfile {
+    /// The version of the profile format, see [`FONT_PROFILE_VERSION`].
+    pub version: String,
+    /// The version of the compiler measuring the fonts.
+    #[serde(default)]
+    pub build_info: String,
+    /// The measured fonts.
+    pub items: Vec<FontProfileItem>,
+}
+
+/// A font in the [`FontProfile`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FontProfileItem {
+    /// The path to the font file, if the font is loaded from the file system.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// The hash of the font file data, in hex.
+    pub hash: String,
+    /// The index of the font in a font collection.
+    pub index: u32,
+    /// The font info, which also contains the variant and the coverage of the
+    /// font.
+    pub info: FontInfo,
+}
+
+impl FontProfile {
+    /// Creates an empty profile in current version.
+    pub fn new() -> Self {
+        Self {
+            version: FONT_PROFILE_VERSION.to_owned(),
+            build_info: crate::build_info::VERSION.to_owned(),
+            items: vec![],
+        }
+    }
+
+    /// Measures the fonts in a font file or collection and adds them to the
+    /// profile.
+    pub fn add_font_data(&mut self, path: Option<String>, data: &[u8]) {
+        let hash = font_data_hash(data);
+        self.items.extend(
+            FontInfo::iter(data)
+                .enumerate()
+                .map(|(index, info)| FontProfileItem {
+                    path: path.clone(),
+                    hash: hash.clone(),
+                    index: index as u32,
+                    info,
+                }),
+        );
+    }
+}
+
+impl FontProfileItem {
+    /// Whether the font data is the measured one, by comparing the hash.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        self.hash == font_data_hash(data)
+    }
+}
+
+/// Computes the hash of font file data, in hex.
+pub fn font_data_hash(data: &[u8]) -> String {
+    format!("{:032x}", typst::utils::hash128(data))
+}
+
+/// Measures the fonts in the given directories, and the system fonts if
+/// `include_system_fonts` is set.
+#[cfg(feature = "system-compile")]
+pub fn measure_fonts(
+    font_paths: &[std::path::PathBuf],
+    include_system_fonts: bool,
+) -> std::io::Result<FontProfile> {
+    use std::collections::BTreeSet;
+
+    let mut db = fontdb::Database::new();
+    if include_system_fonts {
+        db.load_system_fonts();
+    }
+    for path in font_paths {
+        if path.is_dir() {
+            db.load_fonts_dir(path);
+        } else {
+            db.load_font_file(path)?;
+        }
+    }
+
+    // Sorts font files to make the profile reproducible.
+    let files = db
+        .faces()
+        .filter_map(|face| match &face.source {
+            fontdb::Source::File(path) | fontdb::Source::SharedFile(path, _) => Some(path.clone()),
+            fontdb::Source::Binary(_) => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut profile = FontProfile::new();
+    for path in files {
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(err) => {
+                log::warn!("failed to read font file {}: {err}", path.display());
+                continue;
+            }
+        };
+
+        profile.add_font_data(Some(path.to_string_lossy().to_string()), &data);
+    }
+
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGULAR: &[u8] =
+        include_bytes!("../../../assets/data/LibertinusSerif-Regular-subset.otf");
+    const BOLD: &[u8] = include_bytes!("../../../assets/data/LibertinusSerif-Bold-subset.otf");
+
+    #[test]
+    fn test_add_font_data() {
+        let mut profile = FontProfile::new();
+        profile.add_font_data(Some("regular.otf".to_owned()), REGULAR);
+        profile.add_font_data(None, BOLD);
+
+        assert_eq!(profile.version, FONT_PROFILE_VERSION);
+        assert_eq!(profile.items.len(), 2);
+
+        let [regular, bold] = profile.items.as_slice() else {
+            unreachable!()
+        };
+        assert_eq!(regular.path.as_deref(), Some("regular.otf"));
+        assert_eq!(regular.index, 0);
+        assert!(regular.info.family.contains("Libertinus"));
+        assert!(regular.info.variant.weight < bold.info.variant.weight);
+        assert_eq!(bold.path, None);
+
+        assert!(regular.matches(REGULAR));
+        assert!(!regular.matches(BOLD));
+        assert_ne!(regular.hash, bold.hash);
+    }
+
+    #[test]
+    fn test_profile_round_trip() {
+        let mut profile = FontProfile::new();
+        profile.add_font_data(Some("regular.otf".to_owned()), REGULAR);
+
+        let json = serde_json::to_string(&profile).unwrap();
+        let loaded: FontProfile = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.version, profile.version);
+        assert_eq!(loaded.build_info, profile.build_info);
+        assert_eq!(loaded.items.len(), 1);
+        let (item, loaded_item) = (&profile.items[0], &loaded.items[0]);
+        assert_eq!(loaded_item.path, item.path);
+        assert_eq!(loaded_item.hash, item.hash);
+        assert_eq!(loaded_item.index, item.index);
+        assert_eq!(loaded_item.info, item.info);
+        assert!(loaded_item.matches(REGULAR));
+    }
+
+    #[test]
+    #[cfg(feature = "system-compile")]
+    fn test_measure_fonts() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../assets/data");
+        let profile = measure_fonts(&[dir], false).unwrap();
+
+        let paths = profile
+            .items
+            .iter()
+            .filter_map(|item| item.path.as_deref())
+            .filter(|path| path.contains("LibertinusSerif"))
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), 4);
+        assert!(paths.windows(2).all(|w| w[0] < w[1]), "paths are sorted");
+
+        for item in &profile.items {
+            let data = std::fs::read(item.path.as_ref().unwrap()).unwrap();
+            assert!(item.matches(&data));
+        }
+    }
+}
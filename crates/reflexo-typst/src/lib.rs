@@ -32,6 +32,7 @@
 
 pub mod config;
 pub mod error;
+pub mod font_profile;
 pub mod query;
 pub mod task;
 
@@ -39,7 +40,7 @@ pub mod task;
 pub use reflexo_typst2hast::hast;
 
 pub use concepts::*;
-pub use error::{CompileReport, CompileReportMsg};
+pub use error::{CompileReport, CompileReportMsg, JsonPosition, JsonRange};
 
 /// time things about compiler.
 pub use reflexo::time;
@@ -69,9 +70,14 @@ pub use exporter::ast::{dump_ast, AstExport, ExportAstTask};
 pub use exporter::dyn_svg::*;
 #[cfg(feature = "html")]
 pub use exporter::html::*;
+#[cfg(feature = "html")]
+pub use exporter::html_site::*;
+pub use exporter::pages::exported_page_indices;
+#[cfg(feature = "png")]
+pub use exporter::png::*;
 #[cfg(feature = "svg")]
 pub use exporter::svg::*;
-pub use exporter::text::TextExport;
+pub use exporter::text::{PagedTextExport, TextExport};
 #[cfg(feature = "svg")]
 pub use reflexo_vec2svg as svg;
 pub use tinymist_task::compute::*;
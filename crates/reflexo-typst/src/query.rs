@@ -1,5 +1,6 @@
 use comemo::Track;
 use reflexo::typst::TypstDocument;
+use serde::Serialize;
 use typst::{
     diag::{EcoString, StrResult},
     engine::Sink,
@@ -10,6 +11,8 @@ use typst::{
 };
 use typst_eval::eval_string;
 
+use crate::error::{resolve_source_span, JsonRange};
+
 // todo: query exporter
 /// Retrieve the matches for the selector.
 pub fn retrieve(
@@ -45,3 +48,49 @@ pub fn retrieve(
         .into_iter()
         .collect::<Vec<_>>())
 }
+
+/// The location of a queried element.
+#[derive(Debug, Clone, Serialize)]
+pub struct ElementLocation {
+    /// The one-based page number of the element, if it is laid out.
+    pub page: Option<usize>,
+    /// The position of the element on the page, in points.
+    pub position: Option<ElementPosition>,
+    /// The package containing the source file, if any.
+    pub package: Option<String>,
+    /// The path to the source file of the element.
+    pub path: Option<String>,
+    /// The zero-based range of the element in the source file.
+    pub range: Option<JsonRange>,
+}
+
+/// A position on a page, in points.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ElementPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Locate a retrieved element in the document and the source files.
+pub fn locate(world: &dyn World, document: &TypstDocument, elem: &Content) -> ElementLocation {
+    let position = match document {
+        TypstDocument::Paged(..) => elem
+            .location()
+            .and_then(|loc| document.introspector().position(loc))
+            .map(|pos| pos.as_paged_or_default()),
+        _ => None,
+    };
+
+    let (package, path, range) = resolve_source_span(elem.span(), Some(world));
+
+    ElementLocation {
+        page: position.map(|pos| pos.page.get()),
+        position: position.map(|pos| ElementPosition {
+            x: pos.point.x.to_pt(),
+            y: pos.point.y.to_pt(),
+        }),
+        package: (!package.is_empty()).then_some(package),
+        path: (!path.is_empty()).then_some(path),
+        range: range.map(JsonRange::from),
+    }
+}
@@ -4,6 +4,8 @@ pub use tinymist_std::*;
 pub use tinymist_world::debug_loc;
 
 pub mod vector {
+    pub mod extract;
+    pub mod frame;
     #[cfg(feature = "rkyv")]
     pub mod incr;
     pub mod ir;
@@ -0,0 +1,336 @@
+//! Text extraction from the vector items.
+//!
+//! The text items are collected into lines in the order of the vector items,
+//! which follows the reading order of the document. Lines are then grouped
+//! into paragraphs by the vertical gaps between them.
+
+use tiny_skia_path::Transform;
+
+use super::ir::{self, LayoutSelectorExpr, Module, MultiVecDocument, Page, TextItem, VecItem};
+use crate::hash::Fingerprint;
+
+/// The format of the extracted text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TextFormat {
+    /// Plain text, in which paragraphs are separated by blank lines.
+    #[default]
+    Plain,
+    /// Markdown, in which headings are inferred from the font sizes.
+    Markdown,
+}
+
+impl MultiVecDocument {
+    /// Extracts the text of the pages in the first layout.
+    pub fn extract_text(&self, format: TextFormat) -> String {
+        let layout = self
+            .layouts
+            .first()
+            .and_then(|layout| layout.by_selector(&LayoutSelectorExpr::Any).ok());
+        let pages = layout.as_ref().and_then(|layout| layout.pages_meta());
+        extract_text(&self.module, pages.unwrap_or_default(), format)
+    }
+}
+
+/// Extracts the text of the pages.
+pub fn extract_text(module: &Module, pages: &[Page], format: TextFormat) -> String {
+    let pages = pages
+        .iter()
+        .map(|page| {
+            let mut collector = LineCollector::default();
+            collector.walk(module, Transform::identity(), page.content);
+            paragraphs(collector.lines)
+        })
+        .collect::<Vec<_>>();
+
+    let headings = match format {
+        TextFormat::Plain => vec![],
+        TextFormat::Markdown => {
+            let lines = pages.iter().flatten().flatten();
+            heading_sizes(lines.map(|line| (line.size, line.text.chars().count())))
+        }
+    };
+
+    let mut output = String::new();
+    for paragraph in pages.iter().flatten() {
+        if !output.is_empty() {
+            output.push_str("\n\n");
+        }
+
+        match format {
+            TextFormat::Plain => {
+                let lines = paragraph.iter().map(|line| line.text.as_str());
+                output.push_str(&lines.collect::<Vec<_>>().join("\n"));
+            }
+            TextFormat::Markdown => {
+                let size = round_size(paragraph[0].size);
+                if let Some(level) = headings.iter().position(|s| *s == size) {
+                    let lines = paragraph.iter().map(|line| line.text.trim());
+                    output.push_str(&"#".repeat(level + 1));
+                    output.push(' ');
+                    output.push_str(&escape_markdown(&lines.collect::<Vec<_>>().join(" ")));
+                } else {
+                    let lines = paragraph
+                        .iter()
+                        .map(|line| escape_markdown(line.text.trim()));
+                    output.push_str(&lines.collect::<Vec<_>>().join("\n"));
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// A line of text in the page.
+#[derive(Debug, Clone)]
+struct Line {
+    text: String,
+    /// The top of the line.
+    top: f32,
+    /// The bottom of the line.
+    bottom: f32,
+    /// The right of the last text item in the line.
+    right: f32,
+    /// The largest font size in the line.
+    size: f32,
+}
+
+#[derive(Default)]
+struct LineCollector {
+    lines: Vec<Line>,
+    /// Whether a line break is hinted since the last text item.
+    line_break: bool,
+}
+
+impl LineCollector {
+    fn walk(&mut self, module: &Module, ts: Transform, fg: Fingerprint) {
+        let Some(item) = module.get_item(&fg) else {
+            return;
+        };
+
+        use VecItem::*;
+        match item {
+            Group(t) => {
+                for (pos, child) in t.0.iter() {
+                    let ts = ts.pre_translate(pos.x.0, pos.y.0);
+                    self.walk(module, ts, *child);
+                }
+            }
+            Item(t) => {
+                let trans: ir::Transform = t.0.clone().into();
+                let ts = ts.pre_concat(trans.into());
+                self.walk(module, ts, t.1);
+            }
+            Labelled(t) => self.walk(module, ts, t.1),
+            Text(t) => self.push_text(module, ts, t),
+            ContentHint(c) => {
+                if *c == '\n' {
+                    self.line_break = true;
+                }
+            }
+            Image(..) | Link(..) | Path(..) | SizedRawHtml(..) => {}
+            None | ColorTransform(..) | Gradient(..) | Color32(..) | Pattern(..) | Html(..) => {}
+        }
+    }
+
+    fn push_text(&mut self, module: &Module, ts: Transform, t: &TextItem) {
+        let text = t.content.content.as_ref();
+        let Some(font) = module.get_font(&t.shape.font) else {
+            return;
+        };
+        if text.trim().is_empty() && self.lines.is_empty() {
+            return;
+        }
+
+        let scale = (ts.sx * ts.sy - ts.kx * ts.ky).abs().sqrt();
+        let size = t.shape.size.0 * scale;
+        let cap_height = (font.cap_height * t.shape.size).0;
+        let width = t.width().0;
+
+        let [left, top, w, h] =
+            transform_rect(ts, [0., -cap_height, width, t.shape.size.0 - cap_height]);
+        let (right, bottom) = (left + w, top + h);
+
+        let line_break = std::mem::take(&mut self.line_break);
+        if let Some(line) = self.lines.last_mut() {
+            let center = (top + bottom) / 2.;
+            let same_line = line.top <= center && center <= line.bottom;
+            if !line_break && same_line && left >= line.right - size {
+                if left - line.right > size * 0.2
+                    && !line.text.ends_with(char::is_whitespace)
+                    && !text.starts_with(char::is_whitespace)
+                {
+                    line.text.push(' ');
+                }
+                line.text.push_str(text);
+                line.top = line.top.min(top);
+                line.bottom = line.bottom.max(bottom);
+                line.right = right;
+                line.size = line.size.max(size);
+                return;
+            }
+        }
+
+        self.lines.push(Line {
+            text: text.to_owned(),
+            top,
+            bottom,
+            right,
+            size,
+        });
+    }
+}
+
+/// Transforms the rectangle `[x0, y0, x1, y1]` and gets its bounding box in
+/// `[x, y, width, height]`, which covers the rotated and skewed rectangle.
+pub fn transform_rect(ts: Transform, [x0, y0, x1, y1]: [f32; 4]) -> [f32; 4] {
+    let corners = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)]
+        .map(|(x, y)| (ts.sx * x + ts.kx * y + ts.tx, ts.ky * x + ts.sy * y + ts.ty));
+
+    let (mut lx, mut ly) = corners[0];
+    let (mut hx, mut hy) = corners[0];
+    for (x, y) in corners {
+        lx = lx.min(x);
+        ly = ly.min(y);
+        hx = hx.max(x);
+        hy = hy.max(y);
+    }
+    [lx, ly, hx - lx, hy - ly]
+}
+
+/// Groups the lines into paragraphs.
+///
+/// A paragraph ends at a vertical gap larger than half of the line height, at
+/// a change of the font size, or when the next line goes upwards, e.g. at the
+/// start of a new column.
+fn paragraphs(lines: Vec<Line>) -> Vec<Vec<Line>> {
+    let mut paragraphs: Vec<Vec<Line>> = vec![];
+    for line in lines {
+        if line.text.trim().is_empty() {
+            continue;
+        }
+
+        let continues = |prev: &Line| {
+            line.top - prev.bottom <= (prev.bottom - prev.top) * 0.5
+                && line.top >= prev.top
+                && round_size(line.size) == round_size(prev.size)
+        };
+
+        let last = paragraphs.last_mut();
+        if let Some(paragraph) = last.filter(|p| p.last().is_some_and(continues)) {
+            paragraph.push(line);
+        } else {
+            paragraphs.push(vec![line]);
+        }
+    }
+
+    paragraphs
+}
+
+/// Rounds the font size to half a point, so that the sizes can be compared.
+pub fn round_size(size: f32) -> i32 {
+    (size * 2.).round() as i32
+}
+
+/// Infers the rounded font sizes of headings from the font sizes of text runs
+/// and their numbers of characters, see [`round_size`].
+///
+/// The sizes are in the descending order of the heading levels. Sizes notably
+/// larger than the body size, which is the most used size, are headings.
+pub fn heading_sizes(runs: impl IntoIterator<Item = (f32, usize)>) -> Vec<i32> {
+    let mut usage = std::collections::BTreeMap::<i32, usize>::new();
+    for (size, count) in runs {
+        *usage.entry(round_size(size)).or_default() += count;
+    }
+
+    let Some(body) = usage
+        .iter()
+        .max_by_key(|(size, count)| (**count, -**size))
+        .map(|(size, _)| *size)
+    else {
+        return vec![];
+    };
+
+    let threshold = (body as f32 * 1.15).ceil() as i32;
+    usage
+        .keys()
+        .rev()
+        .copied()
+        .filter(|size| *size >= threshold)
+        .take(6)
+        .collect()
+}
+
+/// Escapes the characters interpreted by Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    // Escapes the markers of block elements at the start of the line, e.g. `#`
+    // of headings and `1.` of ordered lists.
+    let digits = escaped.len()
+        - escaped
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    if escaped.starts_with(['#', '-', '+', '=']) {
+        escaped.insert(0, '\\');
+    } else if digits > 0 && escaped[digits..].starts_with(['.', ')']) {
+        escaped.insert(digits, '\\');
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str, top: f32, size: f32) -> Line {
+        Line {
+            text: text.to_owned(),
+            top,
+            bottom: top + size,
+            right: 0.,
+            size,
+        }
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let lines = vec![
+            line("Title", 0., 20.),
+            line("first line", 30., 10.),
+            line("second line", 42., 10.),
+            line("new paragraph", 60., 10.),
+        ];
+        let paragraphs = paragraphs(lines);
+        let texts = paragraphs
+            .iter()
+            .map(|p| p.iter().map(|l| l.text.as_str()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [
+                vec!["Title"],
+                vec!["first line", "second line"],
+                vec!["new paragraph"]
+            ]
+        );
+        let lines = paragraphs.iter().flatten();
+        let runs = lines.map(|line| (line.size, line.text.chars().count()));
+        assert_eq!(heading_sizes(runs), [40]);
+    }
+
+    #[test]
+    fn test_escape_markdown() {
+        assert_eq!(escape_markdown("a *b* [c]"), r"a \*b\* \[c\]");
+        assert_eq!(escape_markdown("# not heading"), r"\# not heading");
+        assert_eq!(escape_markdown("1. not list"), r"1\. not list");
+        assert_eq!(escape_markdown("2024 was"), "2024 was");
+    }
+}
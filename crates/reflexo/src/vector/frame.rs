@@ -0,0 +1,273 @@
+//! Framing of the incremental vector streams.
+//!
+//! Each delta packed by the server is prefixed with a fixed-size
+//! [`DeltaHeader`], so that a client can reject a delta which is packed by an
+//! incompatible server, or which is not based on the client's current state.
+
+use core::fmt;
+
+/// The magic of a [`DeltaHeader`].
+pub const DELTA_MAGIC: [u8; 4] = *b"tsvd";
+
+/// The current version of the delta protocol.
+///
+/// It must be increased whenever the layout of the delta changes.
+pub const DELTA_PROTOCOL_VERSION: u16 = 2;
+
+/// The event names that the server may prefix to a framed delta.
+const EVENT_TAGS: [&[u8]; 2] = [b"diff-v1,", b"new,"];
+
+/// The size of an encoded [`DeltaHeader`] in bytes. It is a multiple of the
+/// alignment of archived modules, so that the following module is kept
+/// aligned.
+pub const DELTA_HEADER_SIZE: usize = 32;
+
+/// Feature flags of a delta.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeltaFeatures(pub u32);
+
+impl DeltaFeatures {
+    /// The delta carries source mapping of the elements.
+    pub const SOURCE_MAPPING: Self = Self(1 << 0);
+    /// The glyphs in the delta are compressed.
+    pub const GLYPH_COMPRESSION: Self = Self(1 << 1);
+
+    /// The features that can be understood by current client.
+    pub const SUPPORTED: Self = Self::SOURCE_MAPPING;
+
+    /// Creates empty features.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Whether all the given features are set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Adds or removes the given features.
+    pub fn set(&mut self, other: Self, enabled: bool) {
+        if enabled {
+            self.0 |= other.0;
+        } else {
+            self.0 &= !other.0;
+        }
+    }
+
+    /// Gets the features not in the given features.
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+/// The kind of a delta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeltaKind {
+    /// The delta contains the entire document, which can be merged regardless
+    /// of the client's state.
+    Full = 0,
+    /// The delta contains the changes since the base revision.
+    Diff = 1,
+}
+
+/// The header of a delta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeltaHeader {
+    /// The version of the delta protocol.
+    pub version: u16,
+    /// The kind of the delta.
+    pub kind: DeltaKind,
+    /// The features used by the delta.
+    pub features: DeltaFeatures,
+    /// The revision which the delta is based on.
+    pub base_revision: u64,
+    /// The revision of the document after merging the delta.
+    pub revision: u64,
+}
+
+impl DeltaHeader {
+    /// Creates a header in the current protocol version.
+    pub fn new(
+        kind: DeltaKind,
+        features: DeltaFeatures,
+        base_revision: u64,
+        revision: u64,
+    ) -> Self {
+        Self {
+            version: DELTA_PROTOCOL_VERSION,
+            kind,
+            features,
+            base_revision,
+            revision,
+        }
+    }
+
+    /// Encodes the header in little endian.
+    pub fn encode(&self) -> [u8; DELTA_HEADER_SIZE] {
+        let mut buf = [0u8; DELTA_HEADER_SIZE];
+        buf[0..4].copy_from_slice(&DELTA_MAGIC);
+        buf[4..6].copy_from_slice(&self.version.to_le_bytes());
+        buf[6] = self.kind as u8;
+        buf[8..12].copy_from_slice(&self.features.0.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.base_revision.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.revision.to_le_bytes());
+        buf
+    }
+
+    /// Decodes a framed delta into the header and the body. The event name
+    /// prefixed by the server, e.g. `diff-v1,`, is skipped if present.
+    pub fn decode(frame: &[u8]) -> Result<(Self, &[u8]), DeltaError> {
+        let frame = EVENT_TAGS
+            .iter()
+            .find_map(|tag| frame.strip_prefix(*tag))
+            .unwrap_or(frame);
+        if frame.len() < DELTA_HEADER_SIZE || frame[0..4] != DELTA_MAGIC {
+            return Err(DeltaError::MissingHeader);
+        }
+
+        let u16_at = |off: usize| u16::from_le_bytes([frame[off], frame[off + 1]]);
+        let u32_at = |off: usize| u32::from_le_bytes(frame[off..off + 4].try_into().unwrap());
+        let u64_at = |off: usize| u64::from_le_bytes(frame[off..off + 8].try_into().unwrap());
+
+        // Checks the version before interpreting the rest of the header.
+        let version = u16_at(4);
+        if version != DELTA_PROTOCOL_VERSION {
+            return Err(DeltaError::VersionMismatch {
+                expected: DELTA_PROTOCOL_VERSION,
+                actual: version,
+            });
+        }
+
+        let kind = match frame[6] {
+            0 => DeltaKind::Full,
+            1 => DeltaKind::Diff,
+            kind => return Err(DeltaError::UnknownKind(kind)),
+        };
+
+        let header = Self {
+            version,
+            kind,
+            features: DeltaFeatures(u32_at(8)),
+            base_revision: u64_at(16),
+            revision: u64_at(24),
+        };
+
+        Ok((header, &frame[DELTA_HEADER_SIZE..]))
+    }
+
+    /// Checks whether the delta can be merged by a client at the given
+    /// revision.
+    pub fn check(&self, client_revision: u64) -> Result<(), DeltaError> {
+        let unsupported = self.features.difference(DeltaFeatures::SUPPORTED);
+        if unsupported != DeltaFeatures::empty() {
+            return Err(DeltaError::UnsupportedFeatures(unsupported));
+        }
+
+        if self.kind == DeltaKind::Diff && self.base_revision != client_revision {
+            return Err(DeltaError::OutOfOrder {
+                client_revision,
+                base_revision: self.base_revision,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// An error when merging a delta.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeltaError {
+    /// The delta is not prefixed with a header, which is likely packed by an
+    /// older server.
+    MissingHeader,
+    /// The delta is packed in another version of the protocol.
+    VersionMismatch { expected: u16, actual: u16 },
+    /// The kind of the delta is unknown.
+    UnknownKind(u8),
+    /// The delta uses features which cannot be understood by the client.
+    UnsupportedFeatures(DeltaFeatures),
+    /// The delta is not based on the client's current revision.
+    OutOfOrder {
+        client_revision: u64,
+        base_revision: u64,
+    },
+    /// The body of the delta is corrupted.
+    Corrupted(String),
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "missing delta header"),
+            Self::VersionMismatch { expected, actual } => write!(
+                f,
+                "delta protocol version mismatch: expected {expected}, found {actual}"
+            ),
+            Self::UnknownKind(kind) => write!(f, "unknown delta kind: {kind}"),
+            Self::UnsupportedFeatures(features) => {
+                write!(f, "unsupported delta features: {:#x}", features.0)
+            }
+            Self::OutOfOrder {
+                client_revision,
+                base_revision,
+            } => write!(
+                f,
+                "out-of-order delta: client is at revision {client_revision}, but the delta is based on revision {base_revision}"
+            ),
+            Self::Corrupted(err) => write!(f, "corrupted delta: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_roundtrip() {
+        let header = DeltaHeader::new(DeltaKind::Diff, DeltaFeatures::SOURCE_MAPPING, 3, 4);
+        let mut frame = b"diff-v1,".to_vec();
+        frame.extend_from_slice(&header.encode());
+        frame.extend_from_slice(b"body");
+
+        let (decoded, body) = DeltaHeader::decode(&frame).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(body, b"body");
+        assert_eq!(decoded.check(3), Ok(()));
+        assert_eq!(
+            decoded.check(2),
+            Err(DeltaError::OutOfOrder {
+                client_revision: 2,
+                base_revision: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_header_mismatch() {
+        assert_eq!(
+            DeltaHeader::decode(b"diff-v1,"),
+            Err(DeltaError::MissingHeader)
+        );
+
+        let mut frame = DeltaHeader::new(DeltaKind::Full, DeltaFeatures::empty(), 0, 1).encode();
+        frame[4..6].copy_from_slice(&1u16.to_le_bytes());
+        assert_eq!(
+            DeltaHeader::decode(&frame),
+            Err(DeltaError::VersionMismatch {
+                expected: DELTA_PROTOCOL_VERSION,
+                actual: 1
+            })
+        );
+
+        let header = DeltaHeader::new(DeltaKind::Full, DeltaFeatures::GLYPH_COMPRESSION, 0, 1);
+        assert_eq!(
+            header.check(0),
+            Err(DeltaError::UnsupportedFeatures(
+                DeltaFeatures::GLYPH_COMPRESSION
+            ))
+        );
+    }
+}
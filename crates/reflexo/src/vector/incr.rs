@@ -1,7 +1,9 @@
+use super::frame::{DeltaError, DeltaHeader, DeltaKind};
 use super::ir::{
     FlatGlyphItem, FlatModule, GlyphRef, LayoutRegionNode, LayoutSourceMapping, Module,
     ModuleMetadata, MultiVecDocument, Page, SourceMappingNode,
 };
+use super::stream::BytesModuleStream;
 use crate::{error::prelude::*, TakeAs};
 
 /// maintains the data of the incremental rendering at client side
@@ -18,11 +20,53 @@ pub struct IncrDocClient {
     pub source_mapping_data: Vec<SourceMappingNode>,
     /// Optional page source mapping references.
     pub page_source_mapping: LayoutSourceMapping,
+
+    /// The revision of the document merged from server.
+    revision: u64,
 }
 
 impl IncrDocClient {
-    /// Merge the delta from server.
-    pub fn merge_delta(&mut self, delta: FlatModule) {
+    /// Gets the revision of the document merged from server.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Merge a framed delta from server.
+    ///
+    /// The delta is rejected without touching the client's state if it is
+    /// packed in another protocol version, uses unsupported features, or is
+    /// not based on the client's current revision.
+    pub fn merge_delta(&mut self, frame: &[u8]) -> Result<DeltaHeader, DeltaError> {
+        let (header, body) = DeltaHeader::decode(frame)?;
+        header.check(self.revision)?;
+
+        let delta = checkout_module(body)?;
+
+        // A full delta replaces the entire document.
+        if header.kind == DeltaKind::Full {
+            *self = Self::default();
+        }
+
+        self.merge_module(delta);
+        self.revision = header.revision;
+        Ok(header)
+    }
+
+    /// Reset the client by a vector artifact, which is either a framed delta
+    /// or an unframed module exported by the vector exporter.
+    pub fn reset_artifact(&mut self, data: &[u8]) -> Result<(), DeltaError> {
+        *self = Self::default();
+        match DeltaHeader::decode(data) {
+            Err(DeltaError::MissingHeader) => {
+                self.merge_module(checkout_module(data)?);
+                Ok(())
+            }
+            _ => self.merge_delta(data).map(|_| ()),
+        }
+    }
+
+    /// Merge an unframed module from server.
+    pub fn merge_module(&mut self, delta: FlatModule) {
         self.doc.merge_delta(&delta);
         for metadata in delta.metadata {
             match metadata {
@@ -62,6 +106,17 @@ impl IncrDocClient {
     }
 }
 
+fn checkout_module(data: &[u8]) -> Result<FlatModule, DeltaError> {
+    let module = BytesModuleStream::from_slice(data)
+        .try_checkout_owned()
+        .map_err(DeltaError::Corrupted)?;
+    if module.magic != FlatModule::default().magic {
+        return Err(DeltaError::Corrupted("bad module magic".to_owned()));
+    }
+
+    Ok(module)
+}
+
 fn access_slice<'a, T>(v: &'a [T], idx: usize, kind: &'static str, pos: usize) -> Result<&'a T> {
     v.get(idx).ok_or_else(
         || error_once!("out of bound access", pos: pos, kind: kind, idx: idx, actual: v.len()),
@@ -66,4 +66,13 @@ impl<'a> BytesModuleStream<'a> {
         let mut dmap = SharedDeserializeMap::default();
         v.deserialize(&mut dmap).unwrap()
     }
+
+    /// Checks and deserializes the module, returning an error instead of
+    /// panicking if the data is corrupted.
+    pub fn try_checkout_owned(&self) -> Result<FlatModule, String> {
+        let v = rkyv::check_archived_root::<FlatModule>(self.data.as_ref())
+            .map_err(|err| format!("{err:?}"))?;
+        let mut dmap = SharedDeserializeMap::default();
+        v.deserialize(&mut dmap).map_err(|err| format!("{err:?}"))
+    }
 }
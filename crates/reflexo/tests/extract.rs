@@ -0,0 +1,48 @@
+//! Tests the text extraction on documents lowered by the real pass.
+
+use reflexo::vector::extract::{extract_text, TextFormat};
+use typst_ts_test_common::compile::lower_paged;
+
+fn extract(source: &str, format: TextFormat) -> String {
+    let (module, pages) = lower_paged(source);
+    extract_text(&module, &pages, format)
+}
+
+#[test]
+fn test_extract_merges_items_into_lines() {
+    // The bold text is a separate text item on the same line.
+    let text = extract("Hello *bold* world", TextFormat::Plain);
+    assert_eq!(text, "Hello bold world");
+}
+
+#[test]
+fn test_extract_wrapped_lines() {
+    let words = "the quick brown fox jumps over the lazy dog";
+    let source = format!("#set page(width: 100pt, height: auto)\n{words}");
+    let text = extract(&source, TextFormat::Plain);
+
+    // The wrapped lines belong to the same paragraph.
+    assert!(text.lines().count() > 1, "{text:?}");
+    assert!(!text.contains("\n\n"), "{text:?}");
+    let extracted = text.split_whitespace().collect::<Vec<_>>();
+    assert_eq!(extracted, words.split(' ').collect::<Vec<_>>());
+}
+
+#[test]
+fn test_extract_reading_order() {
+    let source = "#set page(width: 300pt, height: 200pt, columns: 2)\n\
+        First column.\n\n#colbreak()\n\nSecond column.";
+    let text = extract(source, TextFormat::Plain);
+    // The next column starts a new paragraph although it goes upwards.
+    assert_eq!(text, "First column.\n\nSecond column.");
+}
+
+#[test]
+fn test_extract_markdown_headings() {
+    let source = "= Title\n\nSome body text.\n\n== Section\n\nMore body text.";
+    let text = extract(source, TextFormat::Markdown);
+    assert_eq!(
+        text,
+        "# Title\n\nSome body text.\n\n## Section\n\nMore body text."
+    );
+}
@@ -80,6 +80,17 @@ typst-ts-cli compile ... --format vector
 typst-ts-cli compile ... --format svg --format svg_html
 ```
 
+=== `--pages` option
+
+Exports only the selected pages. It applies to the `pdf`, `png`, `svg`, `svg_html`, `vector` and `text` formats.
+
+```bash
+# export page 1 and pages 3 to 5
+typst-ts-cli compile ... --pages 1,3-5
+# export page 8 and any pages after it
+typst-ts-cli compile ... --pages 8-
+```
+
 === `--dynamic-layout` option
 
 Please setup the #link("https://github.com/Myriad-Dreamin/typst.ts/tree/main/contrib/templates/variables")[variables] package before compilation.
@@ -98,6 +109,14 @@ Output to directory, default in the same directory as the entry file.
 typst-ts-cli compile ... -o dist
 ```
 
+=== `--diagnostic-format` option, default: `human`
+
+The format to emit diagnostics in, possible values: `human`, `short` and `json`. The `json` format prints one JSON object per line to stderr, containing the `severity`, `message`, `path`, zero-based `range`, `hints` and `trace` of each diagnostic.
+
+```bash
+typst-ts-cli compile ... --diagnostic-format json
+```
+
 === `--trace` option
 
 Comma separated options to trace execution of typst compiler when compiling documents:
@@ -137,6 +156,16 @@ typst-ts-cli compile \
   --format svg_html
 ```
 
+=== Example: compile pages of a document into PNG images
+
+Each selected page is written to a separate file, e.g. `main.1.png`, `main.3.png`, etc.
+
+```bash
+typst-ts-cli compile \
+  -e "fuzzers/corpora/math/main.typ"
+  --format png --ppi 288 --pages 1,3-5
+```
+
 === Example: compile a document into the #term.vector-format
 
 ```bash
@@ -153,6 +182,69 @@ typst-ts-cli compile \
   --dynamic-layout
 ```
 
+== The build command
+
+The `build` command compiles all entries listed in a project manifest. The manifest is read from `typst-ts.toml` by default, and a manifest ending with `.json` is parsed as JSON. Relative paths in the manifest are resolved against the directory containing the manifest.
+
+```toml
+# typst-ts.toml
+workspace = "."
+fontPaths = ["assets/fonts"]
+# default formats of entries
+formats = ["pdf"]
+# entries exported with the default formats
+files = ["main.typ"]
+
+[[entries]]
+entry = "slides.typ"
+formats = ["svg", "png"]
+pages = "1,3-5"
+ppi = 300
+output = "dist/slides"
+inputs = { theme = "dark" }
+```
+
+```bash
+typst-ts-cli build --manifest typst-ts.toml
+```
+
+The command exits with failure if any of the entries fails to compile or export.
+
+== The query command
+
+The `query` command compiles an entry file and retrieves the elements matching a selector:
+
+```bash
+typst-ts-cli query -e "fuzzers/corpora/math/main.typ" --selector "heading"
+```
+
+With `--with-location`, each element is emitted as an object with its `value` and `location`. The location contains the one-based `page` number, the `position` on the page in points, and the `path` and zero-based `range` in the source file. The `--output-format yaml` option serializes the result in YAML instead of JSON.
+
+```bash
+typst-ts-cli query -e main.typ --selector "heading" --with-location --output-format yaml
+```
+
+== The query-repl command
+
+The `query-repl` command compiles an entry file once and then reads selectors from a prompt, evaluating each of them against the latest document. The entry file is recompiled when the files it depends on change, so queries never pay for a full compilation.
+
+```bash
+typst-ts-cli query-repl -e "fuzzers/corpora/math/main.typ"
+>> heading.where(level: 1)
+>> heading --field body
+>> <my-label> --one
+```
+
+The `--field <name>` modifier extracts just one field from all retrieved elements, the `--one` modifier expects exactly one element, and the `--with-location` modifier attaches locations as the `query` command does. Type `:help` for help and `:quit` or press `Ctrl-D` to exit.
+
+== The font measure command
+
+The `font measure` command writes a font profile in JSON format, containing the info, variant, coverage, file path and hash of each discovered font. The web compiler can register the fonts by `addFontProfile` without parsing the font data, which is loaded lazily on first use.
+
+```bash
+typst-ts-cli font measure --font-path assets/fonts --no-system-fonts --output fonts.json
+```
+
 // == Package commands
 
 // === Example: list packages in `@preview` namespace
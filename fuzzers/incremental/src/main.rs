@@ -1,10 +1,7 @@
 use std::path::Path;
 
 use reflexo_typst::config::{entry::EntryOpts, CompileOpts};
-use reflexo_typst::vector::{
-    ir::{Abs, Point, Rect},
-    stream::BytesModuleStream,
-};
+use reflexo_typst::vector::ir::{Abs, Point, Rect};
 use reflexo_typst::{TypstDocument, TypstSystemUniverse};
 use reflexo_typst2vec::incr::{IncrDocClient, IncrDocServer};
 use reflexo_vec2svg::IncrSvgDocClient;
@@ -78,8 +75,8 @@ pub fn test_compiler(workspace_dir: &Path, entry_file_path: &Path) {
             .unwrap();
 
         let delta = incr_server.pack_delta(&TypstDocument::Paged(doc));
-        let delta = BytesModuleStream::from_slice(&delta).checkout_owned();
-        incr_client.merge_delta(delta);
+        let delta = delta.strip_prefix(b"diff-v1,").unwrap();
+        incr_client.merge_delta(delta).unwrap();
         incr_client.set_layout(incr_client.doc.layouts[0].unwrap_single());
         let _ = incr_svg_client.render_in_window(&mut incr_client, window);
 
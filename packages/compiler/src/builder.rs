@@ -4,6 +4,7 @@ use js_sys::Uint8Array;
 use reflexo_typst::font::cache::FontInfoCache;
 use reflexo_typst::font::memory::MemoryFontSearcher;
 use reflexo_typst::font::{BufferFontLoader, FontResolverImpl, FontSlot};
+use reflexo_typst::font_profile::{font_data_hash, FontProfile};
 use reflexo_typst::package::registry::{JsRegistry, ProxyContext};
 use reflexo_typst::vfs::browser::ProxyAccessModel;
 use reflexo_typst::{error::prelude::*, Bytes as TypstBytes};
@@ -100,6 +101,15 @@ impl TypstCompilerBuilder {
         Ok(())
     }
 
+    pub async fn add_font_profile(
+        &mut self,
+        profile: JsValue,
+        blob: js_sys::Function,
+    ) -> Result<(), JsValue> {
+        self.fb.add_font_profile(profile, blob)?;
+        Ok(())
+    }
+
     pub async fn build(self) -> Result<TypstCompiler, JsValue> {
         let access_model = self
             .access_model
@@ -177,6 +187,32 @@ impl TypstFontResolverBuilder {
         Ok(())
     }
 
+    /// Adds fonts measured by `typst-ts-cli font measure` to the searcher.
+    ///
+    /// The font data is loaded lazily by calling `blob` with an object
+    /// containing the `path`, `hash` and `index` of the font as `this`.
+    pub fn add_font_profile(
+        &mut self,
+        profile: JsValue,
+        blob: js_sys::Function,
+    ) -> Result<(), JsValue> {
+        let profile: FontProfile = serde_wasm_bindgen::from_value(profile)?;
+
+        for item in profile.items {
+            let context = js_sys::Object::new();
+            let path = item.path.map_or(JsValue::UNDEFINED, JsValue::from);
+            js_sys::Reflect::set(&context, &"path".into(), &path)?;
+            js_sys::Reflect::set(&context, &"hash".into(), &item.hash.into())?;
+            js_sys::Reflect::set(&context, &"index".into(), &item.index.into())?;
+
+            let loader =
+                JsFontLoader::new(item.info.clone(), context.into(), blob.clone(), item.index)
+                    .with_hash(item.hash);
+            self.base.fonts.push((item.info, FontSlot::new(loader)))
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "fonts")]
     fn add_embedded(&mut self) {
         for font_data in typst_assets::fonts() {
@@ -212,6 +248,9 @@ pub struct JsFontLoader {
     pub blob: js_sys::Function,
     /// The index in a font file.
     pub index: u32,
+    /// The hash of the font file data measured by a font profile, which is
+    /// checked when the font is loaded.
+    pub hash: Option<String>,
 }
 
 impl JsFontLoader {
@@ -222,8 +261,15 @@ impl JsFontLoader {
             context,
             blob,
             index,
+            hash: None,
         }
     }
+
+    /// Checks the loaded font data against the hash of a font profile.
+    pub fn with_hash(mut self, hash: String) -> Self {
+        self.hash = Some(hash);
+        self
+    }
 }
 
 impl reflexo_typst::font::FontLoader for JsFontLoader {
@@ -239,6 +285,16 @@ impl reflexo_typst::font::FontLoader for JsFontLoader {
             ));
         };
 
+        if let Some(hash) = &self.hash {
+            let actual = font_data_hash(&blob);
+            if *hash != actual {
+                wasm_bindgen::throw_str(&format!(
+                    "Font Blob hash mismatched: expected {hash}, got {actual}, while loading font: {:?}",
+                    self.info
+                ));
+            }
+        }
+
         typst::text::Font::new(blob, self.index)
     }
 }
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use reflexo_typst::{TypstDocument, TypstPagedDocument};
-use reflexo_typst2vec::incr::IncrDocServer;
+use reflexo_typst2vec::incr::{GcPolicy, IncrDocServer};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -34,6 +34,28 @@ impl IncrServer {
         self.inner.set_should_attach_debug_info(attach);
     }
 
+    /// Sets the garbage collection policy. Items unused in the last `max_age`
+    /// revisions are collected, and at most `max_items` items or `max_bytes`
+    /// bytes of items are kept if specified. Text items are never collected if
+    /// `retain_text` is set, and glyphs and fonts are never collected if
+    /// `retain_glyphs` is set.
+    pub fn set_gc_policy(
+        &mut self,
+        max_age: u32,
+        max_items: Option<u32>,
+        max_bytes: Option<u32>,
+        retain_text: bool,
+        retain_glyphs: bool,
+    ) {
+        self.inner.set_gc_policy(GcPolicy {
+            max_age: max_age as u64,
+            max_items: max_items.map(|n| n as usize),
+            max_bytes: max_bytes.map(|n| n as usize),
+            retain_text,
+            retain_glyphs,
+        });
+    }
+
     pub fn current(&mut self) -> Option<Vec<u8>> {
         self.inner.pack_current()
     }
@@ -0,0 +1,35 @@
+use reflexo_typst::error::prelude::*;
+use reflexo_vec2sema::{a11y_tree, A11yNode};
+use wasm_bindgen::prelude::*;
+
+use crate::{RenderSession, TypstRenderer};
+
+#[wasm_bindgen]
+impl TypstRenderer {
+    /// Renders the accessibility tree of the document.
+    pub fn render_a11y_tree(&mut self, session: &RenderSession) -> Result<JsValue> {
+        let tree = self.render_a11y_tree_internal(session)?;
+        serde_wasm_bindgen::to_value(&tree)
+            .map_err(map_into_err::<JsValue, _>("Renderer.EncodeA11yTree"))
+    }
+
+    /// Renders the accessibility tree of the document into an ARIA-annotated
+    /// HTML layer.
+    pub fn render_a11y_html(&mut self, session: &RenderSession) -> Result<String> {
+        Ok(self.render_a11y_tree_internal(session)?.to_aria_html())
+    }
+}
+
+impl TypstRenderer {
+    pub fn render_a11y_tree_internal(&self, session: &RenderSession) -> Result<A11yNode> {
+        let client = session.client.lock().unwrap();
+        let Some(layout) = &client.layout else {
+            return Err(error_once!("Renderer.MissingLayout"));
+        };
+        let Some(view) = layout.pages(client.module()) else {
+            return Err(error_once!("Renderer.UnsupportedLayout"));
+        };
+
+        Ok(a11y_tree(view.module(), view.pages()))
+    }
+}
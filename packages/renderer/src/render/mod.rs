@@ -1,12 +1,18 @@
+pub mod a11y;
+
 #[cfg(feature = "render_canvas")]
 pub mod canvas;
 
 #[cfg(feature = "render_pdf")]
 pub mod pdf;
 
+pub mod search;
+
 #[cfg(feature = "render_svg")]
 pub mod svg;
 
+pub mod text;
+
 #[cfg(feature = "render_dom")]
 pub mod dom;
 
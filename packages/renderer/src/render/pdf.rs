@@ -19,9 +19,15 @@ impl TypstRenderer {
 }
 
 impl TypstRenderer {
-    pub fn render_to_pdf_internal(&self, _session: &RenderSession) -> Result<Vec<u8>> {
-        // contribution 510KB
-        // Ok(typst::export::pdf(&session.doc))
-        Err(error_once!("Renderer.PdfFeatureNotEnabled"))
+    pub fn render_to_pdf_internal(&self, session: &RenderSession) -> Result<Vec<u8>> {
+        let client = session.client.lock().unwrap();
+        let Some(layout) = &client.layout else {
+            return Err(error_once!("Renderer.MissingLayout"));
+        };
+        let Some(view) = layout.pages(client.module()) else {
+            return Err(error_once!("Renderer.UnsupportedLayout"));
+        };
+
+        reflexo_vec2pdf::render_pdf(view.module(), view.pages())
     }
 }
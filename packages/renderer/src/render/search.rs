@@ -0,0 +1,47 @@
+use reflexo_typst::error::prelude::*;
+use reflexo_vec2sema::{search_text, SearchMatch, SearchOptions};
+use wasm_bindgen::prelude::*;
+
+use crate::{RenderSession, TypstRenderer};
+
+#[wasm_bindgen]
+impl TypstRenderer {
+    /// Searches the text of the document, and returns the matches with the
+    /// highlight rectangles in page coordinates.
+    pub fn search_text(
+        &mut self,
+        session: &RenderSession,
+        query: &str,
+        case_sensitive: bool,
+        regex: bool,
+    ) -> Result<JsValue> {
+        let options = SearchOptions {
+            case_sensitive,
+            regex,
+        };
+        let matches = self.search_text_internal(session, query, &options)?;
+        serde_wasm_bindgen::to_value(&matches)
+            .map_err(map_into_err::<JsValue, _>("Renderer.EncodeSearchMatches"))
+    }
+}
+
+impl TypstRenderer {
+    pub fn search_text_internal(
+        &self,
+        session: &RenderSession,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchMatch>> {
+        let client = session.client.lock().unwrap();
+        let Some(layout) = &client.layout else {
+            return Err(error_once!("Renderer.MissingLayout"));
+        };
+        let Some(view) = layout.pages(client.module()) else {
+            return Err(error_once!("Renderer.UnsupportedLayout"));
+        };
+
+        search_text(view.module(), view.pages(), query, options).map_err(
+            |err| error_once!("Renderer.InvalidSearchPattern", query: query, err: err.to_string()),
+        )
+    }
+}
@@ -0,0 +1,36 @@
+use reflexo_typst::error::prelude::*;
+use reflexo_typst::vector::extract::{extract_text, TextFormat};
+use wasm_bindgen::prelude::*;
+
+use crate::{RenderSession, TypstRenderer};
+
+#[wasm_bindgen]
+impl TypstRenderer {
+    /// Extracts the text of the document, in Markdown if `markdown` is set.
+    pub fn render_text(&mut self, session: &RenderSession, markdown: bool) -> Result<String> {
+        let format = if markdown {
+            TextFormat::Markdown
+        } else {
+            TextFormat::Plain
+        };
+        self.render_text_internal(session, format)
+    }
+}
+
+impl TypstRenderer {
+    pub fn render_text_internal(
+        &self,
+        session: &RenderSession,
+        format: TextFormat,
+    ) -> Result<String> {
+        let client = session.client.lock().unwrap();
+        let Some(layout) = &client.layout else {
+            return Err(error_once!("Renderer.MissingLayout"));
+        };
+        let Some(view) = layout.pages(client.module()) else {
+            return Err(error_once!("Renderer.UnsupportedLayout"));
+        };
+
+        Ok(extract_text(view.module(), view.pages(), format))
+    }
+}
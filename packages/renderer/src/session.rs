@@ -243,9 +243,8 @@ impl RenderSession {
         }
     }
 
-    pub(crate) fn reset_current(&mut self, delta: &[u8]) -> Result<()> {
+    pub(crate) fn reset_current(&mut self, artifact: &[u8]) -> Result<()> {
         let mut client = self.client.lock().unwrap();
-        *client = IncrDocClient::default();
         if cfg!(feature = "render_canvas") {
             let mut canvas_kern = self.canvas_kern.lock().unwrap();
             canvas_kern.reset();
@@ -254,7 +253,12 @@ impl RenderSession {
             let mut svg_kern = self.svg_kern.lock().unwrap();
             svg_kern.reset();
         }
-        Self::merge_delta_inner(&mut self.pages_info, &mut client, delta)
+
+        client
+            .reset_artifact(artifact)
+            .map_err(|err| error_once!("Renderer.ResetArtifactFailed", err: err.to_string()))?;
+        Self::update_pages_inner(&mut self.pages_info, &mut client);
+        Ok(())
     }
 
     pub(crate) fn merge_delta(&mut self, delta: &[u8]) -> Result<()> {
@@ -279,24 +283,24 @@ impl RenderSession {
         client: &mut IncrDocClient,
         delta: &[u8],
     ) -> Result<()> {
-        use reflexo_typst2vec::stream::BytesModuleStream;
-
-        let delta = BytesModuleStream::from_slice(delta).checkout_owned();
-        let _delta_ref = &delta;
-
-        #[cfg(feature = "debug_delta_update")]
-        use reflexo_typst2vec::ir::ModuleStream;
+        let _header = client
+            .merge_delta(delta)
+            .map_err(|err| error_once!("Renderer.MergeDeltaFailed", err: err.to_string()))?;
 
         #[cfg(feature = "debug_delta_update")]
         crate::utils::console_log!(
-            "module counts: g:{:?},i:{:?},l:{:?},gc:{:?}",
-            _delta_ref.glyphs().items.len(),
-            _delta_ref.items().0.len(),
-            _delta_ref.layouts().len(),
-            _delta_ref.gc_items().map(|s| s.len()),
+            "delta: kind:{:?},rev:{}->{},features:{:?}",
+            _header.kind,
+            _header.base_revision,
+            _header.revision,
+            _header.features,
         );
 
-        client.merge_delta(delta);
+        Self::update_pages_inner(pages_info, client);
+        Ok(())
+    }
+
+    fn update_pages_inner(pages_info: &mut PagesInfo, client: &mut IncrDocClient) {
         // checkout the current layout
         // todo: multiple layout
         let layouts = &client.doc.layouts[0];
@@ -327,6 +331,5 @@ impl RenderSession {
         };
 
         *pages_info = PagesInfo { pages };
-        Ok(())
     }
 }
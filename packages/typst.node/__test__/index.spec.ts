@@ -1,6 +1,6 @@
 import test from 'ava';
 
-import { NodeCompiler, PdfStandard } from '../index';
+import { NodeCompiler, PdfStandard, ProjectWatcher } from '../index';
 
 // Switch to the current directory for the tests interacting with FS
 process.chdir(__dirname);
@@ -231,3 +231,28 @@ test('it throws error when entry file does not exist', t => {
   const fileNotFound = diags!.find(d => d.message.includes('file not found'));
   t.truthy(fileNotFound);
 });
+
+test('it records the status of the watched projects', async t => {
+  const watcher = ProjectWatcher.create({
+    workspace: '.',
+  });
+
+  const compiled = new Promise<void>(resolve => watcher.add('inputs/post1.typ', () => resolve()));
+  t.is(watcher.status('inputs/post1.typ')?.status, 'pending');
+
+  watcher.watch();
+  await compiled;
+
+  const status = watcher.status('inputs/post1.typ');
+  t.is(status?.status, 'ok');
+  t.false(status?.compiling);
+  t.is(status?.errorCount, 0);
+  t.is(status?.exportErrorCount, 0);
+  t.true((status?.durationMs ?? -1) >= 0);
+  t.truthy(status?.dependencies.find(dep => dep.endsWith('inputs/post1.typ')));
+  t.deepEqual(watcher.list(), [status]);
+
+  // Releases the watch callback.
+  watcher.clear();
+  watcher.watch();
+});
@@ -335,14 +335,22 @@ impl NodeCompiler {
     }
 
     /// Simply compiles the document as a rich-contented SVG (for browsers).
-    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs")]
+    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs, opts?: RenderSvgOpts")]
     #[cfg(feature = "svg")]
-    pub fn svg(&mut self, compiled_or_by: MayCompileOpts) -> Result<String, NodeError> {
+    pub fn svg(
+        &mut self,
+        compiled_or_by: MayCompileOpts,
+        opts: Option<crate::RenderSvgOpts>,
+    ) -> Result<String, NodeError> {
         use reflexo_typst::ExportWebSvgTask;
         use reflexo_vec2svg::DefaultExportFeature;
 
         type Export = reflexo_typst::WebSvgExport<DefaultExportFeature>;
-        self.compile_as::<Export, _>(compiled_or_by, &ExportWebSvgTask::default())
+        let task = ExportWebSvgTask {
+            options: opts.map(|opts| opts.to_options()),
+            ..ExportWebSvgTask::default()
+        };
+        self.compile_as::<Export, _>(compiled_or_by, &task)
     }
 
     // todo: when feature is disabled, it results a compile error
@@ -1,9 +1,12 @@
 use std::sync::{Arc, Mutex};
 
 use reflexo_typst::hash::FxHashMap;
+use reflexo_typst::path::unix_slash;
 use reflexo_typst::system::SystemWorldComputeGraph;
+use reflexo_typst::typst::syntax::FileId;
 use reflexo_typst::typst_shim::syntax::VirtualPathExt;
 use reflexo_typst::vfs::notify::NotifyMessage;
+use reflexo_typst::vfs::{WorkspaceResolution, WorkspaceResolver};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
@@ -12,10 +15,11 @@ use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFun
 use napi_derive::napi;
 use reflexo_typst::error::WithContextUntyped;
 use reflexo_typst::{
-    error_once, watch_deps, ArcInto, Bytes, CompilationTask, CompileSnapshot, DocumentQuery,
-    EntryReader, EntryState, ExportComputation, ExportWebSvgModuleTask, FlagTask, ProjectInsId,
-    SystemCompilerFeat, TaskInputs, TypstDocument, TypstDocumentTrait, TypstPagedDocument,
-    TypstSystemUniverse, TypstSystemWorld, MEMORY_MAIN_ENTRY,
+    error_once, watch_deps, ArcInto, Bytes, CompilationTask, CompileReport, CompileSnapshot,
+    DiagnosticsTask, DocumentQuery, EntryReader, EntryState, ExportComputation,
+    ExportWebSvgModuleTask, FlagTask, ProjectInsId, SystemCompilerFeat, TaskInputs, TypstDocument,
+    TypstDocumentTrait, TypstPagedDocument, TypstSystemUniverse, TypstSystemWorld,
+    MEMORY_MAIN_ENTRY,
 };
 use tinymist_project::{
     CompileHandler, CompileServerOpts, CompileSignal, CompiledArtifact, Interrupt,
@@ -27,12 +31,172 @@ use crate::{error::*, NodeTypstDocument};
 use crate::{CompileDocArgs, QueryDocArgs};
 
 type WatchFunction = Arc<ThreadsafeFunction<NodeTypstProject, ErrorStrategy::Fatal>>;
+type ProjectStatusMap = Arc<Mutex<FxHashMap<EntryState, ProjectStatus>>>;
 
 /// Project watcher.
 #[napi]
 pub struct ProjectWatcher {
     entry: EntryState,
     tx: mpsc::UnboundedSender<Message>,
+    /// The status of the watched documents, which is shared with the
+    /// background worker.
+    projects: ProjectStatusMap,
+}
+
+/// The status of a watched document.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct NodeProjectStatus {
+    /// The path to the entry file.
+    pub entry: Option<String>,
+    /// The path to the workspace.
+    pub workspace: Option<String>,
+    /// The status of the last compilation, which is one of `pending`, `ok`,
+    /// `warning` and `error`. It is `pending` if the document has not been
+    /// compiled yet.
+    pub status: String,
+    /// Whether the document is being compiled.
+    pub compiling: bool,
+    /// The number of errors in the last compilation.
+    pub error_count: u32,
+    /// The number of failed exports of the last compiled document, which is
+    /// reset by a successful export.
+    pub export_error_count: u32,
+    /// The number of warnings in the last compilation.
+    pub warning_count: u32,
+    /// The duration of the last compilation, in milliseconds.
+    pub duration_ms: Option<f64>,
+    /// The files that the last compilation depended on.
+    pub dependencies: Vec<String>,
+}
+
+/// The status of a watched document, which is keyed by the entry resolved
+/// from the watch items.
+#[derive(Debug, Clone, Default)]
+struct ProjectStatus {
+    /// The report of the last compilation, or `None` if the document has not
+    /// been compiled yet.
+    report: Option<CompileReport>,
+    /// The [`CompileReport::Stage`] of the ongoing compilation.
+    stage: Option<CompileReport>,
+    /// The number of errors in the last compilation.
+    compile_errors: usize,
+    /// The number of failed exports since the last compilation or the last
+    /// successful export.
+    export_errors: usize,
+    /// The number of warnings in the last compilation.
+    warning_count: usize,
+    /// The files that the last compilation depended on.
+    dependencies: Vec<String>,
+}
+
+impl ProjectStatus {
+    /// Starts a compilation of the main file.
+    fn start(&mut self, main: FileId) {
+        let start = reflexo_typst::time::now();
+        self.stage = Some(CompileReport::Stage(main, "compiling", start));
+    }
+
+    /// Records the result of a compilation, whose duration is measured from
+    /// the start of the compilation.
+    fn record(&mut self, main: FileId, diag: Option<(usize, usize)>, deps: Vec<String>) {
+        let elapsed = match self.stage.take() {
+            Some(CompileReport::Stage(_, _, start)) => start.elapsed().unwrap_or_default(),
+            _ => Default::default(),
+        };
+
+        let (errors, warnings) = diag.unwrap_or((1, 0));
+        let report = if errors == 0 {
+            CompileReport::CompileSuccess(main, warnings, elapsed)
+        } else {
+            CompileReport::CompileError(main, errors, elapsed)
+        };
+        self.report = Some(report);
+        self.compile_errors = errors;
+        self.export_errors = 0;
+        self.warning_count = warnings;
+        self.dependencies = deps;
+    }
+
+    /// Records a failed export of the last compiled document.
+    fn record_export_error(&mut self) {
+        self.export_errors += 1;
+    }
+
+    /// Records a successful export of the last compiled document, which
+    /// clears the failed exports.
+    fn record_export_ok(&mut self) {
+        self.export_errors = 0;
+    }
+
+    /// Converts the status of the document at `entry` for node.
+    fn to_node(&self, entry: &EntryState) -> NodeProjectStatus {
+        let status = match &self.report {
+            None => "pending",
+            Some(_) if self.compile_errors > 0 || self.export_errors > 0 => "error",
+            Some(_) if self.warning_count > 0 => "warning",
+            Some(_) => "ok",
+        };
+
+        NodeProjectStatus {
+            entry: entry.main().map(file_path),
+            workspace: entry.workspace_root().map(|root| unix_slash(&root)),
+            status: status.to_owned(),
+            compiling: self.stage.is_some(),
+            error_count: self.compile_errors as u32,
+            export_error_count: self.export_errors as u32,
+            warning_count: self.warning_count as u32,
+            duration_ms: self
+                .report
+                .as_ref()
+                .and_then(CompileReport::duration)
+                .map(|dur| dur.as_secs_f64() * 1000.),
+            dependencies: self.dependencies.clone(),
+        }
+    }
+}
+
+/// A reference to the status of a watched project, through which the project
+/// records its compilations and exports.
+#[derive(Clone)]
+struct ProjectStatusRef {
+    projects: ProjectStatusMap,
+    entry: EntryState,
+}
+
+impl ProjectStatusRef {
+    /// Updates the status, which may be removed after the compilation.
+    fn update(&self, f: impl FnOnce(&mut ProjectStatus)) {
+        if let Some(status) = self.projects.lock().unwrap().get_mut(&self.entry) {
+            f(status);
+        }
+    }
+
+    fn start(&self) {
+        if let Some(main) = self.entry.main() {
+            self.update(|status| status.start(main));
+        }
+    }
+
+    fn record_export_error(&self) {
+        self.update(ProjectStatus::record_export_error);
+    }
+
+    fn record_export_ok(&self) {
+        self.update(ProjectStatus::record_export_ok);
+    }
+}
+
+/// Gets the path to a file, which is absolute if the file is in a workspace.
+fn file_path(id: FileId) -> String {
+    match WorkspaceResolver::resolve(id) {
+        Ok(WorkspaceResolution::Workspace(workspace)) => id
+            .vpath()
+            .realize(&workspace.path())
+            .map(|path| unix_slash(&path))
+            .unwrap_or_else(|_| id.vpath().get_with_slash().to_string()),
+        _ => id.vpath().get_with_slash().to_string(),
+    }
 }
 
 #[napi]
@@ -57,8 +221,10 @@ impl ProjectWatcher {
         let verse = create_universe(args).map_err(map_node_error)?;
         let entry = verse.entry_state();
         let (tx, rx) = mpsc::unbounded_channel();
+        let projects = ProjectStatusMap::default();
+        let worker_projects = projects.clone();
         std::thread::spawn(move || {
-            let worker = ProjectBackgroundWorker::new(verse, rx);
+            let worker = ProjectBackgroundWorker::new(verse, rx, worker_projects);
 
             tokio::runtime::Builder::new_current_thread()
                 .enable_all()
@@ -66,7 +232,11 @@ impl ProjectWatcher {
                 .unwrap()
                 .block_on(worker.run());
         });
-        Ok(ProjectWatcher { entry, tx })
+        Ok(ProjectWatcher {
+            entry,
+            tx,
+            projects,
+        })
     }
 
     /// Evict the **global** cache.
@@ -139,8 +309,15 @@ impl ProjectWatcher {
                 .context_ut("failed to create threadsafe function")
                 .map_err(map_node_error)?,
         );
+        let items = convert_items(items, &self.entry)?;
+        {
+            let mut projects = self.projects.lock().unwrap();
+            for item in &items {
+                projects.entry(item.clone()).or_default();
+            }
+        }
         self.tx
-            .send(Message::Add(convert_items(items, &self.entry)?, tsfn))
+            .send(Message::Add(items, tsfn))
             .map_err(|_| "send add message failed")
             .context_ut("failed to add")
             .map_err(map_node_error)?;
@@ -158,8 +335,16 @@ impl ProjectWatcher {
                 .context_ut("failed to create threadsafe function")
                 .map_err(map_node_error)?,
         );
+        let items = convert_items(items, &self.entry)?;
+        {
+            let mut projects = self.projects.lock().unwrap();
+            projects.retain(|entry, _| items.contains(entry));
+            for item in &items {
+                projects.entry(item.clone()).or_default();
+            }
+        }
         self.tx
-            .send(Message::Update(convert_items(items, &self.entry)?, tsfn))
+            .send(Message::Update(items, tsfn))
             .map_err(|_| "send update message failed")
             .context_ut("failed to update")
             .map_err(map_node_error)?;
@@ -169,8 +354,15 @@ impl ProjectWatcher {
     /// Removes multiple documents from the compiler.
     #[napi(ts_args_type = "items: types.ProjectWatchItems")]
     pub fn remove(&self, items: serde_json::Value) -> Result<(), NodeError> {
+        let items = convert_items(items, &self.entry)?;
+        {
+            let mut projects = self.projects.lock().unwrap();
+            for item in &items {
+                projects.remove(item);
+            }
+        }
         self.tx
-            .send(Message::Remove(convert_items(items, &self.entry)?))
+            .send(Message::Remove(items))
             .map_err(|_| "send remove message failed")
             .context_ut("failed to remove")
             .map_err(map_node_error)?;
@@ -180,6 +372,7 @@ impl ProjectWatcher {
     /// Clears all documents in the compiler.
     #[napi]
     pub fn clear(&self) -> Result<(), NodeError> {
+        self.projects.lock().unwrap().clear();
         self.tx
             .send(Message::Clear)
             .map_err(|_| "send clear message failed")
@@ -188,10 +381,33 @@ impl ProjectWatcher {
         Ok(())
     }
 
-    /// Gets the list of documents in the compiler.
+    /// Gets the status of the documents in the compiler, sorted by their entry
+    /// paths.
+    ///
+    /// == Example
+    ///
+    /// Finds the failing documents:
+    /// ```ts
+    /// const failing = compiler.list().filter(p => p.status === 'error');
+    /// ```
     #[napi]
-    pub fn list(&self) -> Result<Vec<String>, NodeError> {
-        Ok(vec![])
+    pub fn list(&self) -> Result<Vec<NodeProjectStatus>, NodeError> {
+        let projects = self.projects.lock().unwrap();
+        let mut list = projects
+            .iter()
+            .map(|(entry, status)| status.to_node(entry))
+            .collect::<Vec<_>>();
+        list.sort_by(|a, b| (&a.entry, &a.workspace).cmp(&(&b.entry, &b.workspace)));
+        Ok(list)
+    }
+
+    /// Gets the status of a document in the compiler, or `null` if the
+    /// document is not added.
+    #[napi(ts_args_type = "item: types.ProjectWatchItem")]
+    pub fn status(&self, item: serde_json::Value) -> Result<Option<NodeProjectStatus>, NodeError> {
+        let entry = resolve_item(item, &self.entry)?;
+        let projects = self.projects.lock().unwrap();
+        Ok(projects.get(&entry).map(|status| status.to_node(&entry)))
     }
 }
 
@@ -272,13 +488,18 @@ struct ProjectBackgroundWorker {
 }
 
 impl ProjectBackgroundWorker {
-    fn new(verse: TypstSystemUniverse, rx: mpsc::UnboundedReceiver<Message>) -> Self {
+    fn new(
+        verse: TypstSystemUniverse,
+        rx: mpsc::UnboundedReceiver<Message>,
+        projects: ProjectStatusMap,
+    ) -> Self {
         let (intr_tx, intr_rx) = mpsc::unbounded_channel();
         let (dep_tx, dep_rx) = mpsc::unbounded_channel();
 
         let handler = Arc::new(ProjectHandler {
             intr_tx: intr_tx.clone(),
             watch: Arc::default(),
+            projects,
         });
 
         let compiler = ProjectCompilerBase::new(
@@ -345,6 +566,7 @@ impl ProjectBackgroundWorker {
                     let mut watch_fns = self.handler.watch.lock().unwrap();
 
                     self.compiler.clear_dedicates();
+                    watch_fns.clear();
                     for (idx, (entry, watch_fn)) in view.iter().enumerate() {
                         let id = format!("project-{idx}");
 
@@ -353,7 +575,7 @@ impl ProjectBackgroundWorker {
 
                         match id {
                             Ok(id) => {
-                                watch_fns.insert(id, watch_fn.clone());
+                                watch_fns.insert(id, (entry.clone(), watch_fn.clone()));
                             }
                             Err(e) => {
                                 // todo: error handler
@@ -362,6 +584,9 @@ impl ProjectBackgroundWorker {
                         }
                     }
 
+                    // The handler looks up the watched projects.
+                    drop(watch_fns);
+
                     self.compiler
                         .handler
                         .clone()
@@ -398,6 +623,9 @@ type MayCompileOpts<'a> = Either<&'a NodeTypstDocument, CompileDocArgs>;
 #[napi]
 pub struct NodeTypstProject {
     graph: Arc<SystemWorldComputeGraph>,
+    /// The status of the project, which is only present for the watched
+    /// projects.
+    status: Option<ProjectStatusRef>,
 }
 
 // todo: merge me with NodeCompiler.
@@ -408,6 +636,16 @@ impl NodeTypstProject {
         self.graph.snap.world.clone()
     }
 
+    /// Records the result of an export to the status of the watched project.
+    fn record_export<T, E>(&self, res: &std::result::Result<T, E>) {
+        if let Some(status) = &self.status {
+            match res {
+                Ok(_) => status.record_export_ok(),
+                Err(_) => status.record_export_error(),
+            }
+        }
+    }
+
     /// Compiles the document as paged target.
     #[napi]
     pub fn compile(&mut self, opts: CompileDocArgs) -> Result<NodeTypstCompileResult, NodeError> {
@@ -623,9 +861,9 @@ impl NodeTypstProject {
         config: &T::Config,
     ) -> Result<RO, NodeError> {
         let doc = self.may_compile::<reflexo_typst::TypstPagedDocument>(opts)?;
-        T::cast_run(&doc.graph, &doc.doc, config)
-            .map_err(map_node_error)
-            .map(From::from)
+        let output = T::cast_run(&doc.graph, &doc.doc, config);
+        self.record_export(&output);
+        output.map_err(map_node_error).map(From::from)
     }
 
     /// Compiles the document as a specific type.
@@ -638,7 +876,11 @@ impl NodeTypstProject {
         config: &T::Config,
     ) -> std::result::Result<ExecResultRepr<RO>, NodeError> {
         let doc = self.may_compile2::<reflexo_typst::TypstHtmlDocument>(opts)?;
-        Ok(doc.and_then(|doc| Ok(T::cast_run(&doc.graph, &doc.doc, config)?.into())))
+        Ok(doc.and_then(|doc| {
+            let output = T::cast_run(&doc.graph, &doc.doc, config);
+            self.record_export(&output);
+            Ok(output?.into())
+        }))
     }
 
     /// Compiles the document as buffer.
@@ -723,14 +965,22 @@ impl NodeTypstProject {
     }
 
     /// Simply compiles the document as a rich-contented SVG (for browsers).
-    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs")]
+    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs, opts?: RenderSvgOpts")]
     #[cfg(feature = "svg")]
-    pub fn svg(&mut self, compiled_or_by: MayCompileOpts) -> Result<String, NodeError> {
+    pub fn svg(
+        &mut self,
+        compiled_or_by: MayCompileOpts,
+        opts: Option<crate::RenderSvgOpts>,
+    ) -> Result<String, NodeError> {
         use reflexo_typst::ExportWebSvgTask;
         use reflexo_vec2svg::DefaultExportFeature;
 
         type Export = reflexo_typst::WebSvgExport<DefaultExportFeature>;
-        self.compile_as::<Export, _>(compiled_or_by, &ExportWebSvgTask::default())
+        let task = ExportWebSvgTask {
+            options: opts.map(|opts| opts.to_options()),
+            ..ExportWebSvgTask::default()
+        };
+        self.compile_as::<Export, _>(compiled_or_by, &task)
     }
 
     // todo: when feature is disabled, it results a compile error
@@ -774,7 +1024,10 @@ pub struct ProjectInsStateExt {
 
 struct ProjectHandler {
     intr_tx: mpsc::UnboundedSender<Interrupt<SystemCompilerFeat>>,
-    watch: Arc<Mutex<FxHashMap<ProjectInsId, WatchFunction>>>,
+    /// The watched projects, paired with their entries resolved from the
+    /// watch items.
+    watch: Arc<Mutex<FxHashMap<ProjectInsId, (EntryState, WatchFunction)>>>,
+    projects: ProjectStatusMap,
 }
 
 impl CompileHandler<SystemCompilerFeat, ProjectInsStateExt> for ProjectHandler {
@@ -815,18 +1068,35 @@ impl CompileHandler<SystemCompilerFeat, ProjectInsStateExt> for ProjectHandler {
             let id = proj.id.clone();
             let intr_tx = self.intr_tx.clone();
             let watches = self.watch.clone();
+            // The status is keyed by the entry of the watch item, the same as
+            // `ProjectWatcher`.
+            let entry = self
+                .watch
+                .lock()
+                .unwrap()
+                .get(&id)
+                .map(|(entry, _)| entry.clone());
+            let project_status = entry.map(|entry| ProjectStatusRef {
+                projects: self.projects.clone(),
+                entry,
+            });
+            let started = project_status.clone();
 
             let Some(may_compile) =
                 proj.may_compile2(move |graph: &Arc<SystemWorldComputeGraph>| {
                     // todo: don't do this aggressively but we do want to update deps by that
                     let res = CompiledArtifact::from_graph(graph.clone(), true);
+                    if let Some(project_status) = &project_status {
+                        record_compilation(project_status, graph, &res);
+                    }
 
                     let watches = watches.lock().unwrap();
                     let f = watches.get(&id);
-                    if let Some(f) = f {
+                    if let Some((_, f)) = f {
                         let status: Status = f.call(
                             NodeTypstProject {
                                 graph: graph.clone(),
+                                status: project_status.clone(),
                             },
                             ThreadsafeFunctionCallMode::Blocking,
                         );
@@ -843,6 +1113,10 @@ impl CompileHandler<SystemCompilerFeat, ProjectInsStateExt> for ProjectHandler {
                 continue;
             };
 
+            // The compilation may be skipped, so it is started only if scheduled.
+            if let Some(status) = started {
+                status.start();
+            }
             proj.ext.is_compiling = true;
             rayon::spawn(move || {
                 may_compile();
@@ -854,3 +1128,28 @@ impl CompileHandler<SystemCompilerFeat, ProjectInsStateExt> for ProjectHandler {
 
     fn status(&self, _revision: usize, _rep: tinymist_project::CompileReport) {}
 }
+
+/// Records the result of a compilation to the status of the project.
+fn record_compilation(
+    status: &ProjectStatusRef,
+    graph: &Arc<SystemWorldComputeGraph>,
+    res: &CompiledArtifact<SystemCompilerFeat>,
+) {
+    use reflexo_typst::TypstWorld;
+
+    let main = graph.snap.world.main();
+    let diag = match graph.compute::<DiagnosticsTask>() {
+        Ok(diag) => Some((diag.error_cnt(), diag.warning_cnt())),
+        Err(err) => {
+            log::error!("failed to compute diagnostics: {err}");
+            None
+        }
+    };
+    let deps = res
+        .depended_files()
+        .iter()
+        .map(|id| file_path(*id))
+        .collect();
+
+    status.update(|project| project.record(main, diag, deps));
+}
@@ -136,7 +136,8 @@ impl NodeHtmlOutput {
         self.inner.html().unwrap_or_default().to_owned().into()
     }
 
-    /// Gets the [Hast] of the document.
+    /// Gets the [Hast] of the document. The nodes carry their `position`s in
+    /// the source files, if they are resolvable.
     ///
     /// [hast]: https://github.com/syntax-tree/hast
     #[napi]
@@ -202,3 +203,38 @@ pub struct RenderPdfOpts {
     /// This is used when you *enable auto timestamp* in the document.
     pub creation_timestamp: Option<i64>,
 }
+
+/// Arguments to render a SVG.
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg(feature = "svg")]
+pub struct RenderSvgOpts {
+    /// Whether to render selectable and searchable text elements. Defaults to
+    /// `true`.
+    pub text_element: Option<bool>,
+
+    /// Whether to include the builtin css. Defaults to `true`.
+    pub builtin_css: Option<bool>,
+
+    /// Whether to include the js for interactive and responsive actions.
+    /// Defaults to `false`.
+    pub responsive_js: Option<bool>,
+}
+
+#[cfg(feature = "svg")]
+impl RenderSvgOpts {
+    /// Overrides the default svg options by the given arguments.
+    pub(crate) fn to_options(&self) -> reflexo_vec2svg::SvgExportOptions {
+        let mut options = reflexo_vec2svg::SvgExportOptions::default();
+        if let Some(text_element) = self.text_element {
+            options.should_render_text_element = text_element;
+        }
+        if let Some(builtin_css) = self.builtin_css {
+            options.with_builtin_css = builtin_css;
+        }
+        if let Some(responsive_js) = self.responsive_js {
+            options.with_responsive_js = responsive_js;
+        }
+        options
+    }
+}
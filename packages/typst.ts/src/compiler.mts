@@ -188,6 +188,25 @@ interface CompileResult<T, D extends DiagnosticsFormat> {
 
 export interface TypstFontInfo { }
 
+/**
+ * The font profile measured by `typst-ts-cli font measure`.
+ */
+export interface TypstFontProfile {
+  version: string;
+  build_info: string;
+  items: TypstFontProfileItem[];
+}
+
+/**
+ * A font in the {@link TypstFontProfile}.
+ */
+export interface TypstFontProfileItem {
+  path?: string;
+  hash: string;
+  index: number;
+  info: TypstFontInfo;
+}
+
 enum TypstFontResolverCons { }
 export type TypstFontResolver = TypstFontResolverCons;
 
@@ -223,6 +242,18 @@ export interface TypstFontBuilder {
     context?: object,
   ): Promise<void>;
 
+  /**
+   * Add fonts in a profile, loading font data lazily.
+   *
+   * @param profile - The font profile, usually measured by `typst-ts-cli font measure`.
+   * @param blob - The blob function to get the font buffer, whose `this` contains
+   * the `path`, `hash` and `index` of the font.
+   */
+  addFontProfile(
+    profile: TypstFontProfile,
+    blob: (this: Pick<TypstFontProfileItem, 'path' | 'hash' | 'index'>) => Uint8Array,
+  ): Promise<void>;
+
   /**
    * Build the font resolver. The font resolver will be freed after the callback
    * is invoked and before returning the build function.
@@ -491,6 +522,12 @@ export class TypstFontBuilderDriver implements TypstFontBuilder {
   ): Promise<void> {
     return this.fontBuilder.add_lazy_font(info, blob);
   }
+  async addFontProfile(
+    profile: TypstFontProfile,
+    blob: (this: Pick<TypstFontProfileItem, 'path' | 'hash' | 'index'>) => Uint8Array,
+  ): Promise<void> {
+    return this.fontBuilder.add_font_profile(profile, blob);
+  }
   async build<T>(cb: (resolver: TypstFontResolver) => Promise<T>): Promise<T> {
     const fonts = await this.fontBuilder.build();
     const result = await cb(fonts as any as TypstFontResolver);
@@ -0,0 +1,75 @@
+//! Compiles typst sources in memory and lowers them into vector items, so
+//! that the backends are tested against documents lowered by the real pass.
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use reflexo_typst::config::{entry::EntryOpts, CompileOpts};
+use reflexo_typst::{Bytes, TypstDocument, TypstSystemUniverse};
+use reflexo_typst2vec::ir::{Module, Page, VecDocument};
+use reflexo_typst2vec::pass::Typst2VecPass;
+
+use crate::corpus_root;
+
+/// Creates a universe with the embedded fonts only.
+fn universe(workspace: PathBuf, entry: PathBuf) -> TypstSystemUniverse {
+    let verse = TypstSystemUniverse::new(CompileOpts {
+        entry: EntryOpts::new_workspace(workspace),
+        no_system_fonts: true,
+        with_embedded_fonts: typst_assets::fonts().map(Cow::Borrowed).collect(),
+        ..CompileOpts::default()
+    })
+    .unwrap();
+    verse.with_entry_file(entry)
+}
+
+/// Compiles a typst source into a paged document, with the embedded fonts
+/// only.
+pub fn compile_paged(source: &str) -> TypstDocument {
+    let verse = universe(corpus_root(), corpus_root().join("__test__.typ"));
+
+    let doc = verse
+        .snapshot_with_entry_content(Bytes::from_string(source.to_owned()), None)
+        .compile()
+        .output
+        .unwrap_or_else(|err| panic!("failed to compile the source: {err:?}"));
+    TypstDocument::Paged(doc)
+}
+
+/// Compiles a file in the corpora, e.g. `foundations/array-len`, into a paged
+/// document.
+///
+/// The workspace is the root of the repository, so that the corpora can
+/// import the shared templates, e.g. `/contrib/templates/std-tests/preset.typ`.
+pub fn compile_corpus(path: &str) -> TypstDocument {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let verse = universe(root, corpus_root().join(format!("{path}.typ")));
+
+    let doc = verse
+        .snapshot()
+        .compile()
+        .output
+        .unwrap_or_else(|err| panic!("failed to compile {path}: {err:?}"));
+    TypstDocument::Paged(doc)
+}
+
+/// Lowers a document into a vector artifact, i.e. the content of
+/// `*.artifact.sir.in`.
+pub fn lower_artifact(doc: &TypstDocument) -> Vec<u8> {
+    let pass = Typst2VecPass::default();
+    let pages = pass.doc(doc);
+    let module = pass.finalize();
+    VecDocument { module, pages }.to_bytes()
+}
+
+/// Compiles a typst source and lowers it into a module and its pages. The
+/// glyphs are prepared, so that they are accessible from the fonts.
+pub fn lower_paged(source: &str) -> (Module, Vec<Page>) {
+    let doc = compile_paged(source);
+
+    let pass = Typst2VecPass::default();
+    let pages = pass.doc(&doc);
+    let mut module = pass.finalize();
+    module.prepare_glyphs();
+    (module, pages)
+}
@@ -16,3 +16,9 @@ pub fn package_renderer_dir() -> PathBuf {
 pub mod web_artifact;
 
 pub mod std_artifact;
+
+#[cfg(feature = "raster")]
+pub mod raster;
+
+#[cfg(feature = "compile")]
+pub mod compile;
@@ -0,0 +1,397 @@
+//! Rasterized comparison of the artifact snapshots.
+//!
+//! Comparing svg or sir snapshots byte by byte breaks on harmless changes,
+//! e.g. the order of attributes or the ids of glyphs. The pages are instead
+//! rendered by a CPU rasterizer and compared pixel by pixel with a tolerance.
+
+use std::path::{Path, PathBuf};
+
+use reflexo_vec2svg::ir::{LayoutSelectorExpr, Page};
+use reflexo_vec2svg::{MultiVecDocument, SvgDataSelection, SvgExportFeature, SvgExporter};
+use resvg::{tiny_skia, usvg};
+
+use crate::artifact_dir;
+
+/// The options of the rasterized comparison.
+#[derive(Debug, Clone)]
+pub struct RasterOptions {
+    /// The pixels per point of the rendered pages.
+    pub scale: f32,
+    /// The maximum difference of a color channel between two pixels that are
+    /// considered equal, which absorbs the differences of anti-aliasing.
+    pub tolerance: u8,
+    /// The maximum ratio of different pixels in a page that still matches.
+    pub max_diff_ratio: f32,
+    /// The directory to write the diff images of mismatched pages into.
+    pub diff_dir: Option<PathBuf>,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            scale: 2.,
+            tolerance: 16,
+            max_diff_ratio: 0.0005,
+            diff_dir: Some(artifact_dir().join("raster-diff")),
+        }
+    }
+}
+
+impl RasterOptions {
+    /// Creates the options, overridden by the environment variables
+    /// `TYPST_TS_RASTER_SCALE`, `TYPST_TS_RASTER_TOLERANCE`,
+    /// `TYPST_TS_RASTER_MAX_DIFF_RATIO` and `TYPST_TS_RASTER_DIFF_DIR`.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(key: &str) -> Option<T> {
+            std::env::var(key).ok()?.parse().ok()
+        }
+
+        let mut options = Self::default();
+        if let Some(scale) = var("TYPST_TS_RASTER_SCALE") {
+            options.scale = scale;
+        }
+        if let Some(tolerance) = var("TYPST_TS_RASTER_TOLERANCE") {
+            options.tolerance = tolerance;
+        }
+        if let Some(ratio) = var("TYPST_TS_RASTER_MAX_DIFF_RATIO") {
+            options.max_diff_ratio = ratio;
+        }
+        if let Some(dir) = var::<PathBuf>("TYPST_TS_RASTER_DIFF_DIR") {
+            options.diff_dir = Some(dir);
+        }
+        options
+    }
+}
+
+/// The result of comparing a page.
+#[derive(Debug, Clone)]
+pub struct PageComparison {
+    /// The index of the page.
+    pub page: usize,
+    /// The size of the actual page, in pixels.
+    pub actual_size: (u32, u32),
+    /// The size of the expected page, in pixels.
+    pub expected_size: (u32, u32),
+    /// The number of pixels that differ beyond the tolerance.
+    pub diff_pixels: usize,
+    /// The largest difference of a color channel.
+    pub max_delta: u8,
+    /// Whether the page matches the reference.
+    pub matched: bool,
+    /// The path of the diff image, written if the page mismatches.
+    pub diff_image: Option<PathBuf>,
+}
+
+/// Renders each page of a vector artifact into a standalone svg.
+pub fn render_artifact_pages(artifact: &[u8]) -> Result<Vec<String>, String> {
+    let (doc, pages) = load_artifact(artifact)?;
+    Ok(pages
+        .iter()
+        .map(|page| render_pages(&doc, std::slice::from_ref(page)))
+        .collect())
+}
+
+/// Renders all pages of a vector artifact into an svg.
+pub fn render_artifact(artifact: &[u8]) -> Result<String, String> {
+    let (doc, pages) = load_artifact(artifact)?;
+    Ok(render_pages(&doc, &pages))
+}
+
+fn load_artifact(artifact: &[u8]) -> Result<(MultiVecDocument, Vec<Page>), String> {
+    let module =
+        reflexo_typst2vec::stream::BytesModuleStream::from_slice(artifact).try_checkout_owned()?;
+    let mut doc = MultiVecDocument::default();
+    doc.merge_delta(&module);
+    doc.module.prepare_glyphs();
+
+    let layout = doc
+        .layouts
+        .first()
+        .and_then(|layout| layout.by_selector(&LayoutSelectorExpr::Any).ok());
+    let pages = layout
+        .and_then(|layout| layout.pages_meta())
+        .ok_or("no pages in the artifact")?
+        .to_vec();
+    Ok((doc, pages))
+}
+
+fn render_pages(doc: &MultiVecDocument, pages: &[Page]) -> String {
+    let parts = SvgDataSelection {
+        body: true,
+        defs: true,
+        css: true,
+        js: false,
+    };
+    SvgExporter::<SvgExportFeature>::render_flat_svg(&doc.module, pages, Some(parts))
+}
+
+/// Rasterizes an svg onto a white background.
+pub fn rasterize_svg(svg: &str, scale: f32) -> Result<tiny_skia::Pixmap, String> {
+    let tree = usvg::Tree::from_str(svg, &usvg::Options::default()).map_err(|e| e.to_string())?;
+    let size = tree.size();
+    let width = (size.width() * scale).ceil() as u32;
+    let height = (size.height() * scale).ceil() as u32;
+
+    let mut pixmap = tiny_skia::Pixmap::new(width.max(1), height.max(1))
+        .ok_or_else(|| format!("invalid pixmap size {width}x{height}"))?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+    let ts = tiny_skia::Transform::from_scale(scale, scale);
+    resvg::render(&tree, ts, &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+/// Compares two svgs as a page.
+pub fn compare_svg(
+    name: &str,
+    page: usize,
+    actual: &str,
+    expected: &str,
+    options: &RasterOptions,
+) -> Result<PageComparison, String> {
+    let actual = rasterize_svg(actual, options.scale)?;
+    let expected = rasterize_svg(expected, options.scale)?;
+    compare_pixmaps(name, page, &actual, &expected, options)
+}
+
+/// Compares each page of two vector artifacts.
+pub fn compare_artifacts(
+    name: &str,
+    actual: &[u8],
+    expected: &[u8],
+    options: &RasterOptions,
+) -> Result<Vec<PageComparison>, String> {
+    let expected = render_artifact_pages(expected)?;
+    compare_with_pages(name, actual, &expected, options)
+}
+
+/// Compares each page of a vector artifact with the svg of the page.
+pub fn compare_with_pages(
+    name: &str,
+    actual: &[u8],
+    expected: &[String],
+    options: &RasterOptions,
+) -> Result<Vec<PageComparison>, String> {
+    let actual = render_artifact_pages(actual)?;
+    if actual.len() != expected.len() {
+        return Err(format!(
+            "{name}: page count mismatch, actual {} v.s. expected {}",
+            actual.len(),
+            expected.len()
+        ));
+    }
+
+    let pages = actual.iter().zip(expected.iter()).enumerate();
+    pages
+        .map(|(idx, (actual, expected))| compare_svg(name, idx, actual, expected, options))
+        .collect()
+}
+
+/// Compares a vector artifact with a reference snapshot, which is either an
+/// svg rendering all pages or another vector artifact.
+pub fn compare_with_reference(
+    name: &str,
+    actual: &[u8],
+    reference: &Path,
+    options: &RasterOptions,
+) -> Result<Vec<PageComparison>, String> {
+    let expected = std::fs::read(reference).map_err(|e| format!("{reference:?}: {e}"))?;
+    if reference.extension().is_some_and(|ext| ext == "svg") {
+        let expected = String::from_utf8(expected).map_err(|e| e.to_string())?;
+        let actual = render_artifact(actual)?;
+        Ok(vec![compare_svg(name, 0, &actual, &expected, options)?])
+    } else {
+        compare_artifacts(name, actual, &expected, options)
+    }
+}
+
+/// Asserts that all pages of a vector artifact match the reference snapshot.
+pub fn assert_raster_eq(name: &str, actual: &[u8], reference: &Path, options: &RasterOptions) {
+    assert_pages_matched(
+        name,
+        compare_with_reference(name, actual, reference, options),
+    );
+}
+
+fn assert_pages_matched(name: &str, pages: Result<Vec<PageComparison>, String>) {
+    let pages = pages.unwrap_or_else(|err| panic!("{name}: failed to compare: {err}"));
+
+    let mismatched = pages
+        .iter()
+        .filter(|page| !page.matched)
+        .map(|page| {
+            let diff_image = match &page.diff_image {
+                Some(path) => format!(", diff image: {}", path.display()),
+                None => String::new(),
+            };
+            format!(
+                "page {}: {} pixels differ (max delta {}), size {:?} v.s. {:?}{diff_image}",
+                page.page, page.diff_pixels, page.max_delta, page.actual_size, page.expected_size
+            )
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        mismatched.is_empty(),
+        "{name}: rasterized pages mismatch\n{}",
+        mismatched.join("\n")
+    );
+}
+
+/// Gets the reference snapshot of a std test, which is generated next to the
+/// source in the corpora, e.g. `foundations/array-len.artifact.sir.in`.
+pub fn std_reference(path: &str) -> Option<PathBuf> {
+    ["artifact.sir.in", "artifact.svg"]
+        .into_iter()
+        .map(|ext| crate::corpus_root().join(format!("{path}.{ext}")))
+        .find(|reference| reference.exists())
+}
+
+/// Compiles a std test and asserts that its pages match the reference
+/// snapshot. Without a reference snapshot, the pages are compared with the
+/// pages rendered by `typst-svg`.
+#[cfg(feature = "compile")]
+pub fn assert_std_artifact(path: &str, options: &RasterOptions) {
+    let doc = crate::compile::compile_corpus(path);
+    let artifact = crate::compile::lower_artifact(&doc);
+    if let Some(reference) = std_reference(path) {
+        assert_raster_eq(path, &artifact, &reference, options);
+    } else {
+        let reflexo_typst::TypstDocument::Paged(doc) = &doc else {
+            panic!("{path}: expected a paged document");
+        };
+        let expected = doc.pages().iter().map(typst_svg::svg).collect::<Vec<_>>();
+        assert_pages_matched(
+            path,
+            compare_with_pages(path, &artifact, &expected, options),
+        );
+    }
+}
+
+/// Compares two rasterized pages, and writes a diff image if they mismatch.
+///
+/// In the diff image, different pixels are red, and the other pixels are the
+/// faded expected page.
+pub fn compare_pixmaps(
+    name: &str,
+    page: usize,
+    actual: &tiny_skia::Pixmap,
+    expected: &tiny_skia::Pixmap,
+    options: &RasterOptions,
+) -> Result<PageComparison, String> {
+    let width = actual.width().max(expected.width());
+    let height = actual.height().max(expected.height());
+    let mut diff = tiny_skia::Pixmap::new(width, height).ok_or("invalid pixmap size")?;
+
+    let pixel = |pixmap: &tiny_skia::Pixmap, x: u32, y: u32| {
+        pixmap
+            .pixel(x, y)
+            .map(|p| [p.red(), p.green(), p.blue(), p.alpha()])
+    };
+
+    let mut diff_pixels = 0;
+    let mut max_delta = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let color = match (pixel(actual, x, y), pixel(expected, x, y)) {
+                (Some(a), Some(e)) => {
+                    let delta = (0..4).map(|i| a[i].abs_diff(e[i])).max().unwrap_or(0);
+                    max_delta = max_delta.max(delta);
+                    if delta <= options.tolerance {
+                        let luma = (e[0] as u32 * 3 + e[1] as u32 * 6 + e[2] as u32) / 10;
+                        let faded = 255 - (255 - luma as u8) / 4;
+                        [faded, faded, faded]
+                    } else {
+                        diff_pixels += 1;
+                        [255, 0, 0]
+                    }
+                }
+                _ => {
+                    diff_pixels += 1;
+                    [255, 0, 0]
+                }
+            };
+
+            let idx = (y * width + x) as usize;
+            let [r, g, b] = color;
+            diff.pixels_mut()[idx] = tiny_skia::ColorU8::from_rgba(r, g, b, 255).premultiply();
+        }
+    }
+
+    let same_size = actual.width() == expected.width() && actual.height() == expected.height();
+    let allowed = (width as f32 * height as f32 * options.max_diff_ratio) as usize;
+    let matched = same_size && diff_pixels <= allowed;
+
+    let diff_image = match &options.diff_dir {
+        Some(dir) if !matched => {
+            let path = dir.join(format!(
+                "{}-{page}.diff.png",
+                name.replace(['/', '\\'], "_")
+            ));
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            diff.save_png(&path).map_err(|e| e.to_string())?;
+            Some(path)
+        }
+        _ => None,
+    };
+
+    Ok(PageComparison {
+        page,
+        actual_size: (actual.width(), actual.height()),
+        expected_size: (expected.width(), expected.height()),
+        diff_pixels,
+        max_delta,
+        matched,
+        diff_image,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> RasterOptions {
+        RasterOptions {
+            diff_dir: None,
+            ..RasterOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_formatting_changes_match() {
+        let expected = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10"><rect x="2" y="2" width="6" height="6" fill="black"/></svg>"#;
+        let actual = r#"<svg height="10" width="20" xmlns="http://www.w3.org/2000/svg">
+  <g><rect fill="rgb(0, 0, 0)" height="6" width="6" y="2" x="2"></rect></g>
+</svg>"#;
+
+        let page = compare_svg("formatting", 0, actual, expected, &options()).unwrap();
+        assert!(page.matched, "{page:?}");
+        assert_eq!(page.diff_pixels, 0);
+    }
+
+    #[test]
+    fn test_visual_changes_mismatch() {
+        let expected = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10"><rect x="2" y="2" width="6" height="6" fill="black"/></svg>"#;
+        let actual = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10"><rect x="12" y="2" width="6" height="6" fill="black"/></svg>"#;
+
+        let page = compare_svg("visual", 0, actual, expected, &options()).unwrap();
+        assert!(!page.matched);
+        assert_eq!(page.diff_pixels, 2 * 12 * 12);
+    }
+
+    /// Compares the std tests with their reference snapshots. The tests can be
+    /// filtered by `TYPST_TS_RASTER_FILTER`, e.g. `math/`.
+    #[test]
+    #[cfg(feature = "compile")]
+    fn test_std_artifacts() {
+        let filter = std::env::var("TYPST_TS_RASTER_FILTER").unwrap_or_default();
+        let options = RasterOptions::from_env();
+
+        let tests = crate::std_artifact::STD_TEST_FILES
+            .iter()
+            .filter(|(_, path)| path.contains(filter.as_str()))
+            .collect::<Vec<_>>();
+        assert!(!tests.is_empty(), "no std test matches {filter:?}");
+        for (_, path) in tests {
+            assert_std_artifact(path, &options);
+        }
+    }
+}
@@ -1,12 +1,9 @@
 use std::path::Path;
 
 use reflexo_typst::config::{entry::EntryOpts, CompileOpts};
-use reflexo_typst::vector::{
-    ir::{Abs, Point, Rect},
-    stream::BytesModuleStream,
-};
+use reflexo_typst::vector::ir::{Abs, Point, Rect};
 use reflexo_typst::{Bytes, TypstDocument, TypstSystemUniverse};
-use reflexo_typst2vec::incr::{IncrDocClient, IncrDocServer};
+use reflexo_typst2vec::incr::{DeltaKind, IncrDocClient, IncrDocServer, IncrServerSnapshot};
 use reflexo_vec2svg::IncrSvgDocClient;
 
 fn get_driver(workspace_dir: &Path, entry_file_path: &Path) -> TypstSystemUniverse {
@@ -48,26 +45,46 @@ pub fn test_compiler(workspace_dir: &Path, entry_file_path: &Path) {
         .output
         .unwrap();
     let server_delta = incr_server.pack_delta(&TypstDocument::Paged(doc));
-    let server_delta = BytesModuleStream::from_slice(&server_delta).checkout_owned();
-    incr_client.merge_delta(server_delta);
+    let server_delta = server_delta.strip_prefix(b"diff-v1,").unwrap();
+    incr_client.merge_delta(server_delta).unwrap();
     let _ = incr_svg_client.render_in_window(&mut incr_client, window);
 
+    let mut last_delta_size = 0;
     for i in 0..20 {
         eprintln!("Iteration {i}");
 
         // content = content.replace("@netwok2020", "@netwok2020 x");
         content += "\n\nx";
 
+        // Simulates a restart of the server in the middle of the session.
+        if i == 10 {
+            let snapshot = incr_server.snapshot().to_bytes().unwrap();
+            let snapshot = IncrServerSnapshot::from_bytes(&snapshot).unwrap();
+            incr_server = IncrDocServer::restore(snapshot).unwrap();
+        }
+
         let doc = driver
             .snapshot_with_entry_content(Bytes::from_string(content.clone()), None)
             .compile()
             .output
             .unwrap();
 
-        let server_delta = incr_server.pack_delta(&TypstDocument::Paged(doc));
+        let (server_delta, stats) = incr_server.pack_delta_with_stats(&TypstDocument::Paged(doc));
         let sd = server_delta.len();
-        let server_delta = BytesModuleStream::from_slice(&server_delta).checkout_owned();
-        incr_client.merge_delta(server_delta);
+        let server_delta = server_delta.strip_prefix(b"diff-v1,").unwrap();
+        let header = incr_client.merge_delta(server_delta).unwrap();
+
+        // The restored server still knows what the client holds, so it
+        // continues to pack a small diff instead of the full document.
+        if i == 10 {
+            assert!(matches!(header.kind, DeltaKind::Diff), "{header:?}");
+            assert_eq!(stats.new_fonts, 0, "fonts are sent again after restoring");
+            assert!(
+                sd <= last_delta_size * 2,
+                "delta after restoring is too large: {sd} bytes, previous {last_delta_size} bytes"
+            );
+        }
+        last_delta_size = sd;
         incr_client.set_layout(incr_client.doc.layouts[0].unwrap_single());
         let cd = incr_svg_client.render_in_window(&mut incr_client, window);
         // std::fs::write(format!("{}.svg", i), cd.clone()).unwrap();